@@ -0,0 +1,149 @@
+use alloc::string::String;
+use alloc::vec;
+use alloc::vec::Vec;
+use core::ops::Deref;
+use core::slice;
+
+use extract::{blocks_to_text, spans_to_text, walk_blocks, PlainTextOptions};
+use html;
+use parser::Block;
+use parser::Block::Header;
+use RenderOptions;
+
+/// A parsed Markdown document
+///
+/// `Document` wraps the parsed blocks and dereferences to `[Block]`,
+/// so it can be indexed and iterated like a slice.
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct Document(Vec<Block>);
+
+impl Document {
+    /// Returns the wrapped blocks
+    pub fn into_blocks(self) -> Vec<Block> {
+        self.0
+    }
+
+    /// Renders the document to HTML using the default rendering options
+    pub fn to_html(&self) -> String {
+        self.to_html_with_options(&RenderOptions::default())
+    }
+
+    /// Renders the document to HTML using the given rendering options
+    pub fn to_html_with_options(&self, options: &RenderOptions) -> String {
+        html::to_html(&self.0, options)
+    }
+
+    /// Returns the text content of the document without any markup
+    ///
+    /// Blocks are separated by a blank line and list items by a newline,
+    /// with the items of ordered lists starting with their number.
+    /// Tables are drawn as an ASCII grid with aligned columns.
+    /// Comments, link references and horizontal rules are left out.
+    pub fn to_plain_text(&self) -> String {
+        self.to_plain_text_with_options(&PlainTextOptions::default())
+    }
+
+    /// Returns the text content of the document using the given options
+    pub fn to_plain_text_with_options(&self, options: &PlainTextOptions) -> String {
+        blocks_to_text(&self.0, options)
+    }
+
+    /// Returns the level and text of every header in document order
+    pub fn headings(&self) -> Vec<(usize, String)> {
+        let mut ret = vec![];
+        walk_blocks(&self.0, &mut |block| {
+            if let Header(ref spans, level) = *block {
+                ret.push((level, spans_to_text(spans)));
+            }
+        });
+        ret
+    }
+
+    /// Returns the number of whitespace separated words in the plain text
+    pub fn word_count(&self) -> usize {
+        self.to_plain_text().split_whitespace().count()
+    }
+}
+
+impl From<Vec<Block>> for Document {
+    fn from(blocks: Vec<Block>) -> Document {
+        Document(blocks)
+    }
+}
+
+impl Deref for Document {
+    type Target = [Block];
+
+    fn deref(&self) -> &[Block] {
+        &self.0
+    }
+}
+
+impl IntoIterator for Document {
+    type Item = Block;
+    type IntoIter = vec::IntoIter<Block>;
+
+    fn into_iter(self) -> vec::IntoIter<Block> {
+        self.0.into_iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a Document {
+    type Item = &'a Block;
+    type IntoIter = slice::Iter<'a, Block>;
+
+    fn into_iter(self) -> slice::Iter<'a, Block> {
+        self.0.iter()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Document;
+    use parser::parse;
+    use parser::Block::{Header, Hr};
+    use parser::Span::Text;
+
+    fn document(text: &str) -> Document {
+        Document::from(parse(text))
+    }
+
+    #[test]
+    fn derefs_to_blocks() {
+        let doc = document("# Title\n\n---");
+        assert_eq!(doc.len(), 2);
+        assert_eq!(doc[0], Header(vec![Text("Title".to_owned())], 1));
+        assert_eq!((&doc).into_iter().count(), 2);
+        assert_eq!(doc.into_iter().last(), Some(Hr));
+    }
+
+    #[test]
+    fn renders_html() {
+        assert_eq!(
+            document("Hello *world*").to_html(),
+            "<p>Hello <em>world</em></p>\n"
+        );
+    }
+
+    #[test]
+    fn plain_text() {
+        let doc = document("# Title\n\nSome **strong** text\n\n* one\n* two");
+        assert_eq!(doc.to_plain_text(), "Title\n\nSome strong text\n\none\ntwo");
+        assert_eq!(doc.word_count(), 6);
+    }
+
+    #[test]
+    fn headings() {
+        let doc = document("# One\n\ntext\n\n> ## Two *quoted*\n\nThree\n-----");
+        assert_eq!(
+            doc.headings(),
+            vec![
+                (1, "One".to_owned()),
+                (2, "Two quoted".to_owned()),
+                (2, "Three".to_owned())
+            ]
+        );
+        assert_eq!(document("").headings(), vec![]);
+        assert_eq!(document("").word_count(), 0);
+    }
+}
@@ -0,0 +1,555 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+use markdown_generator::list_markers;
+use parser::Block::{
+    Blockquote, CodeBlock, Comment, Details, Div, FootnoteDefinition, Header, LeadParagraph,
+    LineBlock, LinkReference, OrderedList, Paragraph, Raw, Table, UnorderedList,
+};
+use parser::{parse_inline, Alignment, Block, ListItem, ObjectSize, ParseOptions, Span};
+use regex::Regex;
+
+// calls `f` on every block in document order,
+// descending into blockquotes, details, divs and list items
+pub fn walk_blocks<'a, F: FnMut(&'a Block)>(blocks: &'a [Block], f: &mut F) {
+    for block in blocks {
+        f(block);
+        match *block {
+            Blockquote(ref blocks)
+            | Details(_, ref blocks)
+            | Div(_, ref blocks)
+            | FootnoteDefinition(_, ref blocks) => walk_blocks(blocks, f),
+            UnorderedList(ref items, _) | OrderedList(ref items, _, _) => {
+                for item in items {
+                    if let ListItem::Paragraph(ref blocks) = *item {
+                        walk_blocks(blocks, f);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+// calls `f` on every span in document order, descending into nested blocks
+// as well as into links and emphasis
+pub fn walk_spans<'a, F: FnMut(&'a Span)>(blocks: &'a [Block], f: &mut F) {
+    fn walk<'a, F: FnMut(&'a Span)>(spans: &'a [Span], f: &mut F) {
+        for span in spans {
+            f(span);
+            match *span {
+                Span::Link(ref content, _, _)
+                | Span::RefLink(ref content, _, _)
+                | Span::Object(ref content, _, _, _)
+                | Span::Emphasis(ref content)
+                | Span::Strong(ref content)
+                | Span::Highlight(ref content)
+                | Span::Strikethrough(ref content)
+                | Span::InlineFootnote(ref content) => walk(content, f),
+                _ => {}
+            }
+        }
+    }
+
+    walk_blocks(blocks, &mut |block| match *block {
+        Header(ref spans, _)
+        | Paragraph(ref spans)
+        | LeadParagraph(ref spans)
+        | Details(ref spans, _) => walk(spans, f),
+        UnorderedList(ref items, _) | OrderedList(ref items, _, _) => {
+            for item in items {
+                match *item {
+                    ListItem::Simple(ref spans) | ListItem::Task(_, ref spans) => walk(spans, f),
+                    ListItem::Paragraph(_) => {}
+                }
+            }
+        }
+        Table(_, ref header, ref rows) => {
+            for cell in header.iter().chain(rows.iter().flatten()) {
+                walk(cell, f);
+            }
+        }
+        LineBlock(ref lines) => {
+            for line in lines {
+                walk(line, f);
+            }
+        }
+        _ => {}
+    });
+}
+
+/// An image referenced by a document, see `extract_images`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ImageRef<'a> {
+    /// The url of the image, exactly as it's written in the document
+    pub url: &'a str,
+    /// The alternative text of the image, exactly as it's written in the
+    /// document, i.e. including markup like `**bold**`
+    pub alt: &'a str,
+    /// The title of the image, if it has one
+    pub title: Option<&'a str>,
+    /// The size of the image given as `=WIDTHxHEIGHT`, if it has one
+    pub size: Option<ObjectSize>,
+}
+
+/// Returns every image in document order
+///
+/// Images nested in links, emphasis, list items, tables and any other
+/// block are included as well.
+pub fn extract_images(blocks: &[Block]) -> Vec<ImageRef<'_>> {
+    let mut ret = vec![];
+    walk_spans(blocks, &mut |span| {
+        if let Span::Image(ref alt, ref url, ref title, size) = *span {
+            ret.push(ImageRef {
+                url,
+                alt,
+                title: title.as_ref().map(|title| title.as_str()),
+                size,
+            });
+        }
+    });
+    ret
+}
+
+/// Returns the language and content of every code block in document order
+///
+/// Code blocks nested in blockquotes and lists are included as well.
+/// Code blocks without a language have `None` as their language.
+pub fn code_blocks(blocks: &[Block]) -> Vec<(Option<&str>, &str)> {
+    let mut ret = vec![];
+    walk_blocks(blocks, &mut |block| {
+        if let CodeBlock(ref info, ref content) = *block {
+            let lang = info.as_ref().and_then(|info| info_string_language(info));
+            ret.push((lang, content.as_str()));
+        }
+    });
+    ret
+}
+
+/// Returns whether a document has no visible content
+///
+/// That's the case when it has no blocks at all, or only link references,
+/// HTML comments and paragraphs made up of nothing but whitespace, so that
+/// rendering empty input can be skipped.
+pub fn is_empty(blocks: &[Block]) -> bool {
+    blocks.iter().all(|block| match *block {
+        LinkReference(..) | Comment(_) => true,
+        Paragraph(ref spans) => spans.iter().all(|span| match *span {
+            Span::Text(ref text) => text.trim().is_empty(),
+            Span::Break => true,
+            _ => false,
+        }),
+        _ => false,
+    })
+}
+
+// the first word of a fenced code block's info string is its language,
+// unless it's already an attribute or an attribute block
+pub fn info_string_language(info: &str) -> Option<&str> {
+    info.split_whitespace()
+        .next()
+        .filter(|word| !word.contains('=') && !word.starts_with('{'))
+}
+
+// reads the classes and attributes of a pandoc-style attribute block like
+// `{#main .numberLines .wrap startFrom="5"}` in the info string of a fenced
+// code block, in the order they're written. An identifier like `#main` is
+// read as the attribute `id="main"`.
+pub fn info_string_classes(info: &str) -> (Vec<&str>, Vec<(&str, &str)>) {
+    lazy_static! {
+        static ref BLOCK: Regex = Regex::new(r"\{(?P<attributes>[^}]*)\}").unwrap();
+        static ref TOKEN: Regex = Regex::new(
+            r#"\.(?P<class>[\w-]+)|\#(?P<id>[\w-]+)|(?P<name>[\w-]+)=(?:"(?P<quoted>[^"]*)"|(?P<bare>\S+))"#
+        )
+        .unwrap();
+    }
+
+    let mut classes = vec![];
+    let mut attributes = vec![];
+    let block = match BLOCK.captures(info) {
+        Some(caps) => caps.name("attributes").unwrap().as_str(),
+        None => return (classes, attributes),
+    };
+    for caps in TOKEN.captures_iter(block) {
+        if let Some(class) = caps.name("class") {
+            classes.push(class.as_str());
+        } else if let Some(id) = caps.name("id") {
+            attributes.push(("id", id.as_str()));
+        } else {
+            let value = caps.name("quoted").or_else(|| caps.name("bare")).unwrap();
+            attributes.push((caps.name("name").unwrap().as_str(), value.as_str()));
+        }
+    }
+    (classes, attributes)
+}
+
+// finds an attribute like `caption="Listing 1"` or `caption=listing`
+// in the info string of a fenced code block
+pub fn info_string_attribute<'a>(info: &'a str, name: &str) -> Option<&'a str> {
+    lazy_static! {
+        static ref ATTRIBUTE: Regex =
+            Regex::new(r#"(?:^|\s)(?P<name>[\w-]+)=(?:"(?P<quoted>[^"]*)"|(?P<bare>\S+))"#)
+                .unwrap();
+    }
+
+    ATTRIBUTE
+        .captures_iter(info)
+        .find(|caps| &caps["name"] == name)
+        .and_then(|caps| caps.name("quoted").or_else(|| caps.name("bare")))
+        .map(|value| value.as_str())
+}
+
+/// Returns the plain text of the given spans, without any markup
+///
+/// The text of emphasis, links and other nested spans is kept, as well as
+/// the content of code spans, keyboard input and the alt text of images.
+/// Line breaks become newlines, while HTML, comments and footnotes are left out.
+pub fn spans_to_text(spans: &[Span]) -> String {
+    let mut ret = String::new();
+    for span in spans {
+        match *span {
+            Span::Break => ret.push('\n'),
+            Span::Text(ref text) | Span::Code(ref text) | Span::Kbd(ref text) => ret.push_str(text),
+            Span::Image(ref alt, _, _, _) => ret.push_str(&alt_text(alt)),
+            Span::Literal(c) => ret.push(c),
+            Span::Link(ref content, _, _)
+            | Span::RefLink(ref content, _, _)
+            | Span::Object(ref content, _, _, _)
+            | Span::Emphasis(ref content)
+            | Span::Strong(ref content)
+            | Span::Highlight(ref content)
+            | Span::Strikethrough(ref content) => ret.push_str(&spans_to_text(content)),
+            Span::Comment(_)
+            | Span::Html(_)
+            | Span::FootnoteReference(_)
+            | Span::InlineFootnote(_) => {}
+        }
+    }
+    ret
+}
+
+// The alt text of an image is stored as it's written, this strips its markup,
+// e.g. `**bold** [link](url)` becomes `bold link`
+pub fn alt_text(alt: &str) -> String {
+    spans_to_text(&parse_inline(alt, &ParseOptions::default()))
+}
+
+/// Options that control how a document is rendered to plain text
+#[derive(Debug, Default, PartialEq, Eq, Clone)]
+pub struct PlainTextOptions {
+    /// Number the items of ordered lists sequentially, e.g. `1.`, `2.`, `3.`
+    ///
+    /// Otherwise the items keep the numbers they were written with in the
+    /// source, like with `MarkdownOptions::renumber_ordered_lists`.
+    pub renumber_ordered_lists: bool,
+}
+
+impl PlainTextOptions {
+    /// Starts building a set of options from the defaults
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> PlainTextOptionsBuilder {
+        PlainTextOptionsBuilder::default()
+    }
+}
+
+/// A builder for `PlainTextOptions`, created with `PlainTextOptions::new()`
+#[derive(Debug, Default, Clone)]
+pub struct PlainTextOptionsBuilder {
+    options: PlainTextOptions,
+}
+
+impl PlainTextOptionsBuilder {
+    /// Sets whether the items of ordered lists are numbered sequentially
+    pub fn renumber_ordered_lists(mut self, renumber_ordered_lists: bool) -> Self {
+        self.options.renumber_ordered_lists = renumber_ordered_lists;
+        self
+    }
+
+    /// Returns the options
+    pub fn build(self) -> PlainTextOptions {
+        self.options
+    }
+}
+
+// strips all markup from the given blocks, separating blocks by a blank line
+pub fn blocks_to_text(blocks: &[Block], options: &PlainTextOptions) -> String {
+    let mut parts = vec![];
+    for block in blocks {
+        match *block {
+            Header(ref spans, _) | Paragraph(ref spans) | LeadParagraph(ref spans) => {
+                parts.push(spans_to_text(spans))
+            }
+            Blockquote(ref blocks) | Div(_, ref blocks) | FootnoteDefinition(_, ref blocks) => {
+                parts.push(blocks_to_text(blocks, options))
+            }
+            Details(ref summary, ref blocks) => {
+                parts.push(spans_to_text(summary));
+                parts.push(blocks_to_text(blocks, options));
+            }
+            CodeBlock(_, ref content) | Raw(ref content) => parts.push(content.clone()),
+            UnorderedList(ref items, _) => parts.push(items_to_text(items, options)),
+            OrderedList(ref items, ref num_type, ref numbers) => {
+                let markers = list_markers(num_type, numbers, options.renumber_ordered_lists);
+                let items: Vec<String> = items
+                    .iter()
+                    .zip(markers)
+                    .map(|(item, marker)| format!("{} {}", marker, item_to_text(item, options)))
+                    .collect();
+                parts.push(items.join("\n"));
+            }
+            Table(ref alignments, ref header, ref rows) => {
+                parts.push(table_to_text(alignments, header, rows))
+            }
+            LineBlock(ref lines) => {
+                let lines: Vec<String> = lines.iter().map(|line| spans_to_text(line)).collect();
+                parts.push(lines.join("\n"));
+            }
+            _ => {}
+        }
+    }
+    parts.retain(|part| !part.is_empty());
+    parts.join("\n\n")
+}
+
+fn items_to_text(items: &[ListItem], options: &PlainTextOptions) -> String {
+    let items: Vec<String> = items
+        .iter()
+        .map(|item| item_to_text(item, options))
+        .collect();
+    items.join("\n")
+}
+
+fn item_to_text(item: &ListItem, options: &PlainTextOptions) -> String {
+    match *item {
+        ListItem::Simple(ref spans) | ListItem::Task(_, ref spans) => spans_to_text(spans),
+        ListItem::Paragraph(ref blocks) => blocks_to_text(blocks, options),
+    }
+}
+
+// Renders a table as an ASCII grid, padding every cell to the width of the
+// widest cell in its column and aligning it according to the delimiter row:
+//
+// +------+-------+
+// | Name | Count |
+// +======+=======+
+// | a    |    10 |
+// +------+-------+
+pub fn table_to_text(
+    alignments: &[Alignment],
+    header: &[Vec<Span>],
+    rows: &[Vec<Vec<Span>>],
+) -> String {
+    let header: Vec<String> = header.iter().map(|cell| spans_to_text(cell)).collect();
+    let rows: Vec<Vec<String>> = rows
+        .iter()
+        .map(|row| row.iter().map(|cell| spans_to_text(cell)).collect())
+        .collect();
+
+    let columns = rows.iter().map(Vec::len).fold(header.len(), usize::max);
+    let mut widths = vec![0; columns];
+    for row in Some(&header).into_iter().chain(&rows) {
+        for (i, cell) in row.iter().enumerate() {
+            widths[i] = widths[i].max(cell.chars().count());
+        }
+    }
+
+    let separator = |fill: &str| {
+        let cells: Vec<String> = widths.iter().map(|width| fill.repeat(width + 2)).collect();
+        format!("+{}+", cells.join("+"))
+    };
+    let format_row = |row: &[String]| {
+        let cells: Vec<String> = widths
+            .iter()
+            .enumerate()
+            .map(|(i, &width)| {
+                let cell = row.get(i).map_or("", |cell| cell.as_str());
+                let padding = width - cell.chars().count();
+                let left = match alignments.get(i) {
+                    Some(Alignment::Right) => padding,
+                    Some(Alignment::Center) => padding / 2,
+                    _ => 0,
+                };
+                format!(
+                    " {}{}{} ",
+                    " ".repeat(left),
+                    cell,
+                    " ".repeat(padding - left)
+                )
+            })
+            .collect();
+        format!("|{}|", cells.join("|"))
+    };
+
+    // the header is only set apart from the body if there is one
+    let mut lines = vec![separator("-"), format_row(&header)];
+    if !rows.is_empty() {
+        lines.push(separator("="));
+        lines.extend(rows.iter().map(|row| format_row(row)));
+    }
+    lines.push(separator("-"));
+    lines.join("\n")
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        blocks_to_text, code_blocks, extract_images, info_string_attribute, info_string_classes,
+        info_string_language, is_empty, spans_to_text, ImageRef, PlainTextOptions,
+    };
+    use parser::parse;
+    use parser::Block::Paragraph;
+    use parser::{Dimension, ObjectSize};
+
+    #[test]
+    fn finds_code_blocks() {
+        let blocks = parse("```rust\nfn main() {}\n```\n\n* a list\n\n    ```\n    nested\n    ```\n\n> ~~~sh\n> ls\n> ~~~");
+        assert_eq!(
+            code_blocks(&blocks),
+            vec![
+                (Some("rust"), "fn main() {}"),
+                (None, "nested"),
+                (Some("sh"), "ls")
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_indented_code_blocks() {
+        assert_eq!(
+            code_blocks(&parse("text\n\n    code")),
+            vec![(None, "code")]
+        );
+        assert_eq!(code_blocks(&parse("text")), vec![]);
+    }
+
+    #[test]
+    fn reads_info_strings() {
+        let info = "rust caption=\"Listing 1\" id=main";
+        assert_eq!(info_string_language(info), Some("rust"));
+        assert_eq!(info_string_attribute(info, "caption"), Some("Listing 1"));
+        assert_eq!(info_string_attribute(info, "id"), Some("main"));
+        assert_eq!(info_string_attribute(info, "title"), None);
+        assert_eq!(info_string_language("caption=x"), None);
+        assert_eq!(info_string_language(""), None);
+        assert_eq!(info_string_language("{.rust}"), None);
+    }
+
+    #[test]
+    fn reads_info_string_classes() {
+        let info = "python {.numberLines .wrap startFrom=\"5\" theme=dark}";
+        assert_eq!(info_string_language(info), Some("python"));
+        assert_eq!(
+            info_string_classes(info),
+            (
+                vec!["numberLines", "wrap"],
+                vec![("startFrom", "5"), ("theme", "dark")]
+            )
+        );
+        assert_eq!(info_string_classes("python"), (vec![], vec![]));
+        assert_eq!(
+            info_string_classes("{.a #main k=v}"),
+            (vec!["a"], vec![("id", "main"), ("k", "v")])
+        );
+    }
+
+    #[test]
+    fn finds_images() {
+        let blocks = parse(
+            "# ![logo](logo.svg)\n\n\
+             [![badge](badge.png \"Build\")](https://ci.example.com) and *![a](a.png =10x20)*\n\n\
+             * item ![b](b.png)\n\n\
+             > | x |\n> |---|\n> | ![c](c.png) |",
+        );
+        let image = |url, alt| ImageRef {
+            url,
+            alt,
+            title: None,
+            size: None,
+        };
+        assert_eq!(
+            extract_images(&blocks),
+            vec![
+                image("logo.svg", "logo"),
+                ImageRef {
+                    title: Some("Build"),
+                    ..image("badge.png", "badge")
+                },
+                ImageRef {
+                    size: Some(ObjectSize {
+                        width: Some(Dimension::Pixels(10)),
+                        height: Some(Dimension::Pixels(20))
+                    }),
+                    ..image("a.png", "a")
+                },
+                image("b.png", "b"),
+                image("c.png", "c"),
+            ]
+        );
+    }
+
+    #[test]
+    fn strips_markup() {
+        let blocks = parse("# *Hello* [world](http://example.com)\n\n[a]: http://a.com\n\n* one `two`\n* ![three](a.png)\n\n---\n\n> quoted<!-- hidden -->");
+        assert_eq!(
+            blocks_to_text(&blocks, &PlainTextOptions::default()),
+            "Hello world\n\none two\nthree\n\nquoted"
+        );
+    }
+
+    #[test]
+    fn numbers_ordered_lists() {
+        let blocks = parse("1. one\n1. two\n1. three\n\n* four");
+        assert_eq!(
+            blocks_to_text(&blocks, &PlainTextOptions::default()),
+            "1. one\n1. two\n1. three\n\nfour"
+        );
+        let options = PlainTextOptions::new().renumber_ordered_lists(true).build();
+        assert_eq!(
+            blocks_to_text(&blocks, &options),
+            "1. one\n2. two\n3. three\n\nfour"
+        );
+
+        let blocks = parse("3. three\n7. seven");
+        assert_eq!(
+            blocks_to_text(&blocks, &PlainTextOptions::default()),
+            "3. three\n7. seven"
+        );
+    }
+
+    #[test]
+    fn flattens_spans() {
+        let spans = match parse("Some *nested **[link `code`](a.com)** and* ![alt](a.png)\\!").pop()
+        {
+            Some(Paragraph(spans)) => spans,
+            _ => unreachable!(),
+        };
+        assert_eq!(spans_to_text(&spans), "Some nested link code and alt!");
+    }
+
+    #[test]
+    fn aligns_table_columns() {
+        let blocks = parse(
+            "| Name | Qty | Note |\n|:-----|----:|:----:|\n| apple | 3 | ok |\n| fig | 120 | *ripe* |",
+        );
+        assert_eq!(
+            blocks_to_text(&blocks, &PlainTextOptions::default()),
+            "+-------+-----+------+\n\
+             | Name  | Qty | Note |\n\
+             +=======+=====+======+\n\
+             | apple |   3 |  ok  |\n\
+             | fig   | 120 | ripe |\n\
+             +-------+-----+------+"
+        );
+    }
+
+    #[test]
+    fn finds_empty_documents() {
+        assert!(is_empty(&parse("")));
+        assert!(is_empty(&parse("  \n\t\n\n   ")));
+        assert!(is_empty(&parse(
+            "[a]: https://example.com\n\n[b]: /b \"Title\"\n"
+        )));
+        assert!(is_empty(&parse("<!-- nothing to see -->")));
+        assert!(!is_empty(&parse("[a]: https://example.com\n\ntext")));
+        assert!(!is_empty(&parse("---")));
+    }
+}
@@ -1,13 +1,596 @@
+use alloc::borrow::ToOwned;
+use alloc::collections::BTreeMap;
+use alloc::string::{String, ToString};
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::cell::RefCell;
+use core::fmt;
+use core::slice;
+use extract::{
+    alt_text, info_string_attribute, info_string_classes, info_string_language, spans_to_text,
+    walk_blocks,
+};
 use parser::Block;
 use parser::Block::{
-    Blockquote, CodeBlock, Header, Hr, LinkReference, OrderedList, Paragraph, Raw, UnorderedList,
+    Blockquote, CodeBlock, Comment, Details, Div, FootnoteDefinition, Header, Hr, LeadParagraph,
+    LineBlock, LinkReference, OrderedList, Paragraph, Raw, Table, UnorderedList,
+};
+use parser::ObjectSize;
+use parser::Span::{
+    Break, Code, Emphasis, FootnoteReference, Highlight, Image, InlineFootnote, Link, Literal,
+    Object, RefLink, Strikethrough, Strong, Text,
 };
-use parser::Span::{Break, Code, Emphasis, Image, Link, Literal, RefLink, Strong, Text};
-use parser::{ListItem, OrderedListType, Span};
-use regex::Regex;
-use std::collections::HashMap;
+use parser::{emoji_regex, is_comment_text, Alignment, ListItem, OrderedListType, Span, TaskState};
+use regex::{Captures, Regex};
+
+type LinkReferenceMap<'a> = BTreeMap<&'a str, (&'a str, &'a Option<String>)>;
+
+// everything defined somewhere in the document that is needed while rendering it
+struct References<'a> {
+    links: LinkReferenceMap<'a>,
+    footnotes: Footnotes<'a>,
+    // the header ids handed out so far, see `unique_slug`
+    slugs: RefCell<BTreeMap<String, usize>>,
+}
+
+// Footnotes are numbered in the order they are first referenced in.
+// Every reference gets its own id, so that the definition can link back to it.
+#[derive(Default)]
+struct Footnotes<'a> {
+    definitions: BTreeMap<&'a str, &'a [Block]>,
+    // whether the footnotes are listed at the end of the output, which they
+    // aren't for spans rendered on their own
+    listed: bool,
+    // The content of every inline footnote rendered so far. Inline footnotes
+    // are numbered as they are rendered, since the content of headers and
+    // admonitions can be rendered from a recased or rearranged copy.
+    inline: RefCell<Vec<Vec<Span>>>,
+    // all referenced footnotes, a footnote's number is its position + 1
+    order: RefCell<Vec<Footnote<'a>>>,
+    // the number of references to each footnote rendered so far
+    reference_counts: RefCell<BTreeMap<&'a str, usize>>,
+}
+
+// a footnote defined with an id, or the index of an inline footnote in `Footnotes::inline`
+#[derive(Clone, Copy, PartialEq)]
+enum Footnote<'a> {
+    Defined(&'a str),
+    Inline(usize),
+}
+
+/// Options that control how the HTML output is rendered
+///
+/// The default options produce the same output as `to_html`.
+///
+/// Whatever the options, every tag and attribute name the renderer emits is
+/// lowercase and attributes are always written in the same order, e.g. `src`,
+/// `title`, `alt`, `width`, `height` for images, so that the output can be used
+/// in snapshot tests. Raw HTML blocks and inline HTML tags from the document
+/// are escaped, so they show up as text, and URLs that can run scripts are
+/// left out, unless `unsafe_html` is set. HTML comments are passed through,
+/// except for ones whose text could end them early, like `<!-- a --!> b -->`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct RenderOptions {
+    /// Leave HTML comments (`<!-- ... -->`) out of the output instead of passing them through
+    pub strip_comments: bool,
+    /// Resolve relative link and image URLs against this base URL
+    ///
+    /// Relative URLs are resolved like browsers do, following RFC 3986: with
+    /// `https://example.com/docs/index.html` as the base, `a.png` becomes
+    /// `https://example.com/docs/a.png` and `../a.png` becomes
+    /// `https://example.com/a.png`. Note that a base without a trailing slash
+    /// like `https://example.com/docs` ends in a file name. URLs with a scheme
+    /// (`https:`, `mailto:`, ...), as well as URLs starting with `/` or `#` are
+    /// left untouched.
+    pub base_url: Option<String>,
+    /// How to render reference-style links whose reference isn't defined
+    pub reflink_fallback: RefLinkFallback,
+    /// The markup emitted for hard line breaks
+    pub hard_break: HardBreak,
+    /// An element to wrap the whole output in, e.g. `<article class="markdown-body">`
+    pub container: Option<Container>,
+    /// Wrap every header and the content following it in a `<section>`
+    ///
+    /// A section is closed by the next header of the same or a higher level,
+    /// so the sections of lower level headers are nested in their parent's.
+    /// Only headers at the top level of the document open a section.
+    pub sections: bool,
+    /// Make the sections of headers of this level and deeper collapsible, by
+    /// wrapping their content in `<details open>` with the header as `<summary>`
+    ///
+    /// E.g. `Some(2)` leaves the sections of `h1` headers as they are. This
+    /// only has an effect together with `sections`.
+    pub collapsible_sections: Option<usize>,
+    /// Change the case of the text in headers, see `HeadingCase`
+    pub heading_case: HeadingCase,
+    /// Render an image with a title that makes up a whole paragraph as a
+    /// `<figure>`, with the title as its `<figcaption>`
+    ///
+    /// Images within text and images without a title are always rendered as
+    /// a plain `<img>`, with the title in its `title` attribute.
+    pub images_as_figures: bool,
+    /// Turn straight quotes (`"`, `'`) into curly ones, e.g. `“quoted”` and `it’s`
+    ///
+    /// Like the other typographic replacements, this only affects text, code
+    /// spans, code blocks and raw HTML are left as they are.
+    pub smart_quotes: bool,
+    /// Turn `--` into an en dash (`–`) and `---` into an em dash (`—`)
+    pub smart_dashes: bool,
+    /// Turn `...` into an ellipsis (`…`)
+    pub smart_ellipses: bool,
+    /// Give footnote anchors a legacy `name` attribute next to their `id`
+    ///
+    /// The first footnote has the id `fn1` and the references to it `fnref1`,
+    /// `fnref1-2`, ... With this option the id of a footnote moves from its
+    /// `<li>` to an empty `<a id="fn1" name="fn1">` at its start.
+    pub footnote_names: bool,
+    /// Give blockquotes and admonitions class names for styling
+    ///
+    /// Blockquotes are rendered as `<blockquote class="blockquote">`. A
+    /// blockquote whose first line is a GitHub-style alert marker (`[!NOTE]`,
+    /// `[!TIP]`, `[!IMPORTANT]`, `[!WARNING]` or `[!CAUTION]`) is rendered as
+    /// an admonition instead, e.g. `<blockquote class="admonition admonition-note">`
+    /// followed by `<p class="admonition-title">Note</p>`. Without this option
+    /// the marker is rendered as text.
+    pub block_classes: bool,
+    /// How the ids of headers are derived from their text, see `SlugStyle`
+    pub slug_style: SlugStyle,
+    /// Number the lines of code blocks, starting at the given number
+    ///
+    /// Every line, including blank ones, is wrapped in a
+    /// `<span class="line" data-line="1">`, which can be numbered with CSS.
+    pub code_line_numbers: Option<usize>,
+    /// The element paragraphs are wrapped in, `p` by default
+    ///
+    /// With `None` the content of paragraphs is emitted without a wrapper,
+    /// e.g. to render a single line label. Only tag names made up of ASCII
+    /// letters and digits are used, paragraphs are wrapped in `p` otherwise.
+    pub paragraph_tag: Option<String>,
+    /// Leave out the whitespace between block elements, e.g. the newlines
+    /// between `</p>` and `<p>`, to get compact HTML
+    ///
+    /// Whitespace within text and inside of `<pre>` elements is kept, as
+    /// well as whitespace within the raw HTML and comments passed through.
+    pub minify: bool,
+    /// Escape the tags GitHub Flavored Markdown disallows (`<script>`,
+    /// `<style>`, `<iframe>`, `<textarea>`, ...) in the raw HTML blocks and
+    /// inline HTML `unsafe_html` passes through, other tags are left intact
+    ///
+    /// Only the `<` opening a disallowed tag is escaped, like GFM's tagfilter
+    /// extension does. This is much lighter than sanitizing the HTML.
+    pub gfm_tagfilter: bool,
+    /// Render emoji as images from this URL template instead of as unicode
+    /// characters, e.g. `https://cdn.example.com/twemoji/{code}.svg`
+    ///
+    /// `{code}` is replaced with the codepoints of the emoji in lowercase hex,
+    /// joined by `-` and without the variation selector `fe0f`, like the
+    /// file names of Twemoji. The images are rendered as
+    /// `<img src="..." alt="😄" class="emoji" />`. Only the emoji known as
+    /// shortcodes are rendered as images, wherever they appear in text.
+    pub emoji_url: Option<String>,
+    /// Leave out a first and a last line of code blocks made up of spaces and
+    /// tabs only, e.g. one added for readability
+    ///
+    /// At most one line is removed at either end. The parser already drops
+    /// empty lines at the start and end of code blocks, so on parsed input
+    /// this only affects lines with whitespace in them. Blank lines between
+    /// the lines of code and whitespace on the other lines are kept.
+    pub trim_code_blank_lines: bool,
+    /// Pass raw HTML and every URL through as they are
+    ///
+    /// By default raw HTML blocks and inline HTML tags like `<span>` are
+    /// escaped, whatever the other options, and the URLs of links, images
+    /// and objects that can run scripts are left out, i.e. `javascript:`,
+    /// `vbscript:` and `data:` URLs other than PNG, GIF, JPEG and WebP images.
+    /// Only use this for trusted input. Disallowed tags are still escaped
+    /// with `gfm_tagfilter`.
+    pub unsafe_html: bool,
+}
+
+impl Default for RenderOptions {
+    fn default() -> RenderOptions {
+        RenderOptions {
+            strip_comments: false,
+            base_url: None,
+            reflink_fallback: RefLinkFallback::default(),
+            hard_break: HardBreak::default(),
+            container: None,
+            sections: false,
+            collapsible_sections: None,
+            heading_case: HeadingCase::default(),
+            images_as_figures: false,
+            smart_quotes: false,
+            smart_dashes: false,
+            smart_ellipses: false,
+            footnote_names: false,
+            block_classes: false,
+            slug_style: SlugStyle::default(),
+            code_line_numbers: None,
+            paragraph_tag: Some("p".to_owned()),
+            minify: false,
+            gfm_tagfilter: false,
+            emoji_url: None,
+            trim_code_blank_lines: false,
+            unsafe_html: false,
+        }
+    }
+}
+
+/// The ways a reference-style link without a matching definition can be rendered
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum RefLinkFallback {
+    /// Render the original markdown, e.g. `[text][id]`
+    #[default]
+    Raw,
+    /// Render only the link text, e.g. `text`
+    Label,
+}
+
+/// The ways the text of headers can be recased
+///
+/// Only plain, emphasized, strong and highlighted text is changed, inline
+/// code, links and images are left as they are. So are words with capitals
+/// after their first letter, like `API` or `iPhone`. The ids of the headers
+/// are always derived from the original text.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum HeadingCase {
+    /// Keep the text as it is written
+    #[default]
+    Unchanged,
+    /// Capitalize every word, except for articles, conjunctions and short
+    /// prepositions (`a`, `the`, `and`, `of`, ...) that aren't the first word
+    Title,
+    /// Capitalize only the first word and lowercase the rest
+    Sentence,
+}
+
+/// The ways the `id` of a header can be derived from its text
+///
+/// Whatever the style, a header whose id is already taken gets a suffix of
+/// `-1`, `-2`, ... like on GitHub.
+#[derive(Debug, Default, PartialEq, Clone)]
+pub enum SlugStyle {
+    /// Like GitHub: lowercase the text, remove everything but letters,
+    /// digits, spaces, `-` and `_`, then turn every space into a `-`,
+    /// e.g. `What's new in 2.0?` becomes `whats-new-in-20`
+    #[default]
+    GitHub,
+    /// Lowercase the text and join the runs of letters and digits with single
+    /// dashes, e.g. `What's new in 2.0?` becomes `what-s-new-in-2-0`
+    Kebab,
+    /// Lowercase the text and join its parts with underscores, keeping all
+    /// other characters, e.g. `What's new in 2.0?` becomes `what's_new_in_2.0?`
+    ///
+    /// This is how the ids of earlier versions were derived.
+    Underscore,
+    /// Derive the id from the plain text of the header with a function
+    Custom(SlugFn),
+}
+
+/// A function deriving a header id from the header's plain text, see `SlugStyle`
+#[derive(Clone)]
+pub struct SlugFn(Arc<dyn Fn(&str) -> String + Send + Sync>);
+
+impl SlugFn {
+    /// Wraps a function as a slug function
+    pub fn new<F>(slug: F) -> SlugFn
+    where
+        F: Fn(&str) -> String + Send + Sync + 'static,
+    {
+        SlugFn(Arc::new(slug))
+    }
+}
+
+impl fmt::Debug for SlugFn {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SlugFn")
+    }
+}
+
+// functions are only equal if they are the same function
+impl PartialEq for SlugFn {
+    fn eq(&self, other: &SlugFn) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// The markup a hard line break (`Span::Break`) can be rendered as
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum HardBreak {
+    /// `<br />`
+    #[default]
+    SelfClosingBr,
+    /// `<br>`
+    Br,
+    /// A plain newline character
+    Newline,
+}
+
+impl HardBreak {
+    fn to_str(self) -> &'static str {
+        match self {
+            HardBreak::SelfClosingBr => "<br />",
+            HardBreak::Br => "<br>",
+            HardBreak::Newline => "\n",
+        }
+    }
+}
+
+/// An element wrapping the rendered output, see `RenderOptions::container`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Container {
+    /// The tag name of the element, e.g. `article`, it's emitted in lowercase
+    pub tag: String,
+    /// The value of the `class` attribute
+    pub class: Option<String>,
+    /// The value of the `id` attribute
+    pub id: Option<String>,
+}
+
+impl Container {
+    /// Creates a container element with the given tag name and no attributes
+    pub fn new(tag: &str) -> Container {
+        Container {
+            tag: tag.to_owned(),
+            class: None,
+            id: None,
+        }
+    }
+
+    /// Sets the `class` attribute of the element
+    pub fn class(mut self, class: &str) -> Self {
+        self.class = Some(class.to_owned());
+        self
+    }
+
+    /// Sets the `id` attribute of the element
+    pub fn id(mut self, id: &str) -> Self {
+        self.id = Some(id.to_owned());
+        self
+    }
+
+    fn wrap(&self, html: &str, options: &RenderOptions) -> String {
+        let mut attributes = String::new();
+        if let Some(ref id) = self.id {
+            attributes.push_str(&format!(" id=\"{}\"", escape(id, true)));
+        }
+        if let Some(ref class) = self.class {
+            attributes.push_str(&format!(" class=\"{}\"", escape(class, true)));
+        }
+        format!(
+            "<{0}{1}>{nl}{2}</{0}>{nl}",
+            self.tag.to_lowercase(),
+            attributes,
+            html,
+            nl = newline(options)
+        )
+    }
+}
+
+impl RenderOptions {
+    /// Starts building a set of options from the defaults
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> RenderOptionsBuilder {
+        RenderOptionsBuilder::default()
+    }
+}
+
+/// A builder for `RenderOptions`, created with `RenderOptions::new()`
+#[derive(Debug, Default, Clone)]
+pub struct RenderOptionsBuilder {
+    options: RenderOptions,
+}
+
+impl RenderOptionsBuilder {
+    /// Sets whether HTML comments are left out of the output
+    pub fn strip_comments(mut self, strip_comments: bool) -> Self {
+        self.options.strip_comments = strip_comments;
+        self
+    }
+
+    /// Sets the base URL that relative URLs are resolved against
+    pub fn base_url(mut self, base_url: &str) -> Self {
+        self.options.base_url = Some(base_url.to_owned());
+        self
+    }
+
+    /// Sets how reference-style links without a definition are rendered
+    pub fn reflink_fallback(mut self, reflink_fallback: RefLinkFallback) -> Self {
+        self.options.reflink_fallback = reflink_fallback;
+        self
+    }
 
-type LinkReferenceMap<'a> = HashMap<&'a str, (&'a str, &'a Option<String>)>;
+    /// Sets the markup emitted for hard line breaks
+    pub fn hard_break(mut self, hard_break: HardBreak) -> Self {
+        self.options.hard_break = hard_break;
+        self
+    }
+
+    /// Sets an element to wrap the whole output in
+    pub fn container(mut self, container: Container) -> Self {
+        self.options.container = Some(container);
+        self
+    }
+
+    /// Sets whether headers and their content are wrapped in `<section>` elements
+    pub fn sections(mut self, sections: bool) -> Self {
+        self.options.sections = sections;
+        self
+    }
+
+    /// Sets the level from which on the sections of headers are collapsible
+    pub fn collapsible_sections(mut self, level: usize) -> Self {
+        self.options.collapsible_sections = Some(level);
+        self
+    }
+
+    /// Sets how the text of headers is recased
+    pub fn heading_case(mut self, heading_case: HeadingCase) -> Self {
+        self.options.heading_case = heading_case;
+        self
+    }
+
+    /// Sets whether images with a title standing on their own become figures
+    pub fn images_as_figures(mut self, images_as_figures: bool) -> Self {
+        self.options.images_as_figures = images_as_figures;
+        self
+    }
+
+    /// Sets whether straight quotes are turned into curly ones
+    pub fn smart_quotes(mut self, smart_quotes: bool) -> Self {
+        self.options.smart_quotes = smart_quotes;
+        self
+    }
+
+    /// Sets whether `--` and `---` are turned into en and em dashes
+    pub fn smart_dashes(mut self, smart_dashes: bool) -> Self {
+        self.options.smart_dashes = smart_dashes;
+        self
+    }
+
+    /// Sets whether `...` is turned into an ellipsis
+    pub fn smart_ellipses(mut self, smart_ellipses: bool) -> Self {
+        self.options.smart_ellipses = smart_ellipses;
+        self
+    }
+
+    /// Sets whether footnote anchors get a `name` attribute next to their `id`
+    pub fn footnote_names(mut self, footnote_names: bool) -> Self {
+        self.options.footnote_names = footnote_names;
+        self
+    }
+
+    /// Sets whether blockquotes and admonitions get class names
+    pub fn block_classes(mut self, block_classes: bool) -> Self {
+        self.options.block_classes = block_classes;
+        self
+    }
+
+    /// Sets how the ids of headers are derived from their text
+    pub fn slug_style(mut self, slug_style: SlugStyle) -> Self {
+        self.options.slug_style = slug_style;
+        self
+    }
+
+    /// Sets the number the lines of code blocks are numbered from
+    pub fn code_line_numbers(mut self, start: usize) -> Self {
+        self.options.code_line_numbers = Some(start);
+        self
+    }
+
+    /// Sets the element paragraphs are wrapped in, `None` leaves them unwrapped
+    pub fn paragraph_tag(mut self, tag: Option<&str>) -> Self {
+        self.options.paragraph_tag = tag.map(str::to_owned);
+        self
+    }
+
+    /// Sets whether the whitespace between block elements is left out
+    pub fn minify(mut self, minify: bool) -> Self {
+        self.options.minify = minify;
+        self
+    }
+
+    /// Sets whether the tags GFM disallows are escaped in raw HTML
+    pub fn gfm_tagfilter(mut self, gfm_tagfilter: bool) -> Self {
+        self.options.gfm_tagfilter = gfm_tagfilter;
+        self
+    }
+
+    /// Sets the URL template emoji are rendered as images with
+    pub fn emoji_url(mut self, template: &str) -> Self {
+        self.options.emoji_url = Some(template.to_owned());
+        self
+    }
+
+    /// Sets whether a whitespace-only first and last line of code blocks is left out
+    pub fn trim_code_blank_lines(mut self, trim_code_blank_lines: bool) -> Self {
+        self.options.trim_code_blank_lines = trim_code_blank_lines;
+        self
+    }
+
+    /// Sets whether raw HTML and all URLs are passed through as they are
+    pub fn unsafe_html(mut self, unsafe_html: bool) -> Self {
+        self.options.unsafe_html = unsafe_html;
+        self
+    }
+
+    /// Returns the finished options
+    pub fn build(self) -> RenderOptions {
+        self.options
+    }
+}
+
+// words that aren't capitalized in title case, unless they start the header
+const TITLE_CASE_SMALL_WORDS: &[&str] = &[
+    "a", "an", "and", "as", "at", "but", "by", "for", "in", "nor", "of", "on", "or", "per", "so",
+    "the", "to", "via", "vs", "yet",
+];
+
+// Recases the text of a header, `first` tracks whether its first word is still ahead.
+// Words are split at whitespace, so a word split up by markup like `**Hel**lo`
+// is treated as two words.
+fn recase_spans(elements: &[Span], case: HeadingCase, first: &mut bool) -> Vec<Span> {
+    elements
+        .iter()
+        .map(|element| match *element {
+            Text(ref text) => Text(recase_text(text, case, first)),
+            Emphasis(ref content) => Emphasis(recase_spans(content, case, first)),
+            Strong(ref content) => Strong(recase_spans(content, case, first)),
+            Highlight(ref content) => Highlight(recase_spans(content, case, first)),
+            Strikethrough(ref content) => Strikethrough(recase_spans(content, case, first)),
+            Break | Span::Comment(_) | Span::Html(_) | FootnoteReference(_) | InlineFootnote(_) => {
+                element.clone()
+            }
+            _ => {
+                *first = false;
+                element.clone()
+            }
+        })
+        .collect()
+}
+
+fn recase_text(text: &str, case: HeadingCase, first: &mut bool) -> String {
+    let mut ret = String::with_capacity(text.len());
+    let mut rest = text;
+    while !rest.is_empty() {
+        let end = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        let (word, after) = rest.split_at(end);
+        let space = after
+            .find(|c: char| !c.is_whitespace())
+            .unwrap_or(after.len());
+        ret.push_str(&recase_word(word, case, *first));
+        ret.push_str(&after[..space]);
+        if !word.is_empty() {
+            *first = false;
+        }
+        rest = &after[space..];
+    }
+    ret
+}
+
+fn recase_word(word: &str, case: HeadingCase, first: bool) -> String {
+    let mut chars = word.chars();
+    let initial = match chars.next() {
+        Some(initial) => initial,
+        None => return String::new(),
+    };
+    if chars.as_str().chars().any(char::is_uppercase) {
+        return word.to_owned();
+    }
+
+    let capitalize = match case {
+        HeadingCase::Unchanged => return word.to_owned(),
+        HeadingCase::Title => {
+            first || !TITLE_CASE_SMALL_WORDS.contains(&word.to_lowercase().as_str())
+        }
+        HeadingCase::Sentence => first,
+    };
+    if capitalize {
+        initial.to_uppercase().chain(chars).collect()
+    } else {
+        word.to_lowercase()
+    }
+}
 
 // takes a number of elements and returns their collective text as a slug
 fn slugify(elements: &[Span], no_spaces: bool) -> String {
@@ -15,13 +598,20 @@ fn slugify(elements: &[Span], no_spaces: bool) -> String {
 
     for el in elements {
         let next = match *el {
-            Break => "".to_owned(),
+            // footnotes aren't part of the text, so they don't add a separator either
+            FootnoteReference(_) | InlineFootnote(_) => continue,
+            Break | Span::Comment(_) | Span::Html(_) => "".to_owned(),
             Literal(character) => character.to_string(),
-            Text(ref text) | Image(ref text, _, _) | Code(ref text) => text.trim().to_lowercase(),
+            Text(ref text) | Image(ref text, _, _, _) | Code(ref text) | Span::Kbd(ref text) => {
+                text.trim().to_lowercase()
+            }
             RefLink(ref content, _, _)
             | Link(ref content, _, _)
+            | Object(ref content, _, _, _)
             | Strong(ref content)
-            | Emphasis(ref content) => slugify(content, no_spaces),
+            | Emphasis(ref content)
+            | Highlight(ref content)
+            | Strikethrough(ref content) => slugify(content, no_spaces),
         };
         if !ret.is_empty() {
             ret.push('_');
@@ -36,206 +626,2553 @@ fn slugify(elements: &[Span], no_spaces: bool) -> String {
     ret
 }
 
-pub fn to_html(blocks: &[Block]) -> String {
+// derives the id of a header, which is unique within the rendered document
+fn header_id(elements: &[Span], references: &References, options: &RenderOptions) -> String {
+    // the text is taken from the header as it's written, without the
+    // whitespace in front of a footnote at its end
+    let text = spans_to_text(elements);
+    let text = text.trim();
+    let slug = match options.slug_style {
+        SlugStyle::GitHub => text
+            .to_lowercase()
+            .chars()
+            .filter(|&c| c.is_alphanumeric() || c == ' ' || c == '\n' || c == '-' || c == '_')
+            // the lines of a header spanning several lines are words of their own
+            .map(|c| if c == ' ' || c == '\n' { '-' } else { c })
+            .collect(),
+        SlugStyle::Kebab => text
+            .to_lowercase()
+            .split(|c: char| !c.is_alphanumeric())
+            .filter(|part| !part.is_empty())
+            .collect::<Vec<_>>()
+            .join("-"),
+        SlugStyle::Underscore => slugify(elements, true),
+        SlugStyle::Custom(ref slug) => (slug.0)(text),
+    };
+    unique_slug(slug, &mut references.slugs.borrow_mut())
+}
+
+// the first `a` stays `a`, the following ones become `a-1`, `a-2`, ...
+// skipping ids that are taken already, e.g. by a header `a-1`
+fn unique_slug(slug: String, slugs: &mut BTreeMap<String, usize>) -> String {
+    let mut unique = slug.clone();
+    while slugs.contains_key(&unique) {
+        let count = slugs.get_mut(&slug).unwrap();
+        *count += 1;
+        unique = format!("{}-{}", slug, count);
+    }
+    slugs.insert(unique.clone(), 0);
+    unique
+}
+
+pub fn to_html(blocks: &[Block], options: &RenderOptions) -> String {
+    let mut references = References {
+        links: BTreeMap::new(),
+        footnotes: Footnotes {
+            listed: true,
+            ..Footnotes::default()
+        },
+        slugs: RefCell::default(),
+    };
+    collect_references(blocks, &mut references);
+    let mut html = if options.sections {
+        format_sections(blocks, &references, options)
+    } else {
+        format_blocks(blocks, &references, options)
+    };
+    html.push_str(&format_footnotes(&references, options));
+    match options.container {
+        Some(ref container) => container.wrap(&html, options),
+        None => html,
+    }
+}
+
+// The whitespace the renderer puts between block elements, which `minify`
+// leaves out. Whitespace within text, code and raw HTML is never touched.
+fn newline(options: &RenderOptions) -> &'static str {
+    if options.minify {
+        ""
+    } else {
+        "\n"
+    }
+}
+
+/// Returns the rendered content, the slug and the level of every header in document order
+///
+/// The content is the inline HTML of the header, e.g. `<strong>Bold</strong> text`,
+/// while the slug is derived from its plain text and matches the `id` the header
+/// gets in `to_html`. Together they can be used to build a navigation menu.
+pub fn rendered_headings(blocks: &[Block]) -> Vec<(String, String, usize)> {
+    rendered_headings_with_options(blocks, &RenderOptions::default())
+}
+
+/// Like `rendered_headings`, with the slugs matching the ids the headers get
+/// when rendered with `options`
+pub fn rendered_headings_with_options(
+    blocks: &[Block],
+    options: &RenderOptions,
+) -> Vec<(String, String, usize)> {
+    let mut references = References {
+        links: BTreeMap::new(),
+        footnotes: Footnotes {
+            listed: true,
+            ..Footnotes::default()
+        },
+        slugs: RefCell::default(),
+    };
+    collect_references(blocks, &mut references);
+
+    let mut ret = vec![];
+    walk_blocks(blocks, &mut |block| {
+        if let Header(ref elements, level) = *block {
+            ret.push((
+                format_spans(elements, &references, options),
+                header_id(elements, &references, options),
+                level,
+            ));
+        }
+    });
+    ret
+}
+
+// link references and footnotes can be defined anywhere in the document,
+// including inside of blockquotes and list items
+fn collect_references<'a>(blocks: &'a [Block], references: &mut References<'a>) {
+    walk_blocks(blocks, &mut |block| match *block {
+        LinkReference(ref id, ref text, ref title) => {
+            references.links.entry(id).or_insert((text, title));
+        }
+        FootnoteDefinition(ref id, ref blocks) => {
+            references.footnotes.definitions.entry(id).or_insert(blocks);
+        }
+        _ => {}
+    });
+}
+
+fn format_blocks(blocks: &[Block], references: &References, options: &RenderOptions) -> String {
     let mut ret = String::new();
-    let mut link_references: LinkReferenceMap = HashMap::new();
     for block in blocks.iter() {
-        match block {
-            LinkReference(ref id, ref text, ref title) => {
-                link_references.insert(id, (text, title));
-            }
-            _ => {}
-        };
+        ret.push_str(&format_block(block, references, options))
+    }
+    ret = ret.trim().to_owned();
+    ret.push_str(newline(options));
+    ret
+}
+
+// Like `format_blocks`, but every header opens a section that lasts until the
+// next header of the same or a higher level, e.g. h1, h2, h2, h1 turns into
+// <section>h1 <section>h2</section> <section>h2</section></section> <section>h1</section>
+fn format_sections(blocks: &[Block], references: &References, options: &RenderOptions) -> String {
+    fn close_section(html: &mut String, collapsible: bool, nl: &str) {
+        let len = html.trim_end().len();
+        html.truncate(len);
+        if collapsible {
+            html.push_str(nl);
+            html.push_str("</details>");
+        }
+        html.push_str(&format!("{nl}</section>{nl}{nl}", nl = nl));
     }
+
+    let mut ret = String::new();
+    // the levels of the headers whose sections are still open, and whether
+    // they are collapsible
+    let mut open: Vec<(usize, bool)> = vec![];
     for block in blocks.iter() {
-        let next = match block {
-            Header(ref elements, level) => format_header(elements, *level, &link_references),
-            Paragraph(ref elements) => format_paragraph(elements, &link_references),
-            Blockquote(ref elements) => format_blockquote(elements),
-            CodeBlock(ref lang, ref elements) => format_codeblock(lang, elements),
-            UnorderedList(ref elements) => format_unordered_list(elements, &link_references),
-            OrderedList(ref elements, ref num_type) => {
-                format_ordered_list(elements, num_type, &link_references)
-            }
-            LinkReference(_, _, _) => "".to_owned(),
-            Raw(ref elements) => elements.to_owned(),
-            Hr => format!("<hr />\n\n"),
-        };
-        ret.push_str(&next)
+        if let Header(_, level) = *block {
+            while open.last().is_some_and(|&(open, _)| open >= level) {
+                close_section(&mut ret, open.pop().unwrap().1, newline(options));
+            }
+            ret.push_str("<section>");
+            ret.push_str(newline(options));
+            let collapsible = options
+                .collapsible_sections
+                .is_some_and(|collapsible| level >= collapsible);
+            open.push((level, collapsible));
+            if collapsible {
+                let header = format_block(block, references, options);
+                ret.push_str(&format!(
+                    "<details open>{nl}<summary>{}</summary>{nl}{nl}",
+                    header.trim_end(),
+                    nl = newline(options)
+                ));
+                continue;
+            }
+        }
+        ret.push_str(&format_block(block, references, options));
+    }
+    for (_, collapsible) in open.into_iter().rev() {
+        close_section(&mut ret, collapsible, newline(options));
     }
     ret = ret.trim().to_owned();
-    ret.push('\n');
+    ret.push_str(newline(options));
     ret
 }
 
-fn format_spans(elements: &[Span], link_references: &LinkReferenceMap) -> String {
+fn format_block(block: &Block, references: &References, options: &RenderOptions) -> String {
+    match block {
+        Header(ref elements, level) => format_header(elements, *level, references, options),
+        Paragraph(ref elements) => format_paragraph(elements, references, options),
+        LeadParagraph(ref elements) => wrap_paragraph(
+            &format_spans(elements, references, options),
+            " class=\"lead\"",
+            options,
+        ),
+        Blockquote(ref elements) => format_blockquote(elements, references, options),
+        Details(ref summary, ref elements) => {
+            format_details(summary, elements, references, options)
+        }
+        Div(ref classes, ref elements) => format_div(classes, elements, references, options),
+        CodeBlock(ref lang, ref elements) => format_codeblock(lang, elements, options),
+        UnorderedList(ref elements, _) => format_unordered_list(elements, references, options),
+        OrderedList(ref elements, ref num_type, _) => {
+            format_ordered_list(elements, num_type, references, options)
+        }
+        // footnotes are rendered at the end of the document
+        LinkReference(_, _, _) | FootnoteDefinition(_, _) => "".to_owned(),
+        Raw(ref elements) => format_raw_html(elements, options),
+        Comment(ref text) => match format_comment(text, options) {
+            comment if comment.is_empty() => comment,
+            comment => format!("{}{nl}{nl}", comment, nl = newline(options)),
+        },
+        Hr => format!("<hr />{nl}{nl}", nl = newline(options)),
+        LineBlock(ref lines) => format_line_block(lines, references, options),
+        Table(ref alignments, ref header, ref rows) => {
+            format_table(alignments, header, rows, references, options)
+        }
+    }
+}
+
+/// Converts a single block to HTML, producing the same output as rendering a
+/// document consisting of only this block.
+///
+/// Reference-style links can only be resolved against definitions inside of
+/// `block`, so documents that rely on state across blocks should be rendered
+/// as a whole.
+pub fn render_block(block: &Block, options: &RenderOptions) -> String {
+    to_html(slice::from_ref(block), options)
+}
+
+// Converts spans to HTML on their own, without a surrounding block. There are
+// no definitions to resolve reference-style links and footnotes against.
+pub fn render_spans(spans: &[Span], options: &RenderOptions) -> String {
+    let references = References {
+        links: BTreeMap::new(),
+        footnotes: Footnotes::default(),
+        slugs: RefCell::default(),
+    };
+    format_spans(spans, &references, options)
+}
+
+fn format_spans(elements: &[Span], references: &References, options: &RenderOptions) -> String {
     let mut ret = String::new();
+    // the last character of text before the current element, for smart quotes
+    let mut prev = None;
     for element in elements.iter() {
         let next = match *element {
-            Break => format!("<br />"),
+            Break => options.hard_break.to_str().to_owned(),
             Literal(character) => character.to_string(),
-            Text(ref text) => format!("{}", &escape(text, true)),
+            Text(ref text) => {
+                let text = escape(&smarten(text, prev, options), true);
+                match options.emoji_url {
+                    Some(ref template) => format_emoji(&text, template),
+                    None => text,
+                }
+            }
             Code(ref text) => format!("<code>{}</code>", &escape(text, false)),
-            Link(ref content, ref url, None) => format!(
-                "<a href=\"{}\">{}</a>",
-                &escape(url, false),
-                format_spans(content, link_references)
-            ),
-            Link(ref content, ref url, Some(ref title)) => format!(
-                "<a href=\"{}\" title=\"{}\">{}</a>",
-                &escape(url, false),
-                &escape(title, true),
-                format_spans(content, link_references)
-            ),
+            Span::Kbd(ref keys) => format!("<kbd>{}</kbd>", &escape(keys, false)),
+            Span::Html(ref tag) => format_raw_html(tag, options),
+            Span::Comment(ref text) => format_comment(text, options),
+            Link(ref content, ref url, ref title) => {
+                format_link(content, url, title, references, options)
+            }
             RefLink(ref content, ref reference, ref raw) => {
-                if let Some((ref url, None)) = link_references.get::<str>(reference) {
-                    format!(
-                        "<a href=\"{}\">{}</a>",
-                        &escape(url, false),
-                        format_spans(content, link_references)
-                    )
-                } else if let Some((ref url, Some(ref title))) =
-                    link_references.get::<str>(reference)
+                // explicit references take precedence over the link content
+                match references
+                    .links
+                    .get::<str>(reference)
+                    .or_else(|| references.links.get::<str>(&slugify(content, false)))
                 {
-                    format!(
-                        "<a href=\"{}\" title=\"{}\">{}</a>",
-                        &escape(url, false),
-                        &escape(title, true),
-                        format_spans(content, link_references)
-                    )
-                } else if let Some((ref url, None)) =
-                    link_references.get::<str>(&slugify(content, false))
-                {
-                    format!(
-                        "<a href=\"{}\">{}</a>",
-                        &escape(url, false),
-                        format_spans(content, link_references)
-                    )
-                } else if let Some((ref url, Some(ref title))) =
-                    link_references.get::<str>(&slugify(content, false))
-                {
-                    format!(
-                        "<a href=\"{}\" title=\"{}\">{}</a>",
-                        &escape(url, false),
-                        &escape(title, true),
-                        format_spans(content, link_references)
-                    )
-                } else {
-                    raw.to_owned()
+                    Some(&(url, title)) => format_link(content, url, title, references, options),
+                    None => match options.reflink_fallback {
+                        // the raw text of a shortcut reference includes its
+                        // brackets, so `[sic]` is rendered as `[sic]`
+                        RefLinkFallback::Raw => escape(raw, true),
+                        RefLinkFallback::Label => format_spans(content, references, options),
+                    },
                 }
             }
-            Image(ref text, ref url, None) => format!(
-                "<img src=\"{}\" alt=\"{}\" />",
-                &escape(url, false),
-                &escape(text, true)
-            ),
-            Image(ref text, ref url, Some(ref title)) => format!(
-                "<img src=\"{}\" title=\"{}\" alt=\"{}\" />",
-                &escape(url, false),
-                &escape(title, true),
-                &escape(text, true)
-            ),
-            Emphasis(ref content) => format!("<em>{}</em>", format_spans(content, link_references)),
+            Image(ref text, ref url, ref title, ref size) => {
+                format_image(text, url, title.as_deref(), *size, options)
+            }
+            Object(ref content, ref url, ref title, size) => {
+                format_object(content, url, title, size, references, options)
+            }
+            FootnoteReference(ref id) => format_footnote_reference(id, references, options),
+            InlineFootnote(ref content) => format_inline_footnote(content, references, options),
+            Emphasis(ref content) => {
+                format!("<em>{}</em>", format_spans(content, references, options))
+            }
             Strong(ref content) => format!(
                 "<strong>{}</strong>",
-                format_spans(content, link_references)
+                format_spans(content, references, options)
             ),
+            Highlight(ref content) => {
+                format!(
+                    "<mark>{}</mark>",
+                    format_spans(content, references, options)
+                )
+            }
+            Strikethrough(ref content) => {
+                format!("<del>{}</del>", format_spans(content, references, options))
+            }
+        };
+        ret.push_str(&next);
+        prev = match *element {
+            Break => Some('\n'),
+            _ => spans_to_text(slice::from_ref(element)).chars().last(),
         };
-        ret.push_str(&next)
     }
     ret
 }
 
-fn escape(text: &str, replace_entities: bool) -> String {
-    lazy_static! {
-        static ref AMPERSAND: Regex = Regex::new(r"&amp;(?P<x>\S+;)").unwrap();
+// Applies the enabled typographic replacements to text. A quote is an opening
+// one at the start of the text or after whitespace or an opening bracket,
+// `prev` is the character in front of the text.
+fn smarten(text: &str, mut prev: Option<char>, options: &RenderOptions) -> String {
+    let mut text = text.to_owned();
+    if options.smart_dashes {
+        text = text.replace("---", "\u{2014}").replace("--", "\u{2013}");
     }
-
-    let replaced = text
-        .replace("&", "&amp;")
-        .replace("<", "&lt;")
-        .replace("\"", "&quot;")
-        .replace("'", "&#8217;")
-        .replace(">", "&gt;");
-
-    // We can't do lookarounds in the regex crate to match only ampersands with
-    // no entity; afterwards, so we do this ugly hack where we revert the replacement
-    // everywhere it wasn't desired.
-    if replace_entities {
-        return AMPERSAND.replace_all(&replaced, "&$x").into_owned();
+    if options.smart_ellipses {
+        text = text.replace("...", "\u{2026}");
+    }
+    if !options.smart_quotes {
+        return text;
     }
 
-    return replaced;
+    let mut ret = String::with_capacity(text.len());
+    for c in text.chars() {
+        let opening =
+            prev.is_none_or(|prev| prev.is_whitespace() || "([{\u{2013}\u{2014}".contains(prev));
+        ret.push(match (c, opening) {
+            ('"', true) => '\u{201C}',
+            ('"', false) => '\u{201D}',
+            ('\'', true) => '\u{2018}',
+            ('\'', false) => '\u{2019}',
+            _ => c,
+        });
+        prev = Some(c);
+    }
+    ret
 }
 
-fn format_list(
-    elements: &[ListItem],
-    start_tag: &str,
-    end_tag: &str,
-    link_references: &LinkReferenceMap,
+fn format_link(
+    content: &[Span],
+    url: &str,
+    title: &Option<String>,
+    references: &References,
+    options: &RenderOptions,
 ) -> String {
-    let mut ret = String::new();
-    for list_item in elements {
-        let mut content = String::new();
-        match *list_item {
-            ListItem::Simple(ref els) => content.push_str(&format_spans(els, link_references)),
-            ListItem::Paragraph(ref paragraphs) => {
-                content.push_str(&format!("\n{}", to_html(paragraphs)))
-            }
-        }
+    match *title {
+        None => format!(
+            "<a href=\"{}\">{}</a>",
+            &escape(&resolve_url(url, options), false),
+            format_spans(content, references, options)
+        ),
+        Some(ref title) => format!(
+            "<a href=\"{}\" title=\"{}\">{}</a>",
+            &escape(&resolve_url(url, options), false),
+            &escape(title, true),
+            format_spans(content, references, options)
+        ),
+    }
+}
 
-        ret.push_str(&format!("\n<li>{}</li>\n", content))
+// The content of an object is shown when the object can't be, e.g.
+// `<object data="movie.mp4" width="640">a video</object>`
+fn format_object(
+    content: &[Span],
+    url: &str,
+    title: &Option<String>,
+    size: ObjectSize,
+    references: &References,
+    options: &RenderOptions,
+) -> String {
+    let mut attributes = format!("data=\"{}\"", escape(&resolve_url(url, options), false));
+    if let Some(ref title) = *title {
+        attributes.push_str(&format!(" title=\"{}\"", escape(title, true)));
     }
-    format!("<{}>{}</{}>\n\n", start_tag, ret, end_tag)
+    attributes.push_str(&format_size(size));
+    format!(
+        "<object {}>{}</object>",
+        attributes,
+        format_spans(content, references, options)
+    )
 }
 
-fn format_unordered_list(elements: &[ListItem], link_references: &LinkReferenceMap) -> String {
-    format_list(elements, "ul", "ul", link_references)
+fn format_footnote_reference(id: &str, references: &References, options: &RenderOptions) -> String {
+    let footnotes = &references.footnotes;
+    let (&id, _) = match footnotes.definitions.get_key_value(id) {
+        Some(definition) => definition,
+        None => return escape(&format!("[^{}]", id), true),
+    };
+
+    let number = footnote_number(Footnote::Defined(id), footnotes);
+    let mut reference_counts = footnotes.reference_counts.borrow_mut();
+    let count = reference_counts.entry(id).or_insert(0);
+    *count += 1;
+
+    format_footnote_anchor(number, *count, options)
 }
 
-fn format_ordered_list(
-    elements: &[ListItem],
-    num_type: &OrderedListType,
-    link_references: &LinkReferenceMap,
+// an inline footnote is numbered like any other footnote and referenced only once
+fn format_inline_footnote(
+    content: &[Span],
+    references: &References,
+    options: &RenderOptions,
 ) -> String {
-    if num_type != &OrderedListType::Numeric {
-        format_list(
-            elements,
-            &format!("ol type=\"{}\"", num_type.to_str()),
-            "ol",
-            link_references,
-        )
-    } else {
-        format_list(elements, "ol", "ol", link_references)
+    let footnotes = &references.footnotes;
+    if !footnotes.listed {
+        return format!("^[{}]", format_spans(content, references, options));
     }
+    let index = {
+        let mut inline = footnotes.inline.borrow_mut();
+        inline.push(content.to_vec());
+        inline.len() - 1
+    };
+
+    let number = footnote_number(Footnote::Inline(index), footnotes);
+    format_footnote_anchor(number, 1, options)
+}
+
+// the superscript number linking the `count`th reference of a footnote to it
+fn format_footnote_anchor(number: usize, count: usize, options: &RenderOptions) -> String {
+    format!(
+        "<sup class=\"footnote-ref\"><a href=\"#fn{}\"{}>{}</a></sup>",
+        number,
+        footnote_anchor_attributes(&footnote_reference_id(number, count), options),
+        number
+    )
 }
 
-fn format_codeblock(lang: &Option<String>, elements: &str) -> String {
-    if lang.is_none() || (lang.is_some() && lang.as_ref().unwrap().is_empty()) {
-        format!("<pre><code>{}</code></pre>\n\n", &escape(elements, false))
+// the `id` of a footnote anchor, along with a `name` if `footnote_names` is set
+fn footnote_anchor_attributes(id: &str, options: &RenderOptions) -> String {
+    if options.footnote_names {
+        format!(" id=\"{0}\" name=\"{0}\"", id)
     } else {
-        format!(
-            "<pre><code class=\"language-{}\">{}</code></pre>\n\n",
-            &escape(lang.as_ref().unwrap(), false),
-            &escape(elements, false)
-        )
+        format!(" id=\"{}\"", id)
     }
 }
 
-fn format_blockquote(elements: &[Block]) -> String {
-    format!("<blockquote>\n{}</blockquote>\n\n", to_html(elements))
+// returns the number of a footnote, numbering it if it's referenced for the first time
+fn footnote_number<'a>(footnote: Footnote<'a>, footnotes: &Footnotes<'a>) -> usize {
+    let mut order = footnotes.order.borrow_mut();
+    match order.iter().position(|&other| other == footnote) {
+        Some(position) => position + 1,
+        None => {
+            order.push(footnote);
+            order.len()
+        }
+    }
 }
 
-fn format_paragraph(elements: &[Span], link_references: &LinkReferenceMap) -> String {
-    format!("<p>{}</p>\n\n", format_spans(elements, link_references))
+// the first reference to footnote 1 is `fnref1`, the second one `fnref1-2`
+fn footnote_reference_id(number: usize, count: usize) -> String {
+    if count == 1 {
+        format!("fnref{}", number)
+    } else {
+        format!("fnref{}-{}", number, count)
+    }
 }
 
-fn format_header(elements: &[Span], level: usize, link_references: &LinkReferenceMap) -> String {
+// Renders the referenced footnotes as a list, where each footnote links back
+// to all of its references.
+fn format_footnotes(references: &References, options: &RenderOptions) -> String {
+    let footnotes = &references.footnotes;
+    let mut items = String::new();
+    // footnotes can reference other footnotes, which adds them to the end of the list
+    let mut number = 0;
+    while number < footnotes.order.borrow().len() {
+        let footnote = footnotes.order.borrow()[number];
+        number += 1;
+
+        let (content, count) = match footnote {
+            Footnote::Defined(id) => (
+                format_blocks(footnotes.definitions[id], references, options),
+                footnotes.reference_counts.borrow()[id],
+            ),
+            Footnote::Inline(index) => {
+                // rendering the footnote can add more inline footnotes
+                let content = footnotes.inline.borrow()[index].clone();
+                (format_paragraph(&content, references, options), 1)
+            }
+        };
+        let backrefs = (1..=count)
+            .map(|i| {
+                format!(
+                    "<a href=\"#{}\" class=\"footnote-backref\">↩{}</a>",
+                    footnote_reference_id(number, i),
+                    if i == 1 {
+                        "".to_owned()
+                    } else {
+                        format!("<sup>{}</sup>", i)
+                    }
+                )
+            })
+            .collect::<Vec<_>>()
+            .join(" ");
+
+        // the back-references go at the end of the last paragraph, if there is one
+        let content = match content.trim_end().strip_suffix("</p>") {
+            Some(content) => format!("{} {}</p>{}", content, backrefs, newline(options)),
+            None => format!("{}<p>{}</p>{}", content, backrefs, newline(options)),
+        };
+        // `<li>` can't have a `name`, so it goes on an anchor at the start of the item
+        let id = format!("fn{}", number);
+        if options.footnote_names {
+            items.push_str(&format!(
+                "<li>{nl}<a{}></a>{nl}{}</li>{nl}",
+                footnote_anchor_attributes(&id, options),
+                content,
+                nl = newline(options)
+            ));
+        } else {
+            items.push_str(&format!(
+                "<li id=\"{}\">{nl}{}</li>{nl}",
+                id,
+                content,
+                nl = newline(options)
+            ));
+        }
+    }
+
+    if items.is_empty() {
+        return "".to_owned();
+    }
     format!(
-        "<h{} id='{}'>{}</h{}>\n\n",
-        level,
-        slugify(elements, true),
-        format_spans(elements, link_references),
-        level
+        "{nl}<section class=\"footnotes\">{nl}<ol>{nl}{}</ol>{nl}</section>{nl}",
+        items,
+        nl = newline(options)
     )
 }
+
+fn resolve_url(url: &str, options: &RenderOptions) -> String {
+    lazy_static! {
+        static ref SCHEME: Regex = Regex::new(r"^[a-zA-Z][a-zA-Z0-9+.-]*:").unwrap();
+    }
+
+    if !options.unsafe_html && is_dangerous_url(url) {
+        return String::new();
+    }
+
+    match options.base_url {
+        Some(ref base_url)
+            if !url.is_empty()
+                && !url.starts_with('/')
+                && !url.starts_with('#')
+                && !SCHEME.is_match(url) =>
+        {
+            resolve_reference(base_url, url)
+        }
+        _ => url.to_owned(),
+    }
+}
+
+// the components of a URL, split up like in appendix B of RFC 3986
+struct UrlParts<'a> {
+    scheme: Option<&'a str>,
+    authority: Option<&'a str>,
+    path: &'a str,
+    query: Option<&'a str>,
+    fragment: Option<&'a str>,
+}
+
+fn split_url(url: &str) -> UrlParts<'_> {
+    lazy_static! {
+        static ref URL: Regex = Regex::new(
+            r"(?s)^(?:(?P<scheme>[^:/?#]+):)?(?://(?P<authority>[^/?#]*))?(?P<path>[^?#]*)(?:\?(?P<query>[^#]*))?(?:#(?P<fragment>.*))?$"
+        )
+        .unwrap();
+    }
+
+    // every part of the pattern is optional, so it matches any URL
+    let caps = URL.captures(url).unwrap();
+    UrlParts {
+        scheme: caps.name("scheme").map(|m| m.as_str()),
+        authority: caps.name("authority").map(|m| m.as_str()),
+        path: caps.name("path").map_or("", |m| m.as_str()),
+        query: caps.name("query").map(|m| m.as_str()),
+        fragment: caps.name("fragment").map(|m| m.as_str()),
+    }
+}
+
+// Resolves a reference without a scheme against a base URL like a browser
+// does, following section 5.2 of RFC 3986. The base doesn't need a scheme or
+// a host either, e.g. `/docs/` resolves `a.png` to `/docs/a.png`.
+fn resolve_reference(base: &str, reference: &str) -> String {
+    let base = split_url(base);
+    let reference = split_url(reference);
+    let (authority, path, query) = if reference.authority.is_some() {
+        (
+            reference.authority,
+            remove_dot_segments(reference.path),
+            reference.query,
+        )
+    } else if reference.path.is_empty() {
+        (
+            base.authority,
+            base.path.to_owned(),
+            reference.query.or(base.query),
+        )
+    } else if reference.path.starts_with('/') {
+        (
+            base.authority,
+            remove_dot_segments(reference.path),
+            reference.query,
+        )
+    } else {
+        (
+            base.authority,
+            remove_dot_segments(&merge_paths(&base, reference.path)),
+            reference.query,
+        )
+    };
+
+    let mut ret = String::new();
+    if let Some(scheme) = base.scheme {
+        ret.push_str(scheme);
+        ret.push(':');
+    }
+    if let Some(authority) = authority {
+        ret.push_str("//");
+        ret.push_str(authority);
+    }
+    ret.push_str(&path);
+    if let Some(query) = query {
+        ret.push('?');
+        ret.push_str(query);
+    }
+    if let Some(fragment) = reference.fragment {
+        ret.push('#');
+        ret.push_str(fragment);
+    }
+    ret
+}
+
+// a relative path replaces the last segment of the base path, so a base
+// without a trailing slash like `/docs/index.html` ends in a file name
+fn merge_paths(base: &UrlParts, path: &str) -> String {
+    if base.authority.is_some() && base.path.is_empty() {
+        return format!("/{}", path);
+    }
+    match base.path.rfind('/') {
+        Some(end) => format!("{}{}", &base.path[..=end], path),
+        None => path.to_owned(),
+    }
+}
+
+// removes the `.` and `..` segments of a path, see section 5.2.4 of RFC 3986
+fn remove_dot_segments(path: &str) -> String {
+    let mut input = path;
+    // every segment along with the `/` in front of it
+    let mut output: Vec<&str> = vec![];
+    while !input.is_empty() {
+        if let Some(rest) = input
+            .strip_prefix("../")
+            .or_else(|| input.strip_prefix("./"))
+        {
+            input = rest;
+        } else if input.starts_with("/./") || input == "/." {
+            input = if input == "/." { "/" } else { &input[2..] };
+        } else if input.starts_with("/../") || input == "/.." {
+            input = if input == "/.." { "/" } else { &input[3..] };
+            output.pop();
+        } else if input == "." || input == ".." {
+            input = "";
+        } else {
+            let start = usize::from(input.starts_with('/'));
+            let end = input[start..]
+                .find('/')
+                .map_or(input.len(), |end| end + start);
+            output.push(&input[..end]);
+            input = &input[end..];
+        }
+    }
+    output.concat()
+}
+
+// URLs that run scripts when followed, like CommonMark's reference
+// implementation only images are allowed as `data:` URLs. Browsers ignore
+// whitespace and control characters in the scheme, so they are as well.
+fn is_dangerous_url(url: &str) -> bool {
+    lazy_static! {
+        static ref DANGEROUS: Regex = Regex::new(r"(?i)^(?:javascript|vbscript|data):").unwrap();
+        static ref IMAGE_DATA: Regex =
+            Regex::new(r"(?i)^data:image/(?:png|gif|jpeg|webp)[;,]").unwrap();
+    }
+
+    let url: String = url
+        .chars()
+        .filter(|c| !c.is_whitespace() && !c.is_control())
+        .collect();
+    DANGEROUS.is_match(&url) && !IMAGE_DATA.is_match(&url)
+}
+
+// renders raw HTML blocks and inline HTML tags
+fn format_raw_html(html: &str, options: &RenderOptions) -> String {
+    if !options.unsafe_html {
+        escape(html, true)
+    } else if options.gfm_tagfilter {
+        filter_tags(html)
+    } else {
+        html.to_owned()
+    }
+}
+
+// Comments are passed through, unless their text could end them early, like
+// the `--!>` in `<!-- a --!><img> -->`. The parser never produces those, but
+// blocks can be built by hand, so they're escaped unless `unsafe_html` is set.
+fn format_comment(text: &str, options: &RenderOptions) -> String {
+    let comment = format!("<!--{}-->", text);
+    if options.strip_comments {
+        "".to_owned()
+    } else if options.unsafe_html || is_comment_text(text) {
+        comment
+    } else {
+        escape(&comment, true)
+    }
+}
+
+// renders the emoji in escaped text as images from the URL template
+fn format_emoji(text: &str, template: &str) -> String {
+    emoji_regex()
+        .replace_all(text, |caps: &Captures| {
+            let code: Vec<String> = caps[0]
+                .chars()
+                .filter(|&c| c != '\u{fe0f}')
+                .map(|c| format!("{:x}", c as u32))
+                .collect();
+            let url = template.replace("{code}", &code.join("-"));
+            format!(
+                "<img src=\"{}\" alt=\"{}\" class=\"emoji\" />",
+                escape(&url, false),
+                &caps[0]
+            )
+        })
+        .into_owned()
+}
+
+// escapes the `<` of the tags filtered by GFM's tagfilter extension,
+// which are compared case-insensitively
+fn filter_tags(html: &str) -> String {
+    lazy_static! {
+        static ref DISALLOWED: Regex = Regex::new(
+            r"(?i)<(?P<tag>/?(?:title|textarea|style|xmp|iframe|noembed|noframes|script|plaintext))(?P<end>[\s/>]|$)"
+        )
+        .unwrap();
+    }
+
+    DISALLOWED.replace_all(html, "&lt;$tag$end").into_owned()
+}
+
+fn escape(text: &str, replace_entities: bool) -> String {
+    lazy_static! {
+        // Matches an escaped ampersand that starts a named (`&copy;`), decimal (`&#169;`)
+        // or hexadecimal (`&#xA9;`) character reference.
+        static ref AMPERSAND: Regex = Regex::new(
+            r"&amp;(?P<x>[a-zA-Z][a-zA-Z0-9]{1,31};|#[0-9]{1,7};|#[xX][0-9a-fA-F]{1,6};)"
+        )
+        .unwrap();
+    }
+
+    let replaced = text
+        .replace("&", "&amp;")
+        .replace("<", "&lt;")
+        .replace("\"", "&quot;")
+        .replace("'", "&#8217;")
+        .replace(">", "&gt;");
+
+    // We can't do lookarounds in the regex crate to match only ampersands with
+    // no entity; afterwards, so we do this ugly hack where we revert the replacement
+    // everywhere it wasn't desired.
+    if replace_entities {
+        return AMPERSAND.replace_all(&replaced, "&$x").into_owned();
+    }
+
+    replaced
+}
+
+fn format_list(
+    elements: &[ListItem],
+    start_tag: &str,
+    end_tag: &str,
+    references: &References,
+    options: &RenderOptions,
+) -> String {
+    let mut ret = String::new();
+    for list_item in elements {
+        let mut content = String::new();
+        match *list_item {
+            ListItem::Simple(ref els) => content.push_str(&format_spans(els, references, options)),
+            ListItem::Paragraph(ref paragraphs) => content.push_str(&format!(
+                "{}{}",
+                newline(options),
+                format_blocks(paragraphs, references, options)
+            )),
+            ListItem::Task(ref state, ref els) => content.push_str(&format!(
+                "{} {}",
+                format_task_checkbox(state),
+                format_spans(els, references, options)
+            )),
+        }
+
+        ret.push_str(&format!(
+            "{nl}<li>{}</li>{nl}",
+            content,
+            nl = newline(options)
+        ))
+    }
+    format!(
+        "<{}>{}</{}>{nl}{nl}",
+        start_tag,
+        ret,
+        end_tag,
+        nl = newline(options)
+    )
+}
+
+// HTML checkboxes have no attribute for the indeterminate state,
+// so it's exposed as a data attribute for scripts and styles instead
+fn format_task_checkbox(state: &TaskState) -> &'static str {
+    match *state {
+        TaskState::Unchecked => "<input type=\"checkbox\" disabled=\"\" />",
+        TaskState::Checked => "<input type=\"checkbox\" disabled=\"\" checked=\"\" />",
+        TaskState::Indeterminate => {
+            "<input type=\"checkbox\" disabled=\"\" data-indeterminate=\"\" />"
+        }
+    }
+}
+
+fn format_unordered_list(
+    elements: &[ListItem],
+    references: &References,
+    options: &RenderOptions,
+) -> String {
+    format_list(elements, "ul", "ul", references, options)
+}
+
+fn format_ordered_list(
+    elements: &[ListItem],
+    num_type: &OrderedListType,
+    references: &References,
+    options: &RenderOptions,
+) -> String {
+    // numbers are the default, so numeric lists go without a type
+    if num_type != &OrderedListType::Numeric {
+        format_list(
+            elements,
+            &format!("ol type=\"{}\"", num_type.to_str()),
+            "ol",
+            references,
+            options,
+        )
+    } else {
+        format_list(elements, "ol", "ol", references, options)
+    }
+}
+
+// A `caption` attribute in the info string wraps the code block in a figure
+fn format_codeblock(info: &Option<String>, elements: &str, options: &RenderOptions) -> String {
+    let info = info.as_ref().map_or("", |info| info.as_str());
+    let elements = if options.trim_code_blank_lines {
+        trim_blank_lines(elements)
+    } else {
+        elements
+    };
+    let content = match options.code_line_numbers {
+        Some(start) => format_code_lines(elements, start),
+        None => escape(elements, false),
+    };
+    let code = format!(
+        "<pre><code{}>{}</code></pre>{}",
+        format_code_attributes(info),
+        &content,
+        newline(options)
+    );
+    match info_string_attribute(info, "caption") {
+        Some(caption) => format!(
+            "<figure class=\"code\">{nl}<figcaption>{}</figcaption>{nl}{}</figure>{nl}{nl}",
+            escape(caption, true),
+            code,
+            nl = newline(options)
+        ),
+        None => format!("{}{}", code, newline(options)),
+    }
+}
+
+// The language and the classes of an attribute block like `{.wrap startFrom=5}`
+// make up the class of the code element, an identifier like `#main` its id,
+// the other attributes of the block become data attributes, e.g.
+// `data-start-from="5"`. Attributes that are repeated keep their last value.
+fn format_code_attributes(info: &str) -> String {
+    let (classes, attributes) = info_string_classes(info);
+    let classes: Vec<String> = info_string_language(info)
+        .map(|lang| format!("language-{}", lang))
+        .into_iter()
+        .chain(classes.into_iter().map(str::to_owned))
+        .collect();
+
+    let mut ret = String::new();
+    if !classes.is_empty() {
+        ret.push_str(&format!(" class=\"{}\"", escape(&classes.join(" "), true)));
+    }
+    let mut names: Vec<(String, &str)> = vec![];
+    for (name, value) in attributes {
+        let name = match name {
+            "id" => name.to_owned(),
+            _ => data_attribute_name(name),
+        };
+        names.retain(|(other, _)| *other != name);
+        names.push((name, value));
+    }
+    for (name, value) in names {
+        ret.push_str(&format!(" {}=\"{}\"", name, escape(value, true)));
+    }
+    ret
+}
+
+// turns an attribute name like `startFrom` into `data-start-from`
+fn data_attribute_name(name: &str) -> String {
+    let mut ret = "data-".to_owned();
+    for (i, c) in name.trim_start_matches("data-").char_indices() {
+        if c.is_uppercase() && i > 0 {
+            ret.push('-');
+        }
+        ret.extend(c.to_lowercase());
+    }
+    ret
+}
+
+// removes a first and a last line made up of spaces and tabs only
+fn trim_blank_lines(code: &str) -> &str {
+    let is_blank = |line: &str| line.chars().all(|c| c == ' ' || c == '\t');
+    let code = match code.split_once('\n') {
+        Some((first, rest)) if is_blank(first) => rest,
+        _ => code,
+    };
+    match code.rsplit_once('\n') {
+        Some((rest, last)) if is_blank(last) => rest,
+        _ => code,
+    }
+}
+
+// wraps each line of code in a numbered span, the newlines stay between the
+// spans so the code reads the same when copied
+fn format_code_lines(code: &str, start: usize) -> String {
+    code.split('\n')
+        .enumerate()
+        .map(|(i, line)| {
+            format!(
+                "<span class=\"line\" data-line=\"{}\">{}</span>",
+                start + i,
+                escape(line, false)
+            )
+        })
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn format_table(
+    alignments: &[Alignment],
+    header: &[Vec<Span>],
+    rows: &[Vec<Vec<Span>>],
+    references: &References,
+    options: &RenderOptions,
+) -> String {
+    let format_row = |cells: &[Vec<Span>], tag: &str| {
+        let mut ret = format!("<tr>{}", newline(options));
+        for (i, cell) in cells.iter().enumerate() {
+            let align = match alignments.get(i) {
+                Some(Alignment::Left) => " align=\"left\"",
+                Some(Alignment::Center) => " align=\"center\"",
+                Some(Alignment::Right) => " align=\"right\"",
+                Some(Alignment::None) | None => "",
+            };
+            ret.push_str(&format!(
+                "<{tag}{}>{}</{tag}>{nl}",
+                align,
+                format_spans(cell, references, options),
+                tag = tag,
+                nl = newline(options)
+            ));
+        }
+        ret.push_str("</tr>");
+        ret.push_str(newline(options));
+        ret
+    };
+
+    let nl = newline(options);
+    let mut ret = format!(
+        "<table>{nl}<thead>{nl}{}</thead>{nl}",
+        format_row(header, "th"),
+        nl = nl
+    );
+    if !rows.is_empty() {
+        ret.push_str(&format!("<tbody>{}", nl));
+        for row in rows {
+            ret.push_str(&format_row(row, "td"));
+        }
+        ret.push_str(&format!("</tbody>{}", nl));
+    }
+    ret.push_str(&format!("</table>{nl}{nl}", nl = nl));
+    ret
+}
+
+fn format_blockquote(
+    elements: &[Block],
+    references: &References,
+    options: &RenderOptions,
+) -> String {
+    if !options.block_classes {
+        return format!(
+            "<blockquote>{nl}{}</blockquote>{nl}{nl}",
+            format_blocks(elements, references, options),
+            nl = newline(options)
+        );
+    }
+    match admonition(elements) {
+        Some((kind, first_line, elements)) => {
+            // the rest of the marker's paragraph is a paragraph of its own
+            let mut content = String::new();
+            if !first_line.is_empty() {
+                content.push_str(&format_paragraph(first_line, references, options));
+            }
+            for block in elements {
+                content.push_str(&format_block(block, references, options));
+            }
+            format!(
+                "<blockquote class=\"admonition admonition-{}\">{nl}\
+                 <p class=\"admonition-title\">{}{}</p>{nl}{}{nl}</blockquote>{nl}{nl}",
+                kind.to_lowercase(),
+                &kind[..1],
+                kind[1..].to_lowercase(),
+                content.trim(),
+                nl = newline(options)
+            )
+        }
+        None => format!(
+            "<blockquote class=\"blockquote\">{nl}{}</blockquote>{nl}{nl}",
+            format_blocks(elements, references, options),
+            nl = newline(options)
+        ),
+    }
+}
+
+// Returns the kind of admonition (`NOTE`, `TIP`, ...) if the blockquote starts
+// with a line of only `[!KIND]`, along with the rest of the paragraph following
+// that line and the blocks after the paragraph.
+fn admonition(elements: &[Block]) -> Option<(&'static str, &[Span], &[Block])> {
+    const KINDS: [&str; 5] = ["NOTE", "TIP", "IMPORTANT", "WARNING", "CAUTION"];
+
+    let spans = match elements.first() {
+        Some(Paragraph(ref spans)) => spans,
+        _ => return None,
+    };
+    let kind = match spans.first() {
+        Some(RefLink(_, _, ref raw)) => KINDS.iter().find(|kind| *raw == format!("[!{}]", kind))?,
+        _ => return None,
+    };
+    let rest = match spans[1..].split_first() {
+        None => &[],
+        Some((Text(text), rest)) if text == "\n" => rest,
+        Some((&Break, rest)) => rest,
+        Some(_) => return None,
+    };
+    Some((kind, rest, &elements[1..]))
+}
+
+fn format_div(
+    classes: &[String],
+    elements: &[Block],
+    references: &References,
+    options: &RenderOptions,
+) -> String {
+    let class = if classes.is_empty() {
+        "".to_owned()
+    } else {
+        format!(" class=\"{}\"", escape(&classes.join(" "), true))
+    };
+    format!(
+        "<div{}>{nl}{}</div>{nl}{nl}",
+        class,
+        format_blocks(elements, references, options),
+        nl = newline(options)
+    )
+}
+
+fn format_details(
+    summary: &[Span],
+    elements: &[Block],
+    references: &References,
+    options: &RenderOptions,
+) -> String {
+    let summary = if summary.is_empty() {
+        "".to_owned()
+    } else {
+        format!(
+            "<summary>{}</summary>{}",
+            format_spans(summary, references, options),
+            newline(options)
+        )
+    };
+    format!(
+        "<details>{nl}{}{}</details>{nl}{nl}",
+        summary,
+        format_blocks(elements, references, options),
+        nl = newline(options)
+    )
+}
+
+fn format_line_block(
+    lines: &[Vec<Span>],
+    references: &References,
+    options: &RenderOptions,
+) -> String {
+    let mut ret = "<div class=\"line-block\">".to_owned();
+    for line in lines {
+        ret.push_str(&format_spans(line, references, options));
+        ret.push_str(options.hard_break.to_str());
+        ret.push_str(newline(options));
+    }
+    ret.push_str("</div>");
+    ret.push_str(newline(options));
+    ret.push_str(newline(options));
+    ret
+}
+
+fn format_image(
+    alt: &str,
+    url: &str,
+    title: Option<&str>,
+    size: Option<ObjectSize>,
+    options: &RenderOptions,
+) -> String {
+    let mut attributes = format!("src=\"{}\"", escape(&resolve_url(url, options), false));
+    if let Some(title) = title {
+        attributes.push_str(&format!(" title=\"{}\"", escape(title, true)));
+    }
+    // the alt attribute can't contain markup, so only the text is kept
+    attributes.push_str(&format!(" alt=\"{}\"", escape(&alt_text(alt), true)));
+    if let Some(size) = size {
+        attributes.push_str(&format_size(size));
+    }
+    format!("<img {} />", attributes)
+}
+
+// the `width` and `height` attributes of an image or an object
+fn format_size(size: ObjectSize) -> String {
+    let mut attributes = String::new();
+    if let Some(width) = size.width {
+        attributes.push_str(&format!(" width=\"{}\"", width));
+    }
+    if let Some(height) = size.height {
+        attributes.push_str(&format!(" height=\"{}\"", height));
+    }
+    attributes
+}
+
+fn format_paragraph(elements: &[Span], references: &References, options: &RenderOptions) -> String {
+    // the title moves from the image to the caption of the figure
+    if let [Image(ref alt, ref url, Some(ref title), size)] = *elements {
+        if options.images_as_figures {
+            return format!(
+                "<figure>{nl}{}{nl}<figcaption>{}</figcaption>{nl}</figure>{nl}{nl}",
+                format_image(alt, url, None, size, options),
+                escape(title, true),
+                nl = newline(options)
+            );
+        }
+    }
+    wrap_paragraph(&format_spans(elements, references, options), "", options)
+}
+
+fn wrap_paragraph(content: &str, attributes: &str, options: &RenderOptions) -> String {
+    match options.paragraph_tag {
+        Some(ref tag) => format!(
+            "<{tag}{}>{}</{tag}>{nl}{nl}",
+            attributes,
+            content,
+            tag = if is_tag_name(tag) { tag } else { "p" },
+            nl = newline(options)
+        ),
+        None => format!("{}\n\n", content),
+    }
+}
+
+// whether a tag name can be written into the markup as it is, without
+// adding attributes or closing the tag
+fn is_tag_name(tag: &str) -> bool {
+    !tag.is_empty() && tag.bytes().all(|b| b.is_ascii_alphanumeric())
+}
+
+fn format_header(
+    elements: &[Span],
+    level: usize,
+    references: &References,
+    options: &RenderOptions,
+) -> String {
+    let content = match options.heading_case {
+        HeadingCase::Unchanged => format_spans(elements, references, options),
+        case => format_spans(
+            &recase_spans(elements, case, &mut true),
+            references,
+            options,
+        ),
+    };
+    format!(
+        "<h{} id='{}'>{}</h{}>{nl}{nl}",
+        level,
+        escape(&header_id(elements, references, options), false),
+        content,
+        level,
+        nl = newline(options)
+    )
+}
+
+#[cfg(test)]
+mod test {
+    use super::{
+        escape, render_block, rendered_headings, rendered_headings_with_options, to_html,
+        Container, HardBreak, HeadingCase, RefLinkFallback, RenderOptions, SlugFn, SlugStyle,
+    };
+    use parser::Block::{CodeBlock, Comment, Header, Paragraph, Raw, UnorderedList};
+    use parser::Span::{Image, Link, Text};
+    use parser::{
+        parse, parse_with_options, AutolinkText, ListItem, ParseOptions, Span, UnorderedListType,
+    };
+    use render_inline;
+
+    #[test]
+    fn keeps_named_entities() {
+        assert_eq!(escape("&copy; 2019", true), "&copy; 2019");
+        assert_eq!(escape("a&nbsp;b", true), "a&nbsp;b");
+        assert_eq!(escape("&amp;", true), "&amp;");
+    }
+
+    #[test]
+    fn keeps_numeric_entities() {
+        assert_eq!(escape("&#169;", true), "&#169;");
+        assert_eq!(escape("&#x1F600;", true), "&#x1F600;");
+        assert_eq!(escape("&#X1f600;", true), "&#X1f600;");
+    }
+
+    #[test]
+    fn escapes_lone_ampersands() {
+        assert_eq!(escape("&", true), "&amp;");
+        assert_eq!(escape("fish & chips", true), "fish &amp; chips");
+        assert_eq!(escape("AT&T", true), "AT&amp;T");
+    }
+
+    #[test]
+    fn escapes_invalid_entities() {
+        assert_eq!(escape("&;", true), "&amp;;");
+        assert_eq!(escape("&# ;", true), "&amp;# ;");
+        assert_eq!(escape("&#xZZ;", true), "&amp;#xZZ;");
+        assert_eq!(escape("&foo&bar;", true), "&amp;foo&bar;");
+    }
+
+    #[test]
+    fn escapes_entities_in_code() {
+        assert_eq!(escape("&copy;", false), "&amp;copy;");
+    }
+
+    #[test]
+    fn renders_comments() {
+        let options = RenderOptions::default();
+        assert_eq!(
+            to_html(&parse("<!--\na comment\n-->\n\ntext"), &options),
+            "<!--\na comment\n-->\n\n<p>text</p>\n"
+        );
+        assert_eq!(
+            to_html(&parse("some <!-- a comment --> text"), &options),
+            "<p>some <!-- a comment --> text</p>\n"
+        );
+    }
+
+    #[test]
+    fn escapes_malformed_comments() {
+        let options = RenderOptions::default();
+        assert_eq!(
+            to_html(
+                &parse("x <!-- a --!><img src=x onerror=alert(1)> --> y"),
+                &options
+            ),
+            "<p>x &lt;!-- a --!&gt;&lt;img src=x onerror=alert(1)&gt; --&gt; y</p>\n"
+        );
+        assert_eq!(
+            render_block(
+                &Paragraph(vec![Span::Comment(" a --!><img src=x> ".to_owned())]),
+                &options
+            ),
+            "<p>&lt;!-- a --!&gt;&lt;img src=x&gt; --&gt;</p>\n"
+        );
+        assert_eq!(
+            render_block(&Comment("> a ".to_owned()), &options),
+            "&lt;!--&gt; a --&gt;\n"
+        );
+        assert_eq!(
+            render_block(
+                &Comment("> a ".to_owned()),
+                &RenderOptions::new().unsafe_html(true).build()
+            ),
+            "<!--> a -->\n"
+        );
+    }
+
+    #[test]
+    fn strips_comments() {
+        let options = RenderOptions::new().strip_comments(true).build();
+        assert_eq!(
+            to_html(&parse("<!--\na comment\n-->\n\ntext"), &options),
+            "<p>text</p>\n"
+        );
+        assert_eq!(
+            to_html(&parse("some <!-- a comment --> text"), &options),
+            "<p>some  text</p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_single_blocks() {
+        let options = RenderOptions::default();
+        assert_eq!(
+            render_block(&Header(vec![Text("Test".to_owned())], 2), &options),
+            "<h2 id='test'>Test</h2>\n"
+        );
+        assert_eq!(
+            render_block(
+                &UnorderedList(
+                    vec![
+                        ListItem::Simple(vec![Text("a".to_owned())]),
+                        ListItem::Simple(vec![Text("b".to_owned())])
+                    ],
+                    UnorderedListType::Dash
+                ),
+                &options
+            ),
+            "<ul>\n<li>a</li>\n\n<li>b</li>\n</ul>\n"
+        );
+    }
+
+    #[test]
+    fn renders_task_lists() {
+        assert_eq!(
+            to_html(
+                &parse("* [ ] to do\n* [x] done\n* [-] in progress"),
+                &RenderOptions::default()
+            ),
+            "<ul>\n\
+             <li><input type=\"checkbox\" disabled=\"\" /> to do</li>\n\n\
+             <li><input type=\"checkbox\" disabled=\"\" checked=\"\" /> done</li>\n\n\
+             <li><input type=\"checkbox\" disabled=\"\" data-indeterminate=\"\" /> in progress</li>\n\
+             </ul>\n"
+        );
+    }
+
+    #[test]
+    fn renders_reference_links() {
+        let options = RenderOptions::default();
+        assert_eq!(
+            to_html(
+                &parse("[a link][id]\n[Another]\n\n[id]: example.com\n[another]: example.org \"Title\""),
+                &options
+            ),
+            "<p><a href=\"example.com\">a link</a>\n\
+             <a href=\"example.org\" title=\"Title\">Another</a></p>\n"
+        );
+        assert_eq!(
+            to_html(&parse("> [a link][id]\n\n[id]: example.com"), &options),
+            "<blockquote>\n<p><a href=\"example.com\">a link</a></p>\n</blockquote>\n"
+        );
+        assert_eq!(
+            to_html(
+                &parse("[a link][id]\n\n[id]: example.com \"A title\n  on two lines\""),
+                &options
+            ),
+            "<p><a href=\"example.com\" title=\"A title on two lines\">a link</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_all_reference_link_forms() {
+        assert_eq!(
+            to_html(
+                &parse("[Full][Foo Bar], [Foo Bar][] and [Foo Bar]\n\n[foo bar]: example.com"),
+                &RenderOptions::default()
+            ),
+            "<p><a href=\"example.com\">Full</a>, \
+             <a href=\"example.com\">Foo Bar</a> and \
+             <a href=\"example.com\">Foo Bar</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_unresolved_reference_links_as_source() {
+        let options = RenderOptions::default();
+        assert_eq!(
+            to_html(&parse("[a *link*][id]"), &options),
+            "<p>[a *link*][id]</p>\n"
+        );
+        assert_eq!(
+            to_html(&parse("[<b>][id]"), &options),
+            "<p>[&lt;b&gt;][id]</p>\n"
+        );
+    }
+
+    #[test]
+    fn builds_options() {
+        assert_eq!(RenderOptions::new().build(), RenderOptions::default());
+        assert_eq!(
+            RenderOptions::new().strip_comments(true).build(),
+            RenderOptions {
+                strip_comments: true,
+                ..RenderOptions::default()
+            }
+        );
+    }
+
+    #[test]
+    fn resolves_relative_urls() {
+        let options = RenderOptions::new().base_url("/docs/").build();
+        assert_eq!(
+            to_html(&parse("![an image](img/a.png)"), &options),
+            "<p><img src=\"/docs/img/a.png\" alt=\"an image\" /></p>\n"
+        );
+        assert_eq!(
+            to_html(&parse("[a link](page.html \"Title\")"), &options),
+            "<p><a href=\"/docs/page.html\" title=\"Title\">a link</a></p>\n"
+        );
+        assert_eq!(
+            to_html(&parse("[a link][id]\n\n[id]: page.html"), &options),
+            "<p><a href=\"/docs/page.html\">a link</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn keeps_absolute_urls() {
+        let options = RenderOptions::new()
+            .base_url("https://example.com/docs/")
+            .build();
+        assert_eq!(
+            to_html(&parse("[a link](https://example.org/)"), &options),
+            "<p><a href=\"https://example.org/\">a link</a></p>\n"
+        );
+        assert_eq!(
+            to_html(&parse("[a link](mailto:me@example.org)"), &options),
+            "<p><a href=\"mailto:me@example.org\">a link</a></p>\n"
+        );
+        assert_eq!(
+            to_html(&parse("[a link](https://example.org/a/../b)"), &options),
+            "<p><a href=\"https://example.org/a/../b\">a link</a></p>\n"
+        );
+        assert_eq!(
+            to_html(&parse("[a link](/root.html)"), &options),
+            "<p><a href=\"/root.html\">a link</a></p>\n"
+        );
+        assert_eq!(
+            to_html(&parse("![an image](//cdn.example.org/a.png)"), &options),
+            "<p><img src=\"//cdn.example.org/a.png\" alt=\"an image\" /></p>\n"
+        );
+        assert_eq!(
+            to_html(&parse("[a link](#fragment)"), &options),
+            "<p><a href=\"#fragment\">a link</a></p>\n"
+        );
+        assert_eq!(
+            to_html(&parse("[a link](page.html)"), &options),
+            "<p><a href=\"https://example.com/docs/page.html\">a link</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn resolves_urls_like_rfc_3986() {
+        let options = RenderOptions::new()
+            .base_url("https://example.com/docs/guide/index.html?v=1#intro")
+            .build();
+        let href = |url: &str| {
+            let html = to_html(&parse(&format!("[a link]({})", url)), &options);
+            html["<p><a href=\"".len()..html.find("\">").unwrap()].to_owned()
+        };
+        // relative paths replace the file name of the base
+        assert_eq!(
+            href("page.html"),
+            "https://example.com/docs/guide/page.html"
+        );
+        assert_eq!(href("./a/b.png"), "https://example.com/docs/guide/a/b.png");
+        assert_eq!(href("../page.html"), "https://example.com/docs/page.html");
+        assert_eq!(
+            href("../../../../page.html"),
+            "https://example.com/page.html"
+        );
+        assert_eq!(href("a/./b/../c"), "https://example.com/docs/guide/a/c");
+        // a query alone stays on the page of the base
+        assert_eq!(
+            href("?page=2"),
+            "https://example.com/docs/guide/index.html?page=2"
+        );
+        assert_eq!(
+            href("page.html?a=1#b"),
+            "https://example.com/docs/guide/page.html?a=1#b"
+        );
+    }
+
+    #[test]
+    fn resolves_urls_against_bases_without_a_trailing_slash() {
+        let href = |base: &str, url: &str| {
+            let options = RenderOptions::new().base_url(base).build();
+            let html = to_html(&parse(&format!("[a link]({})", url)), &options);
+            html["<p><a href=\"".len()..html.find("\">").unwrap()].to_owned()
+        };
+        assert_eq!(
+            href("https://example.com/docs", "page.html"),
+            "https://example.com/page.html"
+        );
+        assert_eq!(
+            href("https://example.com", "page.html"),
+            "https://example.com/page.html"
+        );
+        assert_eq!(href("/docs/", "../é/a.png"), "/é/a.png");
+        assert_eq!(href("/docs/", "?a=1"), "/docs/?a=1");
+    }
+
+    #[test]
+    fn resolves_urls_with_line_breaks() {
+        let blocks = vec![Paragraph(vec![Link(
+            vec![Span::Text("a link".to_owned())],
+            "x#a\nb".to_owned(),
+            None,
+        )])];
+        let options = RenderOptions::new().base_url("/docs/").build();
+        assert_eq!(
+            to_html(&blocks, &options),
+            "<p><a href=\"/docs/x#a\nb\">a link</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_details() {
+        assert_eq!(
+            to_html(
+                &parse("<details>\n<summary>More</summary>\n\n* a *list*\n* [a link][id]\n</details>\n\n[id]: example.com"),
+                &RenderOptions::default()
+            ),
+            "<details>\n<summary>More</summary>\n\
+             <ul>\n<li>a <em>list</em></li>\n\n<li><a href=\"example.com\">a link</a></li>\n</ul>\n\
+             </details>\n"
+        );
+    }
+
+    #[test]
+    fn renders_unresolved_reference_links_as_label() {
+        let options = RenderOptions::new()
+            .reflink_fallback(RefLinkFallback::Label)
+            .build();
+        assert_eq!(
+            to_html(&parse("[a *link*][id]"), &options),
+            "<p>a <em>link</em></p>\n"
+        );
+        assert_eq!(
+            to_html(&parse("[a link][id]\n\n[id]: example.com"), &options),
+            "<p><a href=\"example.com\">a link</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_hard_breaks() {
+        let text = "a line  \nanother line";
+        assert_eq!(
+            to_html(&parse(text), &RenderOptions::default()),
+            "<p>a line<br />another line</p>\n"
+        );
+        assert_eq!(
+            to_html(
+                &parse(text),
+                &RenderOptions::new().hard_break(HardBreak::Br).build()
+            ),
+            "<p>a line<br>another line</p>\n"
+        );
+        assert_eq!(
+            to_html(
+                &parse(text),
+                &RenderOptions::new().hard_break(HardBreak::Newline).build()
+            ),
+            "<p>a line\nanother line</p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_image_sizes() {
+        assert_eq!(
+            to_html(
+                &parse("![a](a.png \"Title\" =100x50)"),
+                &RenderOptions::default()
+            ),
+            "<p><img src=\"a.png\" title=\"Title\" alt=\"a\" width=\"100\" height=\"50\" /></p>\n"
+        );
+        assert_eq!(
+            to_html(&parse("![a](a.png =100x)"), &RenderOptions::default()),
+            "<p><img src=\"a.png\" alt=\"a\" width=\"100\" /></p>\n"
+        );
+    }
+
+    #[test]
+    fn wraps_output_in_container() {
+        let options = RenderOptions::new()
+            .container(Container::new("article").class("markdown-body"))
+            .build();
+        assert_eq!(
+            to_html(&parse("# Title\n\nSome text"), &options),
+            "<article class=\"markdown-body\">\n<h1 id='title'>Title</h1>\n\n<p>Some text</p>\n</article>\n"
+        );
+
+        let options = RenderOptions::new()
+            .container(Container::new("div").id("content"))
+            .build();
+        assert_eq!(
+            to_html(&parse("Some text"), &options),
+            "<div id=\"content\">\n<p>Some text</p>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn renders_footnote_back_references() {
+        let text = "One[^1] and two[^1].\n\n[^1]: A note.";
+        assert_eq!(
+            to_html(&parse(text), &RenderOptions::default()),
+            concat!(
+                "<p>One<sup class=\"footnote-ref\"><a href=\"#fn1\" id=\"fnref1\">1</a></sup>",
+                " and two<sup class=\"footnote-ref\"><a href=\"#fn1\" id=\"fnref1-2\">1</a></sup>.</p>\n",
+                "\n<section class=\"footnotes\">\n<ol>\n<li id=\"fn1\">\n",
+                "<p>A note. <a href=\"#fnref1\" class=\"footnote-backref\">↩</a>",
+                " <a href=\"#fnref1-2\" class=\"footnote-backref\">↩<sup>2</sup></a></p>\n",
+                "</li>\n</ol>\n</section>\n"
+            )
+        );
+    }
+
+    #[test]
+    fn numbers_footnotes_by_first_reference() {
+        let text = "A[^b] B[^a] C[^missing]\n\n[^a]: First.\n[^b]: Second.\n[^c]: Unused.";
+        let html = to_html(&parse(text), &RenderOptions::default());
+        assert!(html.contains("<a href=\"#fn1\" id=\"fnref1\">1</a>"));
+        assert!(html.contains("<li id=\"fn1\">\n<p>Second."));
+        assert!(html.contains("<li id=\"fn2\">\n<p>First."));
+        assert!(html.contains("C[^missing]"));
+        assert!(!html.contains("Unused"));
+    }
+
+    #[test]
+    fn renders_tables() {
+        assert_eq!(
+            to_html(
+                &parse("| a | b |\n|:-:|---|\n| *1* | 2 |"),
+                &RenderOptions::default()
+            ),
+            "<table>\n<thead>\n<tr>\n<th align=\"center\">a</th>\n<th>b</th>\n</tr>\n</thead>\n\
+             <tbody>\n<tr>\n<td align=\"center\"><em>1</em></td>\n<td>2</td>\n</tr>\n</tbody>\n</table>\n"
+        );
+    }
+
+    #[test]
+    fn wraps_headers_in_sections() {
+        let options = RenderOptions::new().sections(true).build();
+        assert_eq!(
+            to_html(
+                &parse("# A\n\nIntro\n\n## B\n\n## C\n\nText\n\n# D"),
+                &options
+            ),
+            "<section>\n<h1 id='a'>A</h1>\n\n<p>Intro</p>\n\n\
+             <section>\n<h2 id='b'>B</h2>\n</section>\n\n\
+             <section>\n<h2 id='c'>C</h2>\n\n<p>Text</p>\n</section>\n</section>\n\n\
+             <section>\n<h1 id='d'>D</h1>\n</section>\n"
+        );
+        assert_eq!(
+            to_html(&parse("Before\n\n## A"), &options),
+            "<p>Before</p>\n\n<section>\n<h2 id='a'>A</h2>\n</section>\n"
+        );
+    }
+
+    #[test]
+    fn renders_collapsible_sections() {
+        let options = RenderOptions::new()
+            .sections(true)
+            .collapsible_sections(2)
+            .build();
+        assert_eq!(
+            to_html(
+                &parse("# A\n\nIntro\n\n## B\n\nText\n\n### C\n\n## D"),
+                &options
+            ),
+            "<section>\n<h1 id='a'>A</h1>\n\n<p>Intro</p>\n\n\
+             <section>\n<details open>\n<summary><h2 id='b'>B</h2></summary>\n\n<p>Text</p>\n\n\
+             <section>\n<details open>\n<summary><h3 id='c'>C</h3></summary>\n</details>\n</section>\n\
+             </details>\n</section>\n\n\
+             <section>\n<details open>\n<summary><h2 id='d'>D</h2></summary>\n</details>\n</section>\n\
+             </section>\n"
+        );
+        // without sections, headers are rendered as usual
+        assert_eq!(
+            to_html(
+                &parse("## B"),
+                &RenderOptions::new().collapsible_sections(2).build()
+            ),
+            "<h2 id='b'>B</h2>\n"
+        );
+    }
+
+    #[test]
+    fn renders_code_block_captions() {
+        assert_eq!(
+            to_html(
+                &parse("```rust caption=\"Listing 1: <main>\"\nfn main() {}\n```"),
+                &RenderOptions::default()
+            ),
+            "<figure class=\"code\">\n<figcaption>Listing 1: &lt;main&gt;</figcaption>\n\
+             <pre><code class=\"language-rust\">fn main() {}</code></pre>\n</figure>\n"
+        );
+        assert_eq!(
+            to_html(
+                &parse("```rust\nfn main() {}\n```"),
+                &RenderOptions::default()
+            ),
+            "<pre><code class=\"language-rust\">fn main() {}</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn renders_code_block_classes_and_attributes() {
+        assert_eq!(
+            to_html(
+                &parse("```python {.numberLines .wrap startFrom=\"5\" data-theme=dark}\npass\n```"),
+                &RenderOptions::default()
+            ),
+            "<pre><code class=\"language-python numberLines wrap\" \
+             data-start-from=\"5\" data-theme=\"dark\">pass</code></pre>\n"
+        );
+        assert_eq!(
+            to_html(
+                &parse("~~~ {.wrap title=\"<a>\"}\npass\n~~~"),
+                &RenderOptions::default()
+            ),
+            "<pre><code class=\"wrap\" data-title=\"&lt;a&gt;\">pass</code></pre>\n"
+        );
+        assert_eq!(
+            to_html(
+                &parse("```js {#main .a startFrom=\"5\" start-from=6 startFrom=\"7\"}\npass\n```"),
+                &RenderOptions::default()
+            ),
+            "<pre><code class=\"language-js a\" id=\"main\" data-start-from=\"7\">pass</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn emits_lowercase_names_in_a_stable_order() {
+        assert_eq!(
+            to_html(
+                &parse("![alt](a.png \"Title\" =10x20)"),
+                &RenderOptions::default()
+            ),
+            "<p><img src=\"a.png\" title=\"Title\" alt=\"alt\" width=\"10\" height=\"20\" /></p>\n"
+        );
+        assert_eq!(
+            to_html(
+                &parse("![alt](a.png =10x20 \"Title\")"),
+                &RenderOptions::default()
+            ),
+            "<p><img src=\"a.png\" title=\"Title\" alt=\"alt\" width=\"10\" height=\"20\" /></p>\n"
+        );
+
+        let options = RenderOptions::new()
+            .container(Container::new("ARTICLE").id("main").class("body"))
+            .build();
+        assert_eq!(
+            to_html(&parse("text"), &options),
+            "<article id=\"main\" class=\"body\">\n<p>text</p>\n</article>\n"
+        );
+    }
+
+    #[test]
+    fn renders_inline_html() {
+        let text = "a <span>b</span> <SCRIPT src='x'>c</script>";
+        assert_eq!(
+            to_html(&parse(text), &RenderOptions::default()),
+            "<p>a &lt;span&gt;b&lt;/span&gt; &lt;SCRIPT src=&#8217;x&#8217;&gt;c&lt;/script&gt;</p>\n"
+        );
+        assert_eq!(
+            to_html(
+                &parse(text),
+                &RenderOptions::new().unsafe_html(true).build()
+            ),
+            "<p>a <span>b</span> <SCRIPT src='x'>c</script></p>\n"
+        );
+        assert_eq!(
+            to_html(
+                &parse(text),
+                &RenderOptions::new()
+                    .unsafe_html(true)
+                    .gfm_tagfilter(true)
+                    .build()
+            ),
+            "<p>a <span>b</span> &lt;SCRIPT src='x'>c&lt;/script></p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_emoji_as_images() {
+        let blocks = parse_with_options(
+            "Hi :smile: & :warning:",
+            &ParseOptions::new().emoji(true).build(),
+        );
+        assert_eq!(
+            to_html(&blocks, &RenderOptions::default()),
+            "<p>Hi 😄 &amp; ⚠️</p>\n"
+        );
+        assert_eq!(
+            to_html(
+                &blocks,
+                &RenderOptions::new()
+                    .emoji_url("https://cdn.example.com/{code}.svg?a&b")
+                    .build()
+            ),
+            "<p>Hi <img src=\"https://cdn.example.com/1f604.svg?a&amp;b\" alt=\"😄\" class=\"emoji\" /> &amp; \
+             <img src=\"https://cdn.example.com/26a0.svg?a&amp;b\" alt=\"⚠️\" class=\"emoji\" /></p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_breaks_in_headers_and_list_items() {
+        assert_eq!(
+            to_html(
+                &parse("Title  \ncontinued\n---\n\n- a  \n  b\n- c"),
+                &RenderOptions::default()
+            ),
+            "<h2 id='title-continued'>Title<br />continued</h2>\n\n\
+             <ul>\n<li>a<br />b</li>\n\n<li>c</li>\n</ul>\n"
+        );
+    }
+
+    #[test]
+    fn escapes_code_spans_without_decoding_entities() {
+        assert_eq!(
+            to_html(
+                &parse("`&amp; <b> &copy;` &amp;"),
+                &RenderOptions::default()
+            ),
+            "<p><code>&amp;amp; &lt;b&gt; &amp;copy;</code> &amp;</p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_divs() {
+        assert_eq!(
+            to_html(
+                &parse(":::: {.note .wide}\n::: warning\n*Careful*\n:::\n::::"),
+                &RenderOptions::default()
+            ),
+            "<div class=\"note wide\">\n<div class=\"warning\">\n<p><em>Careful</em></p>\n</div>\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn renders_objects() {
+        assert_eq!(
+            to_html(
+                &parse("[a *video*](movie.mp4 \"Trailer\" =640x50%)"),
+                &RenderOptions::new()
+                    .base_url("https://example.com/media/")
+                    .build()
+            ),
+            "<p><object data=\"https://example.com/media/movie.mp4\" title=\"Trailer\" \
+             width=\"640\" height=\"50%\">a <em>video</em></object></p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_inline_markup() {
+        assert_eq!(render_inline("*hi*"), "<em>hi</em>");
+        assert_eq!(
+            render_inline("`code`, [a link](/url) and ![an image](a.png)"),
+            "<code>code</code>, <a href=\"/url\">a link</a> and <img src=\"a.png\" alt=\"an image\" />"
+        );
+        assert_eq!(render_inline("# not a header"), "# not a header");
+    }
+
+    #[test]
+    fn trims_code_blank_lines() {
+        let options = RenderOptions::new().trim_code_blank_lines(true).build();
+        assert_eq!(
+            to_html(&parse("```\n  \n  a\n\n\tb \n \n```"), &options),
+            "<pre><code>  a\n\n\tb </code></pre>\n"
+        );
+        assert_eq!(
+            to_html(&[CodeBlock(None, "\n\n a\n\n".to_owned())], &options),
+            "<pre><code>\n a\n</code></pre>\n"
+        );
+        assert_eq!(
+            to_html(
+                &[CodeBlock(None, "\na\n".to_owned())],
+                &RenderOptions::default()
+            ),
+            "<pre><code>\na\n</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn filters_disallowed_tags() {
+        let options = RenderOptions::new()
+            .unsafe_html(true)
+            .gfm_tagfilter(true)
+            .build();
+        for tag in [
+            "title",
+            "textarea",
+            "style",
+            "xmp",
+            "iframe",
+            "noembed",
+            "noframes",
+            "script",
+            "plaintext",
+        ] {
+            assert_eq!(
+                to_html(&[Raw(format!("<{0} a=\"b\">x</{0}>\n", tag))], &options),
+                format!("&lt;{0} a=\"b\">x&lt;/{0}>\n", tag)
+            );
+            assert_eq!(
+                to_html(&parse(&format!("a <{0}>b</{0}>", tag)), &options),
+                format!("<p>a &lt;{0}>b&lt;/{0}></p>\n", tag)
+            );
+        }
+
+        assert_eq!(
+            to_html(
+                &[Raw("<div><STYLE/><scripts><span>x</span></div>".to_owned())],
+                &options
+            ),
+            "<div>&lt;STYLE/><scripts><span>x</span></div>\n"
+        );
+        assert_eq!(
+            to_html(
+                &[Raw("<script>".to_owned())],
+                &RenderOptions::new().unsafe_html(true).build()
+            ),
+            "<script>\n"
+        );
+    }
+
+    #[test]
+    fn escapes_raw_html_unless_unsafe() {
+        let mut blocks = vec![Raw("<div><script>x</script></div>\n".to_owned())];
+        blocks.extend(parse("a <script>x</script> <b>y</b>"));
+        let escaped = "&lt;div&gt;&lt;script&gt;x&lt;/script&gt;&lt;/div&gt;\n\
+                       <p>a &lt;script&gt;x&lt;/script&gt; &lt;b&gt;y&lt;/b&gt;</p>\n";
+        for options in [
+            RenderOptions::default(),
+            RenderOptions::new().gfm_tagfilter(true).build(),
+            RenderOptions::new()
+                .gfm_tagfilter(true)
+                .strip_comments(true)
+                .build(),
+        ] {
+            assert_eq!(to_html(&blocks, &options), escaped);
+        }
+        assert_eq!(
+            to_html(&blocks, &RenderOptions::new().unsafe_html(true).build()),
+            "<div><script>x</script></div>\n<p>a <script>x</script> <b>y</b></p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_safely_by_default() {
+        let blocks = vec![
+            Raw("<script>alert(1)</script>".to_owned()),
+            Paragraph(vec![
+                Link(
+                    vec![Text("a".to_owned())],
+                    "JavaScript:alert(1)".to_owned(),
+                    None,
+                ),
+                Link(
+                    vec![Text("b".to_owned())],
+                    "java\tscript:x".to_owned(),
+                    None,
+                ),
+                Image(
+                    "c".to_owned(),
+                    "data:image/png;base64,AA".to_owned(),
+                    None,
+                    None,
+                ),
+                Image("d".to_owned(), "data:text/html,x".to_owned(), None, None),
+                Span::Html("<b>".to_owned()),
+            ]),
+        ];
+        assert_eq!(
+            to_html(&blocks, &RenderOptions::default()),
+            "&lt;script&gt;alert(1)&lt;/script&gt;<p><a href=\"\">a</a><a href=\"\">b</a>\
+             <img src=\"data:image/png;base64,AA\" alt=\"c\" /><img src=\"\" alt=\"d\" />&lt;b&gt;</p>\n"
+        );
+        assert_eq!(
+            to_html(&blocks, &RenderOptions::new().unsafe_html(true).build()),
+            "<script>alert(1)</script><p><a href=\"JavaScript:alert(1)\">a</a><a href=\"java\tscript:x\">b</a>\
+             <img src=\"data:image/png;base64,AA\" alt=\"c\" /><img src=\"data:text/html,x\" alt=\"d\" /><b></p>\n"
+        );
+        assert_eq!(
+            to_html(
+                &parse("[x](vbscript:x) [y](https://example.com)"),
+                &RenderOptions::default()
+            ),
+            "<p><a href=\"\">x</a> <a href=\"https://example.com\">y</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_inline_footnotes() {
+        let html = to_html(
+            &parse("One[^1], two^[An *inline* note.] and ^[unclosed.\n\n[^1]: A note."),
+            &RenderOptions::default(),
+        );
+        assert_eq!(
+            html,
+            "<p>One<sup class=\"footnote-ref\"><a href=\"#fn1\" id=\"fnref1\">1</a></sup>, \
+             two<sup class=\"footnote-ref\"><a href=\"#fn2\" id=\"fnref2\">2</a></sup> \
+             and ^[unclosed.</p>\n\
+             \n<section class=\"footnotes\">\n<ol>\n\
+             <li id=\"fn1\">\n<p>A note. <a href=\"#fnref1\" class=\"footnote-backref\">↩</a></p>\n</li>\n\
+             <li id=\"fn2\">\n<p>An <em>inline</em> note. <a href=\"#fnref2\" class=\"footnote-backref\">↩</a></p>\n</li>\n\
+             </ol>\n</section>\n"
+        );
+    }
+
+    #[test]
+    fn renders_inline_footnotes_in_recased_headers() {
+        let options = RenderOptions::new()
+            .heading_case(HeadingCase::Title)
+            .build();
+        assert_eq!(
+            to_html(&parse("# hello^[a note] world"), &options),
+            "<h1 id='hello-world'>Hello<sup class=\"footnote-ref\"><a href=\"#fn1\" id=\"fnref1\">1</a></sup> \
+             World</h1>\n\
+             \n<section class=\"footnotes\">\n<ol>\n\
+             <li id=\"fn1\">\n<p>a note <a href=\"#fnref1\" class=\"footnote-backref\">↩</a></p>\n</li>\n\
+             </ol>\n</section>\n"
+        );
+    }
+
+    #[test]
+    fn leaves_footnotes_out_of_header_ids() {
+        let blocks = parse("# hello ^[a note]\n\n## a [^1] b [^1]\n\n[^1]: a note");
+        let ids = |slug_style: SlugStyle| {
+            let options = RenderOptions::new()
+                .heading_case(HeadingCase::Title)
+                .slug_style(slug_style)
+                .build();
+            rendered_headings_with_options(&blocks, &options)
+                .into_iter()
+                .map(|(_, id, _)| id)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(ids(SlugStyle::GitHub), vec!["hello", "a--b"]);
+        assert_eq!(ids(SlugStyle::Kebab), vec!["hello", "a-b"]);
+        assert_eq!(ids(SlugStyle::Underscore), vec!["hello", "a_b"]);
+        assert_eq!(
+            ids(SlugStyle::Custom(SlugFn::new(|text| format!("[{}]", text)))),
+            vec!["[hello]", "[a  b]"]
+        );
+    }
+
+    #[test]
+    fn renders_inline_footnotes_in_admonitions() {
+        let options = RenderOptions::new().block_classes(true).build();
+        assert_eq!(
+            to_html(&parse("> [!NOTE]\n> see ^[a note]\n\nand ^[another]"), &options),
+            "<blockquote class=\"admonition admonition-note\">\n\
+             <p class=\"admonition-title\">Note</p>\n\
+             <p>see <sup class=\"footnote-ref\"><a href=\"#fn1\" id=\"fnref1\">1</a></sup></p>\n\
+             </blockquote>\n\n\
+             <p>and <sup class=\"footnote-ref\"><a href=\"#fn2\" id=\"fnref2\">2</a></sup></p>\n\
+             \n<section class=\"footnotes\">\n<ol>\n\
+             <li id=\"fn1\">\n<p>a note <a href=\"#fnref1\" class=\"footnote-backref\">↩</a></p>\n</li>\n\
+             <li id=\"fn2\">\n<p>another <a href=\"#fnref2\" class=\"footnote-backref\">↩</a></p>\n</li>\n\
+             </ol>\n</section>\n"
+        );
+    }
+
+    #[test]
+    fn renders_line_blocks() {
+        assert_eq!(
+            to_html(
+                &parse("| Roses are *red*,\n| Violets are blue,\n| Line breaks are kept."),
+                &RenderOptions::default()
+            ),
+            "<div class=\"line-block\">Roses are <em>red</em>,<br />\n\
+             Violets are blue,<br />\nLine breaks are kept.<br />\n</div>\n"
+        );
+    }
+
+    #[test]
+    fn renders_headers_in_title_case() {
+        let options = RenderOptions::new()
+            .heading_case(HeadingCase::Title)
+            .build();
+        assert_eq!(
+            to_html(
+                &parse("# the state of *the art* in `the_code` and the API"),
+                &options
+            ),
+            "<h1 id='the-state-of-the-art-in-the_code-and-the-api'>The State of <em>the Art</em> \
+             in <code>the_code</code> and the API</h1>\n"
+        );
+        assert_eq!(
+            to_html(&parse("## a [link to it](/x) of words"), &options),
+            "<h2 id='a-link-to-it-of-words'>A <a href=\"/x\">link to it</a> of Words</h2>\n"
+        );
+    }
+
+    #[test]
+    fn renders_headers_in_sentence_case() {
+        let options = RenderOptions::new()
+            .heading_case(HeadingCase::Sentence)
+            .build();
+        assert_eq!(
+            to_html(
+                &parse("# The Big **News** About `Code` And iPhone"),
+                &options
+            ),
+            "<h1 id='the-big-news-about-code-and-iphone'>The big <strong>news</strong> \
+             about <code>Code</code> and iPhone</h1>\n"
+        );
+        assert_eq!(
+            to_html(&parse("# `Code` First"), &options),
+            "<h1 id='code-first'><code>Code</code> first</h1>\n"
+        );
+    }
+
+    #[test]
+    fn renders_standalone_images_as_figures() {
+        let options = RenderOptions::new().images_as_figures(true).build();
+        assert_eq!(
+            to_html(&parse("![a cat](cat.png \"My cat\")"), &options),
+            "<figure>\n<img src=\"cat.png\" alt=\"a cat\" />\n\
+             <figcaption>My cat</figcaption>\n</figure>\n"
+        );
+        assert_eq!(
+            to_html(
+                &parse("![a cat](cat.png \"My cat\")"),
+                &RenderOptions::default()
+            ),
+            "<p><img src=\"cat.png\" title=\"My cat\" alt=\"a cat\" /></p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_inline_images_without_figures() {
+        let options = RenderOptions::new().images_as_figures(true).build();
+        assert_eq!(
+            to_html(&parse("see ![a cat](cat.png \"My cat\") here"), &options),
+            "<p>see <img src=\"cat.png\" title=\"My cat\" alt=\"a cat\" /> here</p>\n"
+        );
+        assert_eq!(
+            to_html(&parse("![a cat](cat.png)"), &options),
+            "<p><img src=\"cat.png\" alt=\"a cat\" /></p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_headings_with_slugs() {
+        let blocks = parse("# A **bold** move\n\ntext\n\n## [Link][id]\n\n[id]: /url");
+        assert_eq!(
+            rendered_headings(&blocks),
+            vec![
+                (
+                    "A <strong>bold</strong> move".to_owned(),
+                    "a-bold-move".to_owned(),
+                    1
+                ),
+                ("<a href=\"/url\">Link</a>".to_owned(), "link".to_owned(), 2)
+            ]
+        );
+        assert!(to_html(&blocks, &RenderOptions::default()).contains("<h1 id='a-bold-move'>"));
+    }
+
+    #[test]
+    fn renders_only_smart_dashes() {
+        let options = RenderOptions::new().smart_dashes(true).build();
+        assert_eq!(
+            to_html(
+                &parse("pages 1--5 --- \"quoted\" and 'single'... `a -- b`\n\n    c --- d"),
+                &options
+            ),
+            "<p>pages 1\u{2013}5 \u{2014} &quot;quoted&quot; and &#8217;single&#8217;... \
+             <code>a -- b</code></p>\n\n<pre><code>c --- d</code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn renders_smart_quotes_and_ellipses() {
+        let options = RenderOptions::new()
+            .smart_quotes(true)
+            .smart_ellipses(true)
+            .build();
+        assert_eq!(
+            to_html(
+                &parse("\"It's *a* 'test'\"... (\"*b*\") `\"c\"` d -- e"),
+                &options
+            ),
+            "<p>\u{201C}It\u{2019}s <em>a</em> \u{2018}test\u{2019}\u{201D}\u{2026} \
+             (\u{201C}<em>b</em>\u{201D}) <code>&quot;c&quot;</code> d -- e</p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_footnote_anchors_with_names() {
+        let options = RenderOptions::new().footnote_names(true).build();
+        assert_eq!(
+            to_html(&parse("One[^a], two[^a].\n\n[^a]: A note."), &options),
+            concat!(
+                "<p>One<sup class=\"footnote-ref\"><a href=\"#fn1\" id=\"fnref1\" name=\"fnref1\">1</a></sup>",
+                ", two<sup class=\"footnote-ref\"><a href=\"#fn1\" id=\"fnref1-2\" name=\"fnref1-2\">1</a></sup>.</p>\n",
+                "\n<section class=\"footnotes\">\n<ol>\n<li>\n<a id=\"fn1\" name=\"fn1\"></a>\n",
+                "<p>A note. <a href=\"#fnref1\" class=\"footnote-backref\">↩</a>",
+                " <a href=\"#fnref1-2\" class=\"footnote-backref\">↩<sup>2</sup></a></p>\n",
+                "</li>\n</ol>\n</section>\n"
+            )
+        );
+    }
+
+    #[test]
+    fn renders_kbd() {
+        let options = ParseOptions::new().kbd(true).build();
+        assert_eq!(
+            to_html(
+                &parse_with_options("[[Ctrl+<]] or [[Esc", &options),
+                &RenderOptions::default()
+            ),
+            "<p><kbd>Ctrl+&lt;</kbd> or [[Esc</p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_lead_paragraphs() {
+        let options = ParseOptions::new().lead_paragraphs(true).build();
+        assert_eq!(
+            to_html(
+                &parse_with_options("^^^ First\n\nSecond ^^^ one", &options),
+                &RenderOptions::default()
+            ),
+            "<p class=\"lead\">First</p>\n\n<p>Second ^^^ one</p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_block_classes() {
+        let options = RenderOptions::new().block_classes(true).build();
+        assert_eq!(
+            to_html(&parse("> A quote"), &options),
+            "<blockquote class=\"blockquote\">\n<p>A quote</p>\n</blockquote>\n"
+        );
+        assert_eq!(
+            to_html(&parse("> [!NOTE]\n> Useful *info*.\n>\n> More."), &options),
+            "<blockquote class=\"admonition admonition-note\">\n\
+             <p class=\"admonition-title\">Note</p>\n\
+             <p>Useful <em>info</em>.</p>\n\n<p>More.</p>\n</blockquote>\n"
+        );
+        // the marker has to be on a line of its own
+        assert_eq!(
+            to_html(&parse("> [!NOTE] inline"), &options),
+            "<blockquote class=\"blockquote\">\n<p>[!NOTE] inline</p>\n</blockquote>\n"
+        );
+        assert_eq!(
+            to_html(&parse("> [!NOTE]\n> Text"), &RenderOptions::default()),
+            "<blockquote>\n<p>[!NOTE]\nText</p>\n</blockquote>\n"
+        );
+    }
+
+    #[test]
+    fn renders_slug_styles() {
+        let md = "# What's new in 2.0? Ünïcode!";
+        let render = |style| to_html(&parse(md), &RenderOptions::new().slug_style(style).build());
+        assert_eq!(
+            render(SlugStyle::GitHub),
+            "<h1 id='whats-new-in-20-ünïcode'>What&#8217;s new in 2.0? Ünïcode!</h1>\n"
+        );
+        assert_eq!(
+            render(SlugStyle::Kebab),
+            "<h1 id='what-s-new-in-2-0-ünïcode'>What&#8217;s new in 2.0? Ünïcode!</h1>\n"
+        );
+        assert_eq!(
+            render(SlugStyle::Underscore),
+            "<h1 id='what&#8217;s_new_in_2.0?_ünïcode!'>What&#8217;s new in 2.0? Ünïcode!</h1>\n"
+        );
+        assert_eq!(
+            render(SlugStyle::Custom(SlugFn::new(|text| text
+                .len()
+                .to_string()))),
+            "<h1 id='29'>What&#8217;s new in 2.0? Ünïcode!</h1>\n"
+        );
+    }
+
+    #[test]
+    fn deduplicates_header_ids() {
+        assert_eq!(
+            to_html(&parse("# A\n# A-1\n# A\n# A"), &RenderOptions::default()),
+            "<h1 id='a'>A</h1>\n\n<h1 id='a-1'>A-1</h1>\n\n\
+             <h1 id='a-2'>A</h1>\n\n<h1 id='a-3'>A</h1>\n"
+        );
+    }
+
+    #[test]
+    fn counts_header_ids_per_document() {
+        let blocks = parse("## Intro\n\n> ## Intro\n\n## Intro");
+        let html = "<h2 id='intro'>Intro</h2>\n\n\
+                    <blockquote>\n<h2 id='intro-1'>Intro</h2>\n</blockquote>\n\n\
+                    <h2 id='intro-2'>Intro</h2>\n";
+        // every call starts counting anew
+        assert_eq!(to_html(&blocks, &RenderOptions::default()), html);
+        assert_eq!(to_html(&blocks, &RenderOptions::default()), html);
+        let ids: Vec<String> = rendered_headings(&blocks)
+            .into_iter()
+            .map(|(_, id, _)| id)
+            .collect();
+        assert_eq!(ids, vec!["intro", "intro-1", "intro-2"]);
+    }
+
+    #[test]
+    fn renders_undefined_shortcut_references_literally() {
+        let render = |md| to_html(&parse(md), &RenderOptions::default());
+        assert_eq!(render("[undefined]"), "<p>[undefined]</p>\n");
+        assert_eq!(
+            render("See [Foo *bar*] here"),
+            "<p>See [Foo *bar*] here</p>\n"
+        );
+        assert_eq!(render("[undefined][]"), "<p>[undefined][]</p>\n");
+        assert_eq!(render("[text][undefined]"), "<p>[text][undefined]</p>\n");
+        assert_eq!(
+            render("[defined]\n\n[defined]: example.com"),
+            "<p><a href=\"example.com\">defined</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_references_built_without_brackets() {
+        // the raw text of a reference isn't necessarily in brackets, e.g. `é` is
+        // longer than one byte at either end
+        let reflink = |raw: &str| {
+            Paragraph(vec![Span::RefLink(
+                vec![Text("é".to_owned())],
+                "é".to_owned(),
+                raw.to_owned(),
+            )])
+        };
+        let options = RenderOptions::default();
+        assert_eq!(render_block(&reflink("é"), &options), "<p>é</p>\n");
+        assert_eq!(render_block(&reflink("éé"), &options), "<p>éé</p>\n");
+        assert_eq!(render_block(&reflink("[é]"), &options), "<p>[é]</p>\n");
+        let options = RenderOptions::new()
+            .reflink_fallback(RefLinkFallback::Label)
+            .build();
+        assert_eq!(render_block(&reflink("éé"), &options), "<p>é</p>\n");
+        assert_eq!(render_block(&reflink("[é]"), &options), "<p>é</p>\n");
+    }
+
+    #[test]
+    fn renders_code_line_numbers() {
+        let options = RenderOptions::new().code_line_numbers(1).build();
+        let html = to_html(&parse("```rust\nfn main() {\n\n}\n```"), &options);
+        assert_eq!(html.matches("<span class=\"line\"").count(), 3);
+        assert_eq!(
+            html,
+            "<pre><code class=\"language-rust\">\
+             <span class=\"line\" data-line=\"1\">fn main() {</span>\n\
+             <span class=\"line\" data-line=\"2\"></span>\n\
+             <span class=\"line\" data-line=\"3\">}</span></code></pre>\n"
+        );
+        assert_eq!(
+            to_html(
+                &parse("    a < b"),
+                &RenderOptions::new().code_line_numbers(10).build()
+            ),
+            "<pre><code><span class=\"line\" data-line=\"10\">a &lt; b</span></code></pre>\n"
+        );
+    }
+
+    #[test]
+    fn renders_paragraph_tags() {
+        let md = "A *label*\n\n^^^ Lead";
+        let blocks = parse_with_options(md, &ParseOptions::new().lead_paragraphs(true).build());
+        assert_eq!(
+            to_html(&blocks, &RenderOptions::new().paragraph_tag(None).build()),
+            "A <em>label</em>\n\nLead\n"
+        );
+        assert_eq!(
+            to_html(
+                &blocks,
+                &RenderOptions::new().paragraph_tag(Some("span")).build()
+            ),
+            "<span>A <em>label</em></span>\n\n<span class=\"lead\">Lead</span>\n"
+        );
+        assert_eq!(
+            to_html(
+                &blocks,
+                &RenderOptions::new()
+                    .paragraph_tag(Some("p onclick=x"))
+                    .build()
+            ),
+            "<p>A <em>label</em></p>\n\n<p class=\"lead\">Lead</p>\n"
+        );
+        assert_eq!(
+            to_html(
+                &blocks,
+                &RenderOptions::new().paragraph_tag(Some("")).build()
+            ),
+            "<p>A <em>label</em></p>\n\n<p class=\"lead\">Lead</p>\n"
+        );
+        assert_eq!(
+            to_html(&blocks, &RenderOptions::default()),
+            "<p>A <em>label</em></p>\n\n<p class=\"lead\">Lead</p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_code_blocks_verbatim() {
+        let blocks = parse("```\na \\\nb  \n\\*c\\*\n```");
+        for hard_break in [HardBreak::SelfClosingBr, HardBreak::Br, HardBreak::Newline] {
+            let options = RenderOptions::new().hard_break(hard_break).build();
+            assert_eq!(
+                to_html(&blocks, &options),
+                "<pre><code>a \\\nb  \n\\*c\\*</code></pre>\n"
+            );
+        }
+    }
+
+    #[test]
+    fn renders_autolinks() {
+        let md = "Go to https://example.com/docs. (See www.example.com/faq)";
+        let options = ParseOptions::new().autolinks(true).build();
+        assert_eq!(
+            to_html(&parse_with_options(md, &options), &RenderOptions::default()),
+            "<p>Go to <a href=\"https://example.com/docs\">example.com/docs</a>. \
+             (See <a href=\"http://www.example.com/faq\">www.example.com/faq</a>)</p>\n"
+        );
+        let options = ParseOptions::new()
+            .autolinks(true)
+            .autolink_text(AutolinkText::Original)
+            .build();
+        assert_eq!(
+            to_html(&parse_with_options(md, &options), &RenderOptions::default()),
+            "<p>Go to <a href=\"https://example.com/docs\">https://example.com/docs</a>. \
+             (See <a href=\"http://www.example.com/faq\">www.example.com/faq</a>)</p>\n"
+        );
+        assert_eq!(
+            to_html(
+                &parse_with_options("[https://a.com](https://b.com)", &options),
+                &RenderOptions::default()
+            ),
+            "<p><a href=\"https://b.com\">https://a.com</a></p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_ordered_list_types() {
+        let render = |md| to_html(&parse(md), &RenderOptions::default());
+        assert_eq!(render("1. x"), "<ol>\n<li>x</li>\n</ol>\n");
+        assert_eq!(render("a. x"), "<ol type=\"a\">\n<li>x</li>\n</ol>\n");
+        assert_eq!(render("A. x"), "<ol type=\"A\">\n<li>x</li>\n</ol>\n");
+        assert_eq!(
+            render("i. x\nii. y"),
+            "<ol type=\"i\">\n<li>x</li>\n\n<li>y</li>\n</ol>\n"
+        );
+        assert_eq!(render("I. x"), "<ol type=\"I\">\n<li>x</li>\n</ol>\n");
+    }
+
+    #[test]
+    fn renders_strikethrough() {
+        assert_eq!(
+            to_html(&parse("~~gone~~ and ~kept~"), &RenderOptions::default()),
+            "<p><del>gone</del> and ~kept~</p>\n"
+        );
+        let options = ParseOptions::new().single_tilde_strikethrough(true).build();
+        assert_eq!(
+            to_html(
+                &parse_with_options("~~gone~~ and ~gone~", &options),
+                &RenderOptions::default()
+            ),
+            "<p><del>gone</del> and <del>gone</del></p>\n"
+        );
+    }
+
+    #[test]
+    fn renders_minified_html() {
+        let blocks = parse("First *a*\n*b*\n\n```\nfn main() {\n\n    x  \n}\n```\n\n> Second");
+        assert_eq!(
+            to_html(&blocks, &RenderOptions::default()),
+            "<p>First <em>a</em>\n<em>b</em></p>\n\n\
+             <pre><code>fn main() {\n\n    x  \n}</code></pre>\n\n\
+             <blockquote>\n<p>Second</p>\n</blockquote>\n"
+        );
+        assert_eq!(
+            to_html(&blocks, &RenderOptions::new().minify(true).build()),
+            "<p>First <em>a</em>\n<em>b</em></p>\
+             <pre><code>fn main() {\n\n    x  \n}</code></pre>\
+             <blockquote><p>Second</p></blockquote>"
+        );
+    }
+
+    #[test]
+    fn keeps_raw_html_when_minifying() {
+        let options = RenderOptions::new().minify(true).unsafe_html(true).build();
+        let blocks = vec![
+            Raw("<pre>\n  a   b\n</pre>".to_owned()),
+            Raw("<div>\n  <p>  c  </p>\n</div>".to_owned()),
+            Comment("\n  <p>  d  </p>\n".to_owned()),
+            Paragraph(vec![
+                Text("some ".to_owned()),
+                Span::Html("<span>".to_owned()),
+                Text(" e ".to_owned()),
+                Span::Html("</span>".to_owned()),
+                Text(" text".to_owned()),
+            ]),
+        ];
+        assert_eq!(
+            to_html(&blocks, &options),
+            "<pre>\n  a   b\n</pre><div>\n  <p>  c  </p>\n</div><!--\n  <p>  d  </p>\n-->\
+             <p>some <span> e </span> text</p>"
+        );
+    }
+
+    #[test]
+    fn keeps_all_text_when_minifying() {
+        let options = RenderOptions::new().minify(true).build();
+        assert_eq!(
+            to_html(&parse("a \u{FDD0} b \u{FDD1}\n\n- c\n- d"), &options),
+            "<p>a \u{FDD0} b \u{FDD1}</p><ul><li>c</li><li>d</li></ul>"
+        );
+        assert_eq!(
+            to_html(&parse("| a |\n|---|\n| b |\n\n[^1]\n\n[^1]: c"), &options),
+            "<table><thead><tr><th>a</th></tr></thead><tbody><tr><td>b</td></tr></tbody></table>\
+             <p><sup class=\"footnote-ref\"><a href=\"#fn1\" id=\"fnref1\">1</a></sup></p>\
+             <section class=\"footnotes\"><ol><li id=\"fn1\"><p>c \
+             <a href=\"#fnref1\" class=\"footnote-backref\">↩</a></p></li></ol></section>"
+        );
+    }
+
+    #[test]
+    fn renders_alt_text_without_markup() {
+        assert_eq!(
+            to_html(
+                &parse("![**bold** alt with [a link](x.com) & `code`](u.png)"),
+                &RenderOptions::default()
+            ),
+            "<p><img src=\"u.png\" alt=\"bold alt with a link &amp; code\" /></p>\n"
+        );
+    }
+}
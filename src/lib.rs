@@ -1,30 +1,90 @@
 //! A crate for parsing Markdown in Rust
+//!
+//! File IO is behind the default `std` feature. Without it the crate is
+//! `no_std` and only needs an allocator, e.g. for embedded or WASM targets.
 #![crate_name = "markdown"]
 #![deny(missing_docs)]
 #![deny(warnings)]
-
-extern crate regex;
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 
 #[macro_use]
-extern crate pipeline;
+extern crate alloc;
+// `no_std` brings `core` into scope already
+#[cfg(any(feature = "std", test))]
+extern crate core;
+extern crate regex;
 
 #[macro_use]
 extern crate lazy_static;
 
+use alloc::string::String;
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
 use std::fs::File;
+#[cfg(feature = "std")]
 use std::io::{self, Read};
+#[cfg(feature = "std")]
 use std::path::Path;
 
+mod document;
+mod extract;
 mod html;
+mod lint;
 mod markdown_generator;
+mod offset;
 mod parser;
+#[cfg(feature = "std")]
+mod reader;
+mod tree;
+mod validate;
 
-pub use parser::{Block, ListItem, Span};
+pub use document::Document;
+pub use extract::{
+    code_blocks, extract_images, is_empty, spans_to_text, ImageRef, PlainTextOptions,
+    PlainTextOptionsBuilder,
+};
+pub use html::{
+    render_block, rendered_headings, rendered_headings_with_options, Container, HardBreak,
+    HeadingCase, RefLinkFallback, RenderOptions, RenderOptionsBuilder, SlugFn, SlugStyle,
+};
+pub use lint::{lint, parse_strict, parse_strict_with_options, LintKind, LintWarning, ParseError};
+pub use markdown_generator::{EmphasisChar, MarkdownOptions, MarkdownOptionsBuilder};
+pub use offset::{utf16_offset, utf16_range};
+pub use parser::{
+    Alignment, AutolinkText, Block, BlockHook, Dimension, Feature, ListItem, ObjectSize, ParseMode,
+    ParseOptions, ParseOptionsBuilder, Span, SpanHook, TaskState, UnorderedListType,
+};
+#[cfg(feature = "std")]
+pub use reader::{parse_reader, parse_reader_with_options};
+pub use tree::debug_tree;
+pub use validate::{validate, Violation};
 
 /// Converts a Markdown string to HTML
 pub fn to_html(text: &str) -> String {
+    to_html_with_options(text, &RenderOptions::default())
+}
+
+/// Converts a Markdown string to HTML using the given rendering options
+pub fn to_html_with_options(text: &str, options: &RenderOptions) -> String {
     let result = parser::parse(text);
-    html::to_html(&result)
+    html::to_html(&result, options)
+}
+
+/// Converts a Markdown string of inline markup only to HTML, e.g. for a table
+/// cell or a comment preview
+///
+/// Emphasis, code, links, images and other spans are rendered, but blocks
+/// aren't parsed and nothing is wrapped in a `<p>`. Reference-style links
+/// fall back to text, since there are no definitions to resolve them with.
+pub fn render_inline(text: &str) -> String {
+    render_inline_with_options(text, &RenderOptions::default())
+}
+
+/// Converts a Markdown string of inline markup only to HTML using the given
+/// rendering options, see `render_inline`
+pub fn render_inline_with_options(text: &str, options: &RenderOptions) -> String {
+    let spans = parser::parse_inline(text, &ParseOptions::default());
+    html::render_spans(&spans, options)
 }
 
 /// Converts a Markdown string to a tokenset of Markdown items
@@ -32,18 +92,33 @@ pub fn tokenize(text: &str) -> Vec<Block> {
     parser::parse(text)
 }
 
+/// Parses a Markdown string into a `Document`
+pub fn parse(text: &str) -> Document {
+    Document::from(parser::parse(text))
+}
+
+/// Parses a Markdown string into a `Document` using the given parsing options
+pub fn parse_with_options(text: &str, options: &ParseOptions) -> Document {
+    Document::from(parser::parse_with_options(text, options))
+}
+
 /// Convert tokenset of Markdown items back to String
 pub fn generate_markdown(x: Vec<Block>) -> String {
-    markdown_generator::generate(x)
+    generate_markdown_with_options(x, &MarkdownOptions::default())
+}
+
+/// Convert tokenset of Markdown items back to String using the given options
+pub fn generate_markdown_with_options(x: Vec<Block>, options: &MarkdownOptions) -> String {
+    markdown_generator::generate(x, options)
 }
 
 /// Opens a file and converts its contents to HTML
+#[cfg(feature = "std")]
 pub fn file_to_html(path: &Path) -> io::Result<String> {
     let mut file = File::open(path)?;
 
     let mut text = String::new();
     file.read_to_string(&mut text)?;
 
-    let result = parser::parse(&text);
-    Ok(html::to_html(&result))
+    Ok(to_html(&text))
 }
@@ -0,0 +1,245 @@
+use alloc::collections::BTreeSet;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+use core::slice;
+use regex::Regex;
+
+use extract::{walk_blocks, walk_spans};
+use parser::{Block, ParseOptions, Span};
+use validate::parse_with_ranges;
+
+/// A likely mistake found by `lint`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct LintWarning {
+    /// The kind of mistake
+    pub kind: LintKind,
+    /// The byte range of the top-level block the mistake appears in
+    ///
+    /// Use `utf16_range` to get the range in UTF-16 code units editors use.
+    pub range: Range<usize>,
+}
+
+/// The kinds of mistakes `lint` reports
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub enum LintKind {
+    /// A `*` or `_` run that could open emphasis, but is never closed
+    ///
+    /// It follows whitespace or punctuation and precedes a character that
+    /// isn't whitespace, like in `an *unclosed emphasis`.
+    UnclosedEmphasis,
+    /// A backtick that doesn't start a code span, because no backtick run of
+    /// the same length closes it
+    UnterminatedCodeSpan,
+    /// A code fence without a closing fence, which is read as part of a paragraph
+    ///
+    /// Only fences in top-level paragraphs are found, not ones nested in
+    /// blockquotes or list items.
+    UnterminatedCodeFence,
+    /// A reference link like `[text][id]` or `[text][]` whose reference
+    /// isn't defined, holding the reference
+    ///
+    /// Shortcut references like `[text]` aren't reported, since brackets are
+    /// just as often meant as text.
+    UndefinedReference(String),
+}
+
+/// Finds constructs in a Markdown string that fell back to text, because
+/// they aren't closed or their reference isn't defined
+///
+/// The warnings are returned in document order. Like with `validate`, every
+/// warning points at the top-level block it appears in.
+pub fn lint(md: &str, options: &ParseOptions) -> Vec<LintWarning> {
+    lint_blocks(md, &parse_with_ranges(md, options))
+}
+
+/// An error found by `parse_strict`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct ParseError {
+    /// Why the document was rejected
+    pub reason: LintKind,
+    /// The byte range of the top-level block the error appears in
+    pub range: Range<usize>,
+}
+
+/// Parses a Markdown string, failing if any construct fell back to text
+///
+/// This is a stricter variant of `tokenize` for linting. The constructs it
+/// rejects are the ones `lint` warns about, like unterminated code fences or
+/// undefined references. It has nothing to do with `ParseMode::Strict`.
+pub fn parse_strict(md: &str) -> Result<Vec<Block>, Vec<ParseError>> {
+    parse_strict_with_options(md, &ParseOptions::default())
+}
+
+/// Parses a Markdown string using the given parsing options, failing if any
+/// construct fell back to text, see `parse_strict`
+pub fn parse_strict_with_options(
+    md: &str,
+    options: &ParseOptions,
+) -> Result<Vec<Block>, Vec<ParseError>> {
+    let blocks = parse_with_ranges(md, options);
+    let errors: Vec<ParseError> = lint_blocks(md, &blocks)
+        .into_iter()
+        .map(|warning| ParseError {
+            reason: warning.kind,
+            range: warning.range,
+        })
+        .collect();
+    if !errors.is_empty() {
+        return Err(errors);
+    }
+    Ok(blocks.into_iter().map(|(block, _)| block).collect())
+}
+
+fn lint_blocks(md: &str, blocks: &[(Block, Range<usize>)]) -> Vec<LintWarning> {
+    lazy_static! {
+        static ref FENCE: Regex = Regex::new(r"(?m)^ {0,3}(?:`{3,}|~{3,})").unwrap();
+    }
+
+    let mut references = BTreeSet::new();
+    for (block, _) in blocks.iter() {
+        walk_blocks(slice::from_ref(block), &mut |block| {
+            if let Block::LinkReference(ref id, _, _) = *block {
+                references.insert(id.to_lowercase());
+            }
+        });
+    }
+
+    let mut warnings = vec![];
+    for (block, range) in blocks {
+        let mut report = |kind| {
+            warnings.push(LintWarning {
+                kind,
+                range: range.clone(),
+            })
+        };
+
+        // a fence that isn't closed doesn't start a code block, so its line
+        // ends up in a paragraph, along with the backticks of the fence
+        let unterminated_fence = match block {
+            Block::Paragraph(_) | Block::LeadParagraph(_) => FENCE.is_match(&md[range.clone()]),
+            _ => false,
+        };
+        if unterminated_fence {
+            report(LintKind::UnterminatedCodeFence);
+        }
+
+        walk_spans(slice::from_ref(block), &mut |span| match *span {
+            Span::Text(ref text) => {
+                if text.contains('`') && !unterminated_fence {
+                    report(LintKind::UnterminatedCodeSpan);
+                }
+                if opens_emphasis(text) {
+                    report(LintKind::UnclosedEmphasis);
+                }
+            }
+            Span::RefLink(_, ref reference, ref raw)
+                if raw.ends_with(']') && raw.contains("][") && !references.contains(reference) =>
+            {
+                report(LintKind::UndefinedReference(reference.clone()))
+            }
+            _ => {}
+        });
+    }
+    warnings
+}
+
+// whether text contains a `*` or `_` run that could open emphasis
+fn opens_emphasis(text: &str) -> bool {
+    let mut prev: Option<char> = None;
+    let mut chars = text.chars().peekable();
+    while let Some(c) = chars.next() {
+        if (c == '*' || c == '_') && prev != Some(c) {
+            let after_boundary =
+                prev.is_none_or(|prev| prev.is_whitespace() || prev.is_ascii_punctuation());
+            // skip to the end of the run
+            while chars.peek() == Some(&c) {
+                chars.next();
+            }
+            if after_boundary && chars.peek().is_some_and(|next| !next.is_whitespace()) {
+                return true;
+            }
+        }
+        prev = Some(c);
+    }
+    false
+}
+
+#[cfg(test)]
+mod test {
+    use super::{lint, parse_strict, LintKind, LintWarning, ParseError};
+    use parser::Block::Paragraph;
+    use parser::ParseOptions;
+    use parser::Span::Text;
+
+    #[test]
+    fn warns_about_unterminated_code_fences() {
+        let md = "Intro\n\n```rust\nfn main() {}\n";
+        assert_eq!(
+            lint(md, &ParseOptions::default()),
+            vec![LintWarning {
+                kind: LintKind::UnterminatedCodeFence,
+                range: 7..27
+            }]
+        );
+    }
+
+    #[test]
+    fn warns_about_undefined_references() {
+        let md = "See [the docs][docs] and [this][], [defined][a] or [brackets].\n\n[a]: /a\n";
+        assert_eq!(
+            lint(md, &ParseOptions::default()),
+            vec![
+                LintWarning {
+                    kind: LintKind::UndefinedReference("docs".to_owned()),
+                    range: 0..62
+                },
+                LintWarning {
+                    kind: LintKind::UndefinedReference("this".to_owned()),
+                    range: 0..62
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn warns_about_unclosed_spans() {
+        let options = ParseOptions::default();
+        assert_eq!(
+            lint("An *unclosed emphasis", &options)[0].kind,
+            LintKind::UnclosedEmphasis
+        );
+        assert_eq!(
+            lint("Some `code", &options)[0].kind,
+            LintKind::UnterminatedCodeSpan
+        );
+        assert_eq!(
+            lint(
+                "2 * 3, snake_case, \\*escaped, *closed* and `code`",
+                &options
+            ),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn parses_strictly() {
+        assert_eq!(
+            parse_strict("Some text"),
+            Ok(vec![Paragraph(vec![Text("Some text".to_owned())])])
+        );
+        assert_eq!(
+            parse_strict("See [the docs][docs].\n\n```rust\nfn main() {}\n"),
+            Err(vec![
+                ParseError {
+                    reason: LintKind::UndefinedReference("docs".to_owned()),
+                    range: 0..21
+                },
+                ParseError {
+                    reason: LintKind::UnterminatedCodeFence,
+                    range: 23..43
+                }
+            ])
+        );
+    }
+}
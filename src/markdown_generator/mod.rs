@@ -1,4 +1,127 @@
-use super::{Block, ListItem, Span};
+use super::{Alignment, Block, Dimension, ListItem, ObjectSize, Span, TaskState};
+use alloc::borrow::ToOwned;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::iter;
+use extract::walk_blocks;
+use parser::{is_comment_text, OrderedListType, UnorderedListType};
+
+/// Options that control how blocks are written back to Markdown
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct MarkdownOptions {
+    /// The number of blank lines written between two blocks, 1 by default
+    ///
+    /// This also applies to the blocks nested in blockquotes, list items and
+    /// the like. Note that without any blank line, consecutive paragraphs are
+    /// read back as a single one.
+    pub blank_lines_between_blocks: usize,
+    /// Write all link references after the last block instead of where they are defined
+    ///
+    /// They're written in the order they're defined in, including the ones
+    /// defined inside of blockquotes and list items.
+    pub link_references_at_end: bool,
+    /// Number the items of ordered lists sequentially, e.g. `1.`, `2.`, `3.`
+    ///
+    /// Otherwise every item is written with the number it was written with
+    /// in the source, e.g. `2.`, `5.`, `9.`. Items of lists built without
+    /// numbers are written with the first number (`1.`, `a.`, `i.`, ...).
+    pub renumber_ordered_lists: bool,
+    /// The character emphasis and strong emphasis are written with, `*` by default
+    ///
+    /// The other character is used where the configured one would merge with
+    /// a delimiter next to it, e.g. for an emphasis nested in another one.
+    /// Emphasis within a word always uses `*`.
+    pub emphasis_char: EmphasisChar,
+    /// The bullet all unordered lists are written with, instead of the one
+    /// they were written with originally
+    ///
+    /// Note that adjacent lists are only told apart by their bullets, so
+    /// they're read back as a single list when they get the same one.
+    pub bullet: Option<UnorderedListType>,
+}
+
+/// The characters emphasis can be written with, see `MarkdownOptions::emphasis_char`
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum EmphasisChar {
+    /// `*emphasis*` and `**strong emphasis**`
+    #[default]
+    Asterisk,
+    /// `_emphasis_` and `__strong emphasis__`
+    Underscore,
+}
+
+impl EmphasisChar {
+    /// Returns the delimiter character
+    pub fn to_char(self) -> char {
+        match self {
+            EmphasisChar::Asterisk => '*',
+            EmphasisChar::Underscore => '_',
+        }
+    }
+}
+
+impl Default for MarkdownOptions {
+    fn default() -> MarkdownOptions {
+        MarkdownOptions {
+            blank_lines_between_blocks: 1,
+            link_references_at_end: false,
+            renumber_ordered_lists: false,
+            emphasis_char: EmphasisChar::default(),
+            bullet: None,
+        }
+    }
+}
+
+impl MarkdownOptions {
+    /// Starts building a set of options from the defaults
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> MarkdownOptionsBuilder {
+        MarkdownOptionsBuilder::default()
+    }
+}
+
+/// A builder for `MarkdownOptions`, created with `MarkdownOptions::new()`
+#[derive(Debug, Default, Clone)]
+pub struct MarkdownOptionsBuilder {
+    options: MarkdownOptions,
+}
+
+impl MarkdownOptionsBuilder {
+    /// Sets the number of blank lines written between two blocks
+    pub fn blank_lines_between_blocks(mut self, blank_lines: usize) -> Self {
+        self.options.blank_lines_between_blocks = blank_lines;
+        self
+    }
+
+    /// Sets whether link references are written after the last block
+    pub fn link_references_at_end(mut self, link_references_at_end: bool) -> Self {
+        self.options.link_references_at_end = link_references_at_end;
+        self
+    }
+
+    /// Sets whether the items of ordered lists are numbered sequentially
+    pub fn renumber_ordered_lists(mut self, renumber_ordered_lists: bool) -> Self {
+        self.options.renumber_ordered_lists = renumber_ordered_lists;
+        self
+    }
+
+    /// Sets the character emphasis is written with
+    pub fn emphasis_char(mut self, emphasis_char: EmphasisChar) -> Self {
+        self.options.emphasis_char = emphasis_char;
+        self
+    }
+
+    /// Sets the bullet all unordered lists are written with
+    pub fn bullet(mut self, bullet: UnorderedListType) -> Self {
+        self.options.bullet = Some(bullet);
+        self
+    }
+
+    /// Returns the finished options
+    pub fn build(self) -> MarkdownOptions {
+        self.options
+    }
+}
 
 trait JoinHelper<I>
 where
@@ -16,68 +139,273 @@ where
     }
 }
 
-fn gen_block(b: Block) -> String {
+fn gen_block(b: Block, options: &MarkdownOptions) -> String {
     use Block::*;
     match b {
-        Header(s, level) => format!(
-            "{} {}",
-            ::std::iter::repeat("#".to_string()).take(level).j(""),
-            generate_from_spans(s)
-        ),
-        Paragraph(s) => generate_from_spans(s),
-        Blockquote(bb) => generate(bb).lines().map(|x| format!("> {}", x)).j("\n"),
+        Header(s, level) => format!("{} {}", "#".repeat(level), generate_from_spans(s, options)),
+        Paragraph(s) => generate_from_spans(s, options),
+        LeadParagraph(s) => format!("^^^ {}", generate_from_spans(s, options)),
+        Blockquote(bb) => gen_blocks(bb, options)
+            .lines()
+            .map(|x| format!("> {}", x))
+            .j("\n"),
         CodeBlock(lang, x) => {
-            if lang.is_none() {
-                x.lines().map(|x| format!("    {}", x)).j("\n")
+            if let Some(lang) = lang {
+                format!("```{}\n{}```", lang, x)
             } else {
-                format!("```{}\n{}```", lang.unwrap(), x)
+                x.lines().map(|x| format!("    {}", x)).j("\n")
             }
         }
-        // [TODO]: Ordered list generation - 2017-12-10 10:12pm
-        OrderedList(_x, _num_type) => unimplemented!("Generate ordered list"),
-        UnorderedList(x) => generate_from_li(x),
+        OrderedList(x, num_type, numbers) => {
+            let markers = list_markers(&num_type, &numbers, options.renumber_ordered_lists);
+            generate_from_li(x, markers, options)
+        }
+        UnorderedList(x, bullet) => {
+            let bullet = options.bullet.unwrap_or(bullet);
+            generate_from_li(x, iter::repeat(bullet.to_char().to_string()), options)
+        }
+        FootnoteDefinition(id, x) => format!(
+            "[^{}]: {}",
+            id,
+            gen_blocks(x, options)
+                .lines()
+                .enumerate()
+                .map(|(i, x)| if i == 0 || x.is_empty() {
+                    x.to_string()
+                } else {
+                    format!("    {}", x)
+                })
+                .j("\n")
+        ),
         LinkReference(id, url, None) => format!("[{}]: {}", id, url),
         LinkReference(id, url, Some(title)) => format!("[{}]: {} \"{}\"", id, url, title),
+        Comment(x) => gen_comment(x),
+        Details(summary, x) => format!(
+            "<details>\n<summary>{}</summary>\n\n{}\n\n</details>",
+            generate_from_spans(summary, options),
+            gen_blocks(x, options)
+        ),
+        Div(classes, x) => {
+            let attributes = match classes.as_slice() {
+                [class] => class.clone(),
+                _ => format!("{{{}}}", classes.iter().map(|c| format!(".{}", c)).j(" ")),
+            };
+            format!("::: {}\n\n{}\n\n:::", attributes, gen_blocks(x, options))
+        }
         Raw(x) => x,
         Hr => "===".to_owned(),
+        LineBlock(lines) => lines
+            .into_iter()
+            .map(|line| {
+                format!("| {}", generate_from_spans(line, options))
+                    .trim_end()
+                    .to_owned()
+            })
+            .j("\n"),
+        Table(alignments, header, rows) => {
+            let delimiters = alignments
+                .iter()
+                .map(|alignment| match alignment {
+                    Alignment::None => "---",
+                    Alignment::Left => ":--",
+                    Alignment::Center => ":-:",
+                    Alignment::Right => "--:",
+                })
+                .map(str::to_owned);
+            let mut lines = vec![
+                gen_row(header, options),
+                format!("| {} |", delimiters.j(" | ")),
+            ];
+            lines.extend(rows.into_iter().map(|row| gen_row(row, options)));
+            lines.join("\n")
+        }
     }
 }
 
-fn gen_span(s: Span) -> String {
+fn gen_row(cells: Vec<Vec<Span>>, options: &MarkdownOptions) -> String {
+    format!(
+        "| {} |",
+        cells
+            .into_iter()
+            .map(|cell| generate_from_spans(cell, options))
+            .j(" | ")
+    )
+}
+
+// `delimiter` is the character used for emphasis and strong emphasis
+fn gen_span(s: Span, delimiter: char, options: &MarkdownOptions) -> String {
     use Span::*;
     match s {
         Break => "  \n".to_string(),
         Text(x) => x,
         Literal(x) => format!("\\{}", x),
         Code(x) => format!("`{}`", x),
-        Link(a, b, None) => format!("[{}]({})", generate_from_spans(a), b),
-        Link(a, b, Some(c)) => format!("[{}]({} \"{}\")", generate_from_spans(a), b, c),
+        Kbd(x) => format!("[[{}]]", x),
+        Comment(x) => gen_comment(x),
+        Html(x) => x,
+        Link(a, b, None) => format!("[{}]({})", generate_from_spans(a, options), gen_url(b)),
+        Link(a, b, Some(c)) => format!(
+            "[{}]({} \"{}\")",
+            generate_from_spans(a, options),
+            gen_url(b),
+            c
+        ),
+        Object(a, b, title, size) => format!(
+            "[{}]({}{}{})",
+            generate_from_spans(a, options),
+            gen_url(b),
+            gen_title(title),
+            gen_size(Some(size))
+        ),
         RefLink(_, _, raw) => raw,
-        Image(a, b, None) => format!("![{}]({})", a, b),
-        Image(a, b, Some(c)) => format!("![{}]({} \"{}\")", a, b, c),
-        Emphasis(x) => format!("*{}*", generate_from_spans(x)),
-        Strong(x) => format!("**{}**", generate_from_spans(x)),
+        FootnoteReference(id) => format!("[^{}]", id),
+        InlineFootnote(x) => format!("^[{}]", generate_from_spans(x, options)),
+        Image(a, b, title, size) => {
+            format!(
+                "![{}]({}{}{})",
+                a,
+                gen_url(b),
+                gen_title(title),
+                gen_size(size)
+            )
+        }
+        Emphasis(x) => format!(
+            "{0}{1}{0}",
+            delimiter,
+            gen_spans(x, Some(delimiter), options)
+        ),
+        Strong(x) => format!(
+            "{0}{0}{1}{0}{0}",
+            delimiter,
+            gen_spans(x, Some(delimiter), options)
+        ),
+        Highlight(x) => format!("=={}==", generate_from_spans(x, options)),
+        Strikethrough(x) => format!("~~{}~~", generate_from_spans(x, options)),
+    }
+}
+
+// text that would end a comment early is written with `<` as a character
+// reference, so that it's read back as text instead of HTML
+fn gen_comment(text: String) -> String {
+    if is_comment_text(&text) {
+        format!("<!--{}-->", text)
+    } else {
+        format!("&lt;!--{}-->", text)
     }
 }
 
-fn generate_from_li(data: Vec<ListItem>) -> String {
+// urls containing whitespace have to be wrapped in angle brackets
+fn gen_url(url: String) -> String {
+    if url.contains(char::is_whitespace) {
+        format!("<{}>", url)
+    } else {
+        url
+    }
+}
+
+fn gen_title(title: Option<String>) -> String {
+    title.map_or(String::new(), |t| format!(" \"{}\"", t))
+}
+
+fn gen_size(size: Option<ObjectSize>) -> String {
+    size.map_or(String::new(), |s| {
+        let dimension = |d: Option<Dimension>| d.map_or(String::new(), |d| d.to_string());
+        format!(" ={}x{}", dimension(s.width), dimension(s.height))
+    })
+}
+
+// The markers of the items of an ordered list, either with the numbers they
+// were written with or numbered sequentially. Items without a number, e.g.
+// of a list that wasn't parsed, get the first number.
+pub fn list_markers<'a>(
+    num_type: &'a OrderedListType,
+    numbers: &'a [String],
+    renumber: bool,
+) -> impl Iterator<Item = String> + 'a {
+    (0..).map(move |i| match numbers.get(i) {
+        Some(number) if !renumber => format!("{}.", number),
+        _ => gen_numbering(num_type, if renumber { i + 1 } else { 1 }),
+    })
+}
+
+// the marker of the `n`th item of an ordered list, e.g. `3.`, `c.` or `iii.`
+fn gen_numbering(num_type: &OrderedListType, n: usize) -> String {
+    let numbering = match *num_type {
+        OrderedListType::Numeric => n.to_string(),
+        OrderedListType::Lowercase => letter(n).to_string(),
+        OrderedListType::Uppercase => letter(n).to_ascii_uppercase().to_string(),
+        OrderedListType::LowercaseRoman => roman(n),
+        OrderedListType::UppercaseRoman => roman(n).to_uppercase(),
+    };
+    format!("{}.", numbering)
+}
+
+// lists numbered with letters are read as single letters, so they stop at `z`
+fn letter(n: usize) -> char {
+    (b'a' + (n.clamp(1, 26) - 1) as u8) as char
+}
+
+fn roman(mut n: usize) -> String {
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "m"),
+        (900, "cm"),
+        (500, "d"),
+        (400, "cd"),
+        (100, "c"),
+        (90, "xc"),
+        (50, "l"),
+        (40, "xl"),
+        (10, "x"),
+        (9, "ix"),
+        (5, "v"),
+        (4, "iv"),
+        (1, "i"),
+    ];
+    let mut ret = String::new();
+    for &(value, numeral) in NUMERALS.iter() {
+        while n >= value {
+            ret.push_str(numeral);
+            n -= value;
+        }
+    }
+    ret
+}
+
+// `markers` holds the bullet or number of each item, the lines following the
+// first one of an item are indented up to its content
+fn generate_from_li<I>(data: Vec<ListItem>, markers: I, options: &MarkdownOptions) -> String
+where
+    I: Iterator<Item = String>,
+{
     use ListItem::*;
 
     data.into_iter()
-        .map(|x| {
+        .zip(markers)
+        .map(|(x, marker)| {
+            let indent = " ".repeat(marker.len() + 1);
             format!(
-                "* {}",
+                "{} {}",
+                marker,
                 match x {
-                    Simple(x) => generate_from_spans(x),
+                    Simple(x) => generate_from_spans(x, options),
+                    Task(state, x) => format!(
+                        "[{}] {}",
+                        match state {
+                            TaskState::Unchecked => ' ',
+                            TaskState::Checked => 'x',
+                            TaskState::Indeterminate => '-',
+                        },
+                        generate_from_spans(x, options)
+                    ),
                     Paragraph(x) => format!(
                         "{}\n",
-                        generate(x)
+                        gen_blocks(x, options)
                             .lines()
                             .enumerate()
-                            .map(|(i, x)| if i == 0 {
+                            .map(|(i, x)| if i == 0 || x.is_empty() {
                                 x.to_string()
                             } else {
-                                format!("    {}", x)
+                                format!("{}{}", indent, x)
                             })
                             .j("\n")
                     ),
@@ -87,10 +415,254 @@ fn generate_from_li(data: Vec<ListItem>) -> String {
         .j("\n")
 }
 
-fn generate_from_spans(data: Vec<Span>) -> String {
-    data.into_iter().map(gen_span).j("")
+fn generate_from_spans(data: Vec<Span>, options: &MarkdownOptions) -> String {
+    gen_spans(data, None, options)
 }
 
-pub fn generate(data: Vec<Block>) -> String {
-    data.into_iter().map(gen_block).j("\n\n")
+// Emphasis right next to delimiters of the same character would merge with
+// them, e.g. `***a***` can't tell `Strong(Emphasis(a))` from `Emphasis(Strong(a))`
+// and `*a**b*` isn't two emphases. So the configured delimiter switches to the
+// other one when the spans are nested in an emphasis using it (`outer`) or
+// follow one, and back again. Underscores inside of a word don't start or end
+// emphasis in strict mode, so emphasis within a word always uses `*`.
+fn gen_spans(data: Vec<Span>, outer: Option<char>, options: &MarkdownOptions) -> String {
+    let preferred = options.emphasis_char.to_char();
+    let last = data.len().saturating_sub(1);
+    let mut ret = String::new();
+    let mut data = data.into_iter().enumerate().peekable();
+    while let Some((i, span)) = data.next() {
+        let before = if i == 0 { outer } else { ret.chars().last() };
+        let after = match data.peek() {
+            Some((_, Span::Text(text))) => text.chars().next(),
+            _ if i == last => outer,
+            _ => None,
+        };
+        let intraword =
+            before.is_some_and(char::is_alphanumeric) || after.is_some_and(char::is_alphanumeric);
+        let delimiters = if preferred == '_' && !intraword {
+            ['_', '*']
+        } else {
+            ['*', '_']
+        };
+        let delimiter = delimiters
+            .iter()
+            .cloned()
+            .find(|&d| before != Some(d) && after != Some(d))
+            .unwrap_or(delimiters[0]);
+        ret.push_str(&gen_span(span, delimiter, options));
+    }
+    ret
+}
+
+pub fn generate(data: Vec<Block>, options: &MarkdownOptions) -> String {
+    if !options.link_references_at_end {
+        return gen_blocks(data, options);
+    }
+
+    let mut references = vec![];
+    walk_blocks(&data, &mut |block| {
+        if let Block::LinkReference(..) = *block {
+            references.push(block.clone());
+        }
+    });
+    let blocks = gen_blocks(data, options);
+    let references = references
+        .into_iter()
+        .map(|reference| gen_block(reference, options))
+        .j("\n");
+    match (blocks.is_empty(), references.is_empty()) {
+        (_, true) => blocks,
+        (true, false) => references,
+        (false, false) => format!(
+            "{}{}{}",
+            blocks,
+            "\n".repeat(options.blank_lines_between_blocks + 1),
+            references
+        ),
+    }
+}
+
+fn gen_blocks(data: Vec<Block>, options: &MarkdownOptions) -> String {
+    let separator = "\n".repeat(options.blank_lines_between_blocks + 1);
+    data.into_iter()
+        .filter(|b| !(options.link_references_at_end && matches!(b, Block::LinkReference(..))))
+        .map(|b| gen_block(b, options))
+        .collect::<Vec<String>>()
+        .join(&separator)
+}
+
+#[cfg(test)]
+mod test {
+    use super::{generate, EmphasisChar, MarkdownOptions};
+    use html::to_html;
+    use parser::parse;
+    use parser::Block::{OrderedList, Paragraph};
+    use parser::Span::{Comment, Emphasis, Strong, Text};
+    use parser::{ListItem, OrderedListType, UnorderedListType};
+    use RenderOptions;
+
+    #[test]
+    fn separates_blocks_with_one_blank_line() {
+        assert_eq!(
+            generate(parse("# a\n\n\n\nb\n\nc"), &MarkdownOptions::default()),
+            "# a\n\nb\n\nc"
+        );
+    }
+
+    #[test]
+    fn separates_blocks_with_configured_blank_lines() {
+        let options = MarkdownOptions::new().blank_lines_between_blocks(2).build();
+        assert_eq!(
+            generate(parse("# a\n\nb\n\n> c\n>\n> d"), &options),
+            "# a\n\n\nb\n\n\n> c\n> \n> \n> d"
+        );
+    }
+
+    #[test]
+    fn escapes_malformed_comments() {
+        let blocks = vec![Paragraph(vec![Comment(" a --!><b> ".to_owned())])];
+        let md = generate(blocks, &MarkdownOptions::default());
+        assert_eq!(md, "&lt;!-- a --!><b> -->");
+        assert_eq!(
+            to_html(&parse(&md), &RenderOptions::default()),
+            "<p>&lt;!-- a --!&gt;&lt;b&gt; --&gt;</p>\n"
+        );
+    }
+
+    #[test]
+    fn keeps_list_bullets() {
+        let md = "- a\n- b\n\n* c\n\n+ d";
+        assert_eq!(generate(parse(md), &MarkdownOptions::default()), md);
+    }
+
+    #[test]
+    fn nested_emphasis_round_trips() {
+        let text = |t: &str| Text(t.to_owned());
+        let cases = vec![
+            Strong(vec![Emphasis(vec![text("x")])]),
+            Emphasis(vec![Strong(vec![text("x")])]),
+            Emphasis(vec![Emphasis(vec![text("x")])]),
+            Strong(vec![Strong(vec![text("x")])]),
+            Emphasis(vec![Strong(vec![Emphasis(vec![text("x")])])]),
+            Strong(vec![text("a "), Emphasis(vec![text("b")])]),
+            Emphasis(vec![Emphasis(vec![text("a")]), text(" b")]),
+        ];
+        for span in cases {
+            let blocks = vec![Paragraph(vec![span])];
+            let md = generate(blocks.clone(), &MarkdownOptions::default());
+            assert_eq!(parse(&md), blocks, "{}", md);
+        }
+
+        let siblings = vec![Paragraph(vec![
+            Emphasis(vec![text("a")]),
+            Emphasis(vec![text("b")]),
+        ])];
+        let md = generate(siblings.clone(), &MarkdownOptions::default());
+        assert_eq!(md, "*a*_b_");
+        assert_eq!(parse(&md), siblings);
+    }
+
+    #[test]
+    fn writes_configured_emphasis_and_bullets() {
+        let blocks =
+            parse("Some *emphasis*, **strong** and *nested **strong***\n\n* a\n* b\n\n+ c");
+        let options = MarkdownOptions::new()
+            .emphasis_char(EmphasisChar::Underscore)
+            .bullet(UnorderedListType::Dash)
+            .build();
+        assert_eq!(
+            generate(blocks.clone(), &options),
+            "Some _emphasis_, __strong__ and _nested **strong**_\n\n- a\n- b\n\n- c"
+        );
+        let options = MarkdownOptions::new()
+            .emphasis_char(EmphasisChar::Asterisk)
+            .bullet(UnorderedListType::Asterisk)
+            .build();
+        assert_eq!(
+            generate(blocks, &options),
+            "Some *emphasis*, **strong** and *nested __strong__*\n\n* a\n* b\n\n* c"
+        );
+
+        // underscores within a word don't start emphasis in strict mode
+        let options = MarkdownOptions::new()
+            .emphasis_char(EmphasisChar::Underscore)
+            .build();
+        assert_eq!(generate(parse("in*word*s"), &options), "in*word*s");
+    }
+
+    #[test]
+    fn divs_round_trip() {
+        let blocks = parse("::: {.note .wide}\n::: warning\nText\n:::\n:::");
+        let md = generate(blocks.clone(), &MarkdownOptions::default());
+        assert_eq!(md, "::: {.note .wide}\n\n::: warning\n\nText\n\n:::\n\n:::");
+        assert_eq!(parse(&md), blocks);
+    }
+
+    #[test]
+    fn objects_round_trip() {
+        let md = "[a *video*](movie.mp4 \"Trailer\" =640x50%)";
+        assert_eq!(generate(parse(md), &MarkdownOptions::default()), md);
+    }
+
+    #[test]
+    fn keeps_link_references() {
+        let md = "[a][1] and [b][2]\n\n[1]: /one\n\n> [c][3]\n> \n> [3]: /three \"Three\"\n\n[2]: /two \"Two\"";
+        let blocks = parse(md);
+        assert_eq!(generate(blocks.clone(), &MarkdownOptions::default()), md);
+
+        let options = MarkdownOptions::new().link_references_at_end(true).build();
+        let generated = generate(blocks.clone(), &options);
+        assert_eq!(
+            generated,
+            "[a][1] and [b][2]\n\n> [c][3]\n\n[1]: /one\n[3]: /three \"Three\"\n[2]: /two \"Two\""
+        );
+        assert_eq!(
+            to_html(&parse(&generated), &RenderOptions::default()),
+            to_html(&blocks, &RenderOptions::default())
+        );
+    }
+
+    #[test]
+    fn renumbers_ordered_lists() {
+        let blocks = parse("1. a\n1. b\n1. c");
+        assert_eq!(
+            generate(blocks.clone(), &MarkdownOptions::default()),
+            "1. a\n1. b\n1. c"
+        );
+        let options = MarkdownOptions::new().renumber_ordered_lists(true).build();
+        assert_eq!(generate(blocks, &options), "1. a\n2. b\n3. c");
+
+        let roman = parse("i. a\nii. b\niii. c\niv. d");
+        assert_eq!(generate(roman, &options), "i. a\nii. b\niii. c\niv. d");
+        let letters = parse("A. a\nA. b");
+        assert_eq!(generate(letters, &options), "A. a\nB. b");
+    }
+
+    #[test]
+    fn keeps_ordered_list_numbers() {
+        let blocks = parse("2. a\n5. b\n9. c\n\n- d\n\nc. e\nd. f");
+        assert_eq!(
+            generate(blocks.clone(), &MarkdownOptions::default()),
+            "2. a\n5. b\n9. c\n\n- d\n\nc. e\nd. f"
+        );
+        let options = MarkdownOptions::new().renumber_ordered_lists(true).build();
+        assert_eq!(
+            generate(blocks, &options),
+            "1. a\n2. b\n3. c\n\n- d\n\na. e\nb. f"
+        );
+
+        let built = vec![OrderedList(
+            vec![ListItem::Simple(vec![Text("a".to_owned())])],
+            OrderedListType::Numeric,
+            vec![],
+        )];
+        assert_eq!(generate(built, &MarkdownOptions::default()), "1. a");
+    }
+
+    #[test]
+    fn indents_ordered_list_items_up_to_their_content() {
+        let blocks = parse("1. a\n\n   b\n2. c");
+        let options = MarkdownOptions::new().renumber_ordered_lists(true).build();
+        assert_eq!(generate(blocks, &options), "1. a\n\n   b\n\n2. c");
+    }
 }
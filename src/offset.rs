@@ -0,0 +1,44 @@
+use core::ops::Range;
+
+/// Converts a byte offset into `source` to an offset in UTF-16 code units
+///
+/// Editors and the Language Server Protocol count positions in UTF-16 code
+/// units, so characters outside the Basic Multilingual Plane like emoji count
+/// twice there, while other non-ASCII characters count once instead of two to
+/// four times. Panics if `offset` isn't on a character boundary of `source`.
+pub fn utf16_offset(source: &str, offset: usize) -> usize {
+    source[..offset].encode_utf16().count()
+}
+
+/// Converts a byte range into `source`, e.g. the one of a `Violation`, to
+/// a range of UTF-16 code units, see `utf16_offset`
+pub fn utf16_range(source: &str, range: Range<usize>) -> Range<usize> {
+    let start = utf16_offset(source, range.start);
+    start..start + source[range].encode_utf16().count()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{utf16_offset, utf16_range};
+    use parser::{Feature, ParseOptions};
+    use validate::validate;
+
+    #[test]
+    fn converts_offsets() {
+        let source = "aé€😀b";
+        assert_eq!(utf16_offset(source, 0), 0);
+        assert_eq!(utf16_offset(source, 1), 1);
+        assert_eq!(utf16_offset(source, 3), 2);
+        assert_eq!(utf16_offset(source, 6), 3);
+        assert_eq!(utf16_offset(source, 10), 5);
+        assert_eq!(utf16_offset(source, source.len()), 6);
+    }
+
+    #[test]
+    fn converts_violation_ranges() {
+        let source = "# Grüße 😀\n\nSome `code` 🎉\n";
+        let violations = validate(source, &ParseOptions::default(), &[Feature::InlineCode]);
+        assert_eq!(violations[0].range, 16..32);
+        assert_eq!(utf16_range(source, violations[0].range.clone()), 12..26);
+    }
+}
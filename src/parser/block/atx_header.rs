@@ -1,9 +1,10 @@
 use parser::span::parse_spans;
 use parser::Block;
 use parser::Block::Header;
+use parser::ParseOptions;
 use regex::Regex;
 
-pub fn parse_atx_header(lines: &[&str]) -> Option<(Block, usize)> {
+pub fn parse_atx_header(lines: &[&str], options: &ParseOptions) -> Option<(Block, usize)> {
     lazy_static! {
         static ref ATX_HEADER_RE: Regex =
             Regex::new(r"^(?P<level>#{1,6})\s(?P<text>.*?)(?:\s#*)?$").unwrap();
@@ -13,7 +14,7 @@ pub fn parse_atx_header(lines: &[&str]) -> Option<(Block, usize)> {
         let caps = ATX_HEADER_RE.captures(lines[0]).unwrap();
         return Some((
             Header(
-                parse_spans(caps.name("text").unwrap().as_str()),
+                parse_spans(caps.name("text").unwrap().as_str(), options),
                 caps.name("level").unwrap().as_str().len(),
             ),
             1,
@@ -26,27 +27,32 @@ pub fn parse_atx_header(lines: &[&str]) -> Option<(Block, usize)> {
 mod test {
     use super::parse_atx_header;
     use parser::Block::Header;
+    use parser::ParseOptions;
     use parser::Span::Text;
 
     #[test]
     fn finds_atx_header() {
         assert_eq!(
-            parse_atx_header(&vec!["### Test", "testtest"]).unwrap(),
+            parse_atx_header(&["### Test", "testtest"], &ParseOptions::default()).unwrap(),
             (Header(vec![Text("Test".to_owned())], 3), 1)
         );
 
         assert_eq!(
-            parse_atx_header(&vec!["# Test", "testtest"]).unwrap(),
+            parse_atx_header(&["# Test", "testtest"], &ParseOptions::default()).unwrap(),
             (Header(vec![Text("Test".to_owned())], 1), 1)
         );
 
         assert_eq!(
-            parse_atx_header(&vec!["###### Test", "testtest"]).unwrap(),
+            parse_atx_header(&["###### Test", "testtest"], &ParseOptions::default()).unwrap(),
             (Header(vec![Text("Test".to_owned())], 6), 1)
         );
 
         assert_eq!(
-            parse_atx_header(&vec!["### Test and a pretty long sentence", "testtest"]).unwrap(),
+            parse_atx_header(
+                &["### Test and a pretty long sentence", "testtest"],
+                &ParseOptions::default()
+            )
+            .unwrap(),
             (
                 Header(vec![Text("Test and a pretty long sentence".to_owned())], 3),
                 1
@@ -57,25 +63,25 @@ mod test {
     #[test]
     fn ignores_closing_hashes() {
         assert_eq!(
-            parse_atx_header(&vec!["### Test ###", "testtest"]).unwrap(),
+            parse_atx_header(&["### Test ###", "testtest"], &ParseOptions::default()).unwrap(),
             (Header(vec![Text("Test".to_owned())], 3), 1)
         );
 
         assert_eq!(
-            parse_atx_header(&vec!["# Test #", "testtest"]).unwrap(),
+            parse_atx_header(&["# Test #", "testtest"], &ParseOptions::default()).unwrap(),
             (Header(vec![Text("Test".to_owned())], 1), 1)
         );
 
         assert_eq!(
-            parse_atx_header(&vec!["###### Test ##", "testtest"]).unwrap(),
+            parse_atx_header(&["###### Test ##", "testtest"], &ParseOptions::default()).unwrap(),
             (Header(vec![Text("Test".to_owned())], 6), 1)
         );
 
         assert_eq!(
-            parse_atx_header(&vec![
-                "### Test and a pretty long sentence #########",
-                "testtest"
-            ])
+            parse_atx_header(
+                &["### Test and a pretty long sentence #########", "testtest"],
+                &ParseOptions::default()
+            )
             .unwrap(),
             (
                 Header(vec![Text("Test and a pretty long sentence".to_owned())], 3),
@@ -86,8 +92,17 @@ mod test {
 
     #[test]
     fn no_false_positives() {
-        assert_eq!(parse_atx_header(&vec!["####### Test", "testtest"]), None);
-        assert_eq!(parse_atx_header(&vec!["Test #", "testtest"]), None);
-        assert_eq!(parse_atx_header(&vec!["T ### est #", "testtest"]), None);
+        assert_eq!(
+            parse_atx_header(&["####### Test", "testtest"], &ParseOptions::default()),
+            None
+        );
+        assert_eq!(
+            parse_atx_header(&["Test #", "testtest"], &ParseOptions::default()),
+            None
+        );
+        assert_eq!(
+            parse_atx_header(&["T ### est #", "testtest"], &ParseOptions::default()),
+            None
+        );
     }
 }
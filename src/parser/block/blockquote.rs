@@ -1,8 +1,10 @@
+use alloc::string::String;
 use parser::block::parse_blocks;
 use parser::Block;
 use parser::Block::Blockquote;
+use parser::ParseOptions;
 
-pub fn parse_blockquote(lines: &[&str]) -> Option<(Block, usize)> {
+pub fn parse_blockquote(lines: &[&str], options: &ParseOptions) -> Option<(Block, usize)> {
     // if the first char isnt a blockquote don't even bother
     if lines[0].is_empty() || !lines[0].starts_with(">") {
         return None;
@@ -23,14 +25,10 @@ pub fn parse_blockquote(lines: &[&str]) -> Option<(Block, usize)> {
         // stop parsing on two newlines or if the paragraph after
         // a newline isn't started with a >
         // we continue to parse if it's just another empty line
-        if prev_newline && line.len() > 0 && !line.starts_with(">") {
+        if prev_newline && !line.is_empty() && !line.starts_with('>') {
             break;
         }
-        if line.is_empty() {
-            prev_newline = true;
-        } else {
-            prev_newline = false;
-        }
+        prev_newline = line.is_empty();
         let mut chars = line.chars();
         let begin = match chars.next() {
             Some('>') => match chars.next() {
@@ -47,7 +45,7 @@ pub fn parse_blockquote(lines: &[&str]) -> Option<(Block, usize)> {
     }
 
     if i > 0 {
-        return Some((Blockquote(parse_blocks(&content)), i));
+        return Some((Blockquote(parse_blocks(&content, options)), i));
     }
 
     None
@@ -57,15 +55,19 @@ pub fn parse_blockquote(lines: &[&str]) -> Option<(Block, usize)> {
 mod test {
     use super::parse_blockquote;
     use parser::Block::Blockquote;
+    use parser::ParseOptions;
 
     #[test]
     fn finds_blockquote() {
-        match parse_blockquote(&vec!["> A citation", "> is good"]) {
+        match parse_blockquote(&["> A citation", "> is good"], &ParseOptions::default()) {
             Some((Blockquote(_), 2)) => (),
             _ => panic!(),
         }
 
-        match parse_blockquote(&vec!["> A citation", "> is good,", "very good"]) {
+        match parse_blockquote(
+            &["> A citation", "> is good,", "very good"],
+            &ParseOptions::default(),
+        ) {
             Some((Blockquote(_), 3)) => (),
             _ => panic!(),
         }
@@ -73,7 +75,10 @@ mod test {
 
     #[test]
     fn knows_when_to_stop() {
-        match parse_blockquote(&vec!["> A citation", "> is good", "", "whatever"]) {
+        match parse_blockquote(
+            &["> A citation", "> is good", "", "whatever"],
+            &ParseOptions::default(),
+        ) {
             Some((Blockquote(_), 3)) => (),
             _ => panic!(),
         }
@@ -81,13 +86,19 @@ mod test {
 
     #[test]
     fn no_false_positives() {
-        assert_eq!(parse_blockquote(&vec!["wat > this"]), None);
+        assert_eq!(
+            parse_blockquote(&["wat > this"], &ParseOptions::default()),
+            None
+        );
     }
 
     #[test]
     fn no_early_matching() {
         assert_eq!(
-            parse_blockquote(&vec!["Hello", "> A citation", "> is good", "", "whatever"]),
+            parse_blockquote(
+                &["Hello", "> A citation", "> is good", "", "whatever"],
+                &ParseOptions::default()
+            ),
             None
         );
     }
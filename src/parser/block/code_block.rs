@@ -1,61 +1,51 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use parser::block::{indent_width, is_blank, strip_indent};
 use parser::Block;
 use parser::Block::CodeBlock;
+use parser::ParseOptions;
 use regex::Regex;
 
-pub fn parse_code_block(lines: &[&str]) -> Option<(Block, usize)> {
+pub fn parse_code_block(lines: &[&str], options: &ParseOptions) -> Option<(Block, usize)> {
     lazy_static! {
-        static ref CODE_BLOCK_SPACES: Regex = Regex::new(r"^ {4}").unwrap();
-        static ref CODE_BLOCK_TABS: Regex = Regex::new(r"^\t").unwrap();
-        static ref CODE_BLOCK_BACKTICKS: Regex = Regex::new(r"^```").unwrap();
+        static ref CODE_BLOCK_FENCE: Regex =
+            Regex::new(r"^(?P<indent> {0,3})(?P<fence>`{3,}|~{3,})(?P<info>.*)$").unwrap();
+    }
+
+    if let Some(caps) = CODE_BLOCK_FENCE.captures(lines[0]) {
+        return parse_fenced_code_block(
+            lines,
+            caps.name("indent").unwrap().as_str().len(),
+            caps.name("fence").unwrap().as_str(),
+            caps.name("info").unwrap().as_str(),
+        );
+    }
+
+    // a line of only whitespace is a blank line, even when it's indented,
+    // so it can't start indented code
+    if is_blank(lines[0]) {
+        return None;
     }
 
     let mut content = String::new();
-    let mut lang: Option<String> = None;
     let mut line_number = 0;
-    let mut backtick_opened = false;
-    let mut backtick_closed = false;
 
+    // indented code is indented by at least four columns
     for line in lines {
-        if !backtick_opened && CODE_BLOCK_SPACES.is_match(line) {
-            if line_number > 0 && !content.is_empty() {
-                content.push('\n');
-            }
-            // remove top-level spaces
-            content.push_str(&line[4..line.len()]);
-            line_number += 1;
-        } else if !backtick_opened && CODE_BLOCK_TABS.is_match(line) {
-            if line_number > 0 && !content.is_empty() {
-                content.push('\n');
-            }
-
-            if !(line_number == 0 && line.trim().is_empty()) {
-                // remove top-level spaces
-                content.push_str(&line[1..line.len()]);
-            }
-            line_number += 1;
-        } else if CODE_BLOCK_BACKTICKS.is_match(line) {
-            line_number += 1;
-
-            if !backtick_opened && !(line_number == 0 && line.get(3..).is_some()) {
-                lang = Some(String::from(line.get(3..).unwrap()));
-                backtick_opened = true;
-            } else if backtick_opened {
-                backtick_closed = true;
-                break;
-            }
-        } else if backtick_opened {
-            content.push_str(line);
-            content.push('\n');
-
-            line_number += 1;
-        } else {
+        if indent_width(line, options) < 4 {
             break;
         }
+        if line_number > 0 {
+            content.push('\n');
+        }
+        // remove top-level indentation
+        content.push_str(&strip_indent(line, 4, options));
+        line_number += 1;
     }
 
-    if line_number > 0 && ((backtick_opened && backtick_closed) || !backtick_opened) {
+    if line_number > 0 {
         return Some((
-            CodeBlock(lang, content.trim_matches('\n').to_owned()),
+            CodeBlock(None, content.trim_matches('\n').to_owned()),
             line_number,
         ));
     }
@@ -63,50 +53,194 @@ pub fn parse_code_block(lines: &[&str]) -> Option<(Block, usize)> {
     None
 }
 
+fn parse_fenced_code_block(
+    lines: &[&str],
+    indent: usize,
+    fence: &str,
+    info: &str,
+) -> Option<(Block, usize)> {
+    // A backtick in the info string of a backtick fence would make the opening
+    // line ambiguous with inline code, so it doesn't start a code block.
+    // Tilde fences have no such restriction.
+    if fence.starts_with('`') && info.contains('`') {
+        return None;
+    }
+
+    let mut content = String::new();
+
+    for (i, line) in lines.iter().enumerate().skip(1) {
+        if is_closing_fence(line, fence) {
+            return Some((
+                CodeBlock(
+                    Some(info.trim().to_owned()),
+                    content.trim_matches('\n').to_owned(),
+                ),
+                i + 1,
+            ));
+        }
+        // content lines lose as much indentation as the opening fence has
+        let unindented = line.trim_start_matches(' ');
+        content.push_str(&line[(line.len() - unindented.len()).min(indent)..]);
+        content.push('\n');
+    }
+
+    // unclosed fences are not treated as code blocks
+    None
+}
+
+// A closing fence uses the same character as the opening fence, is at least
+// as long and may be indented by up to three spaces.
+fn is_closing_fence(line: &str, fence: &str) -> bool {
+    let line = line.trim_end();
+    let fence_line = line.trim_start_matches(' ');
+    let fence_char = fence.chars().next().unwrap();
+
+    line.len() - fence_line.len() <= 3
+        && fence_line.len() >= fence.len()
+        && fence_line.chars().all(|c| c == fence_char)
+}
+
 #[cfg(test)]
 mod test {
     use super::parse_code_block;
     use parser::Block::CodeBlock;
+    use parser::ParseOptions;
 
     #[test]
     fn finds_code_block() {
         assert_eq!(
-            parse_code_block(&vec!["    Test"]).unwrap(),
-            ((CodeBlock(None, "Test".to_owned()), 1))
+            parse_code_block(&["    Test"], &ParseOptions::default()).unwrap(),
+            (CodeBlock(None, "Test".to_owned()), 1)
         );
 
         assert_eq!(
-            parse_code_block(&vec!["    Test", "    this"]).unwrap(),
-            ((CodeBlock(None, "Test\nthis".to_owned()), 2))
+            parse_code_block(&["    Test", "    this"], &ParseOptions::default()).unwrap(),
+            (CodeBlock(None, "Test\nthis".to_owned()), 2)
         );
 
         assert_eq!(
-            parse_code_block(&vec!["```testlang", "Test", "this", "```"]).unwrap(),
-            ((
+            parse_code_block(
+                &["```testlang", "Test", "this", "```"],
+                &ParseOptions::default()
+            )
+            .unwrap(),
+            (
                 CodeBlock(Some(String::from("testlang")), "Test\nthis".to_owned()),
                 4
-            ))
+            )
+        );
+    }
+
+    #[test]
+    fn finds_tilde_code_block() {
+        assert_eq!(
+            parse_code_block(
+                &["~~~testlang", "Test", "this", "~~~"],
+                &ParseOptions::default()
+            )
+            .unwrap(),
+            (
+                CodeBlock(Some(String::from("testlang")), "Test\nthis".to_owned()),
+                4
+            )
+        );
+
+        assert_eq!(
+            parse_code_block(&["~~~~", "```", "~~~", "~~~~~"], &ParseOptions::default()).unwrap(),
+            (CodeBlock(Some(String::new()), "```\n~~~".to_owned()), 4)
+        );
+    }
+
+    #[test]
+    fn strips_fence_indentation() {
+        assert_eq!(
+            parse_code_block(
+                &["  ```", "  a", "    b", " c", "  ```"],
+                &ParseOptions::default()
+            )
+            .unwrap(),
+            (CodeBlock(Some(String::new()), "a\n  b\nc".to_owned()), 5)
+        );
+    }
+
+    #[test]
+    fn backticks_in_info_string() {
+        assert_eq!(
+            parse_code_block(&["```foo`bar", "Test", "```"], &ParseOptions::default()),
+            None
+        );
+
+        assert_eq!(
+            parse_code_block(&["~~~foo`bar", "Test", "~~~"], &ParseOptions::default()).unwrap(),
+            (
+                CodeBlock(Some(String::from("foo`bar")), "Test".to_owned()),
+                3
+            )
         );
     }
 
     #[test]
     fn knows_when_to_stop() {
         assert_eq!(
-            parse_code_block(&vec!["    Test", "    this", "stuff", "    now"]).unwrap(),
-            ((CodeBlock(None, "Test\nthis".to_owned()), 2))
+            parse_code_block(
+                &["    Test", "    this", "stuff", "    now"],
+                &ParseOptions::default()
+            )
+            .unwrap(),
+            (CodeBlock(None, "Test\nthis".to_owned()), 2)
         );
     }
 
     #[test]
     fn no_false_positives() {
-        assert_eq!(parse_code_block(&vec!["   Test"]), None);
+        assert_eq!(
+            parse_code_block(&["   Test"], &ParseOptions::default()),
+            None
+        );
+        assert_eq!(
+            parse_code_block(&["```", "Test"], &ParseOptions::default()),
+            None
+        );
+        assert_eq!(
+            parse_code_block(&["\t", "     "], &ParseOptions::default()),
+            None
+        );
+        assert_eq!(
+            parse_code_block(&["```", "Test", "~~~"], &ParseOptions::default()),
+            None
+        );
+        assert_eq!(
+            parse_code_block(&["````", "Test", "```"], &ParseOptions::default()),
+            None
+        );
     }
 
     #[test]
     fn no_early_matching() {
         assert_eq!(
-            parse_code_block(&vec!["Test", "    this", "stuff", "    now"]),
+            parse_code_block(
+                &["Test", "    this", "stuff", "    now"],
+                &ParseOptions::default()
+            ),
             None
         );
     }
+
+    #[test]
+    fn keeps_backslashes_and_trailing_spaces() {
+        assert_eq!(
+            parse_code_block(
+                &["```", "a \\", "b  ", "c\\  ", "\\*d\\*", "```"],
+                &ParseOptions::default()
+            ),
+            Some((
+                CodeBlock(Some(String::new()), "a \\\nb  \nc\\  \n\\*d\\*".to_owned()),
+                6
+            ))
+        );
+        assert_eq!(
+            parse_code_block(&["    a\\", "    b  "], &ParseOptions::default()),
+            Some((CodeBlock(None, "a\\\nb  ".to_owned()), 2))
+        );
+    }
 }
@@ -0,0 +1,89 @@
+use alloc::string::String;
+use parser::span::is_comment_text;
+use parser::Block;
+use parser::Block::Comment;
+use regex::Regex;
+
+pub fn parse_comment(lines: &[&str]) -> Option<(Block, usize)> {
+    lazy_static! {
+        static ref COMMENT_BEGIN: Regex = Regex::new(r"^ {0,3}<!--").unwrap();
+    }
+
+    if !COMMENT_BEGIN.is_match(lines[0]) {
+        return None;
+    }
+
+    // skip everything up to and including the opening <!--
+    let start = lines[0].find("<!--").unwrap() + 4;
+    let mut content = String::new();
+
+    for (i, line) in lines.iter().enumerate() {
+        let line = if i == 0 { &line[start..] } else { line };
+        if i > 0 {
+            content.push('\n');
+        }
+
+        if let Some(end) = line.find("-->") {
+            // text after the closing --> means this is
+            // an inline comment inside of a paragraph
+            if !line[end + 3..].trim().is_empty() {
+                return None;
+            }
+            content.push_str(&line[..end]);
+            // text that could end the comment early isn't a comment
+            if !is_comment_text(&content) {
+                return None;
+            }
+            return Some((Comment(content), i + 1));
+        }
+        content.push_str(line);
+    }
+
+    // unterminated comments are treated as text
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_comment;
+    use parser::Block::Comment;
+
+    #[test]
+    fn finds_comment() {
+        assert_eq!(
+            parse_comment(&["<!-- a comment -->"]).unwrap(),
+            (Comment(" a comment ".to_owned()), 1)
+        );
+
+        assert_eq!(
+            parse_comment(&["   <!--a comment-->  ", "text"]).unwrap(),
+            (Comment("a comment".to_owned()), 1)
+        );
+    }
+
+    #[test]
+    fn finds_multiline_comment() {
+        assert_eq!(
+            parse_comment(&["<!--", "a comment", "", "over many lines", "-->", "text"]).unwrap(),
+            (Comment("\na comment\n\nover many lines\n".to_owned()), 5)
+        );
+    }
+
+    #[test]
+    fn no_false_positives() {
+        assert_eq!(parse_comment(&["<!-- a comment --> and text"]), None);
+        assert_eq!(parse_comment(&["<!-- an unterminated", "comment"]), None);
+        assert_eq!(parse_comment(&["    <!-- code -->"]), None);
+        assert_eq!(parse_comment(&["<!-- a -- b -->"]), None);
+        assert_eq!(
+            parse_comment(&["<!-- a --!>", "<img src=x onerror=alert(1)>", "-->"]),
+            None
+        );
+        assert_eq!(parse_comment(&["<!-->"]), None);
+    }
+
+    #[test]
+    fn no_early_matching() {
+        assert_eq!(parse_comment(&["text <!-- a comment -->"]), None);
+    }
+}
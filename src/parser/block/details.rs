@@ -0,0 +1,202 @@
+use parser::block::parse_blocks;
+use parser::span::parse_spans;
+use parser::Block;
+use parser::Block::Details;
+use parser::ParseOptions;
+use regex::Regex;
+
+pub fn parse_details(lines: &[&str], options: &ParseOptions) -> Option<(Block, usize)> {
+    lazy_static! {
+        static ref DETAILS_BEGIN: Regex = Regex::new(r"^ {0,3}<details>(?P<rest>.*)$").unwrap();
+        static ref DETAILS_END: Regex = Regex::new(r"^ {0,3}</details>\s*$").unwrap();
+        static ref SUMMARY: Regex =
+            Regex::new(r"^\s*<summary>(?P<text>.*?)</summary>\s*$").unwrap();
+    }
+
+    let caps = DETAILS_BEGIN.captures(lines[0])?;
+
+    // the summary can either follow the opening tag directly
+    // or be on the line after it
+    let rest = caps.name("rest").unwrap().as_str();
+    let (summary, mut i) = if rest.trim().is_empty() {
+        match lines.get(1).and_then(|line| SUMMARY.captures(line)) {
+            Some(caps) => (parse_spans(&caps["text"], options), 2),
+            None => (vec![], 1),
+        }
+    } else {
+        (parse_spans(&SUMMARY.captures(rest)?["text"], options), 1)
+    };
+
+    // collect everything up to the matching closing tag,
+    // keeping track of nested details blocks
+    let mut content = vec![];
+    let mut depth = 0;
+    while i < lines.len() {
+        let line = lines[i];
+        i += 1;
+        if DETAILS_BEGIN.is_match(line) {
+            depth += 1;
+        } else if DETAILS_END.is_match(line) {
+            if depth == 0 {
+                return Some((
+                    Details(summary, parse_blocks(&content.join("\n"), options)),
+                    i,
+                ));
+            }
+            depth -= 1;
+        }
+        content.push(line);
+    }
+
+    // unclosed details are not treated as a block
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_details;
+    use parser::Block::{Details, Paragraph, UnorderedList};
+    use parser::Span::{Emphasis, Text};
+    use parser::{ListItem, ParseOptions, UnorderedListType};
+
+    #[test]
+    fn finds_details() {
+        assert_eq!(
+            parse_details(
+                &[
+                    "<details>",
+                    "<summary>A *summary*</summary>",
+                    "",
+                    "Some text",
+                    "</details>",
+                    "More text"
+                ],
+                &ParseOptions::default()
+            )
+            .unwrap(),
+            (
+                Details(
+                    vec![
+                        Text("A ".to_owned()),
+                        Emphasis(vec![Text("summary".to_owned())])
+                    ],
+                    vec![Paragraph(vec![Text("Some text".to_owned())])]
+                ),
+                5
+            )
+        );
+
+        assert_eq!(
+            parse_details(
+                &[
+                    "<details><summary>Summary</summary>",
+                    "Some text",
+                    "</details>"
+                ],
+                &ParseOptions::default()
+            )
+            .unwrap(),
+            (
+                Details(
+                    vec![Text("Summary".to_owned())],
+                    vec![Paragraph(vec![Text("Some text".to_owned())])]
+                ),
+                3
+            )
+        );
+
+        assert_eq!(
+            parse_details(
+                &["<details>", "Some text", "</details>"],
+                &ParseOptions::default()
+            )
+            .unwrap(),
+            (
+                Details(vec![], vec![Paragraph(vec![Text("Some text".to_owned())])]),
+                3
+            )
+        );
+    }
+
+    #[test]
+    fn parses_markdown_content() {
+        assert_eq!(
+            parse_details(
+                &[
+                    "<details><summary>Steps</summary>",
+                    "",
+                    "- one",
+                    "- *two*",
+                    "",
+                    "</details>"
+                ],
+                &ParseOptions::default()
+            )
+            .unwrap(),
+            (
+                Details(
+                    vec![Text("Steps".to_owned())],
+                    vec![UnorderedList(
+                        vec![
+                            ListItem::Simple(vec![Text("one".to_owned())]),
+                            ListItem::Simple(vec![Emphasis(vec![Text("two".to_owned())])])
+                        ],
+                        UnorderedListType::Dash
+                    )]
+                ),
+                6
+            )
+        );
+    }
+
+    #[test]
+    fn finds_nested_details() {
+        assert_eq!(
+            parse_details(
+                &[
+                    "<details>",
+                    "<details>",
+                    "Some text",
+                    "</details>",
+                    "</details>"
+                ],
+                &ParseOptions::default()
+            )
+            .unwrap(),
+            (
+                Details(
+                    vec![],
+                    vec![Details(
+                        vec![],
+                        vec![Paragraph(vec![Text("Some text".to_owned())])]
+                    )]
+                ),
+                5
+            )
+        );
+    }
+
+    #[test]
+    fn no_false_positives() {
+        assert_eq!(
+            parse_details(&["<details>", "Some text"], &ParseOptions::default()),
+            None
+        );
+        assert_eq!(
+            parse_details(&["<details> text", "</details>"], &ParseOptions::default()),
+            None
+        );
+        assert_eq!(
+            parse_details(&["    <details>", "</details>"], &ParseOptions::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn no_early_matching() {
+        assert_eq!(
+            parse_details(&["text <details>", "</details>"], &ParseOptions::default()),
+            None
+        );
+    }
+}
@@ -0,0 +1,136 @@
+use alloc::borrow::ToOwned;
+use parser::block::parse_blocks;
+use parser::Block;
+use parser::Block::Div;
+use parser::ParseOptions;
+use regex::Regex;
+
+// Parses a Pandoc-style fenced div like `::: warning` or `::: {.note .wide}`,
+// which ends at a line of only colons. An opening fence always has a class
+// or an attribute block, so nested divs can be told apart from the end of
+// the outer one.
+pub fn parse_div(lines: &[&str], options: &ParseOptions) -> Option<(Block, usize)> {
+    lazy_static! {
+        static ref DIV_BEGIN: Regex = Regex::new(
+            r"^ {0,3}:{3,}[ \t]*(?:\{(?P<attributes>[^}]*)\}|(?P<class>[^\s{}:]+))[ \t]*:*[ \t]*$"
+        )
+        .unwrap();
+        static ref DIV_END: Regex = Regex::new(r"^ {0,3}:{3,}[ \t]*$").unwrap();
+    }
+
+    let caps = DIV_BEGIN.captures(lines[0])?;
+    // only the classes of an attribute block are kept, e.g. not an `#id`
+    let classes = match caps.name("class") {
+        Some(class) => vec![class.as_str().to_owned()],
+        None => caps["attributes"]
+            .split_whitespace()
+            .filter_map(|word| word.strip_prefix('.'))
+            .filter(|class| !class.is_empty())
+            .map(str::to_owned)
+            .collect(),
+    };
+
+    // collect everything up to the matching closing fence,
+    // keeping track of nested divs
+    let mut content = vec![];
+    let mut depth = 0;
+    let mut i = 1;
+    while i < lines.len() {
+        let line = lines[i];
+        i += 1;
+        if DIV_BEGIN.is_match(line) {
+            depth += 1;
+        } else if DIV_END.is_match(line) {
+            if depth == 0 {
+                return Some((Div(classes, parse_blocks(&content.join("\n"), options)), i));
+            }
+            depth -= 1;
+        }
+        content.push(line);
+    }
+
+    // unclosed divs are not treated as a block
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_div;
+    use parser::Block::{Div, Paragraph};
+    use parser::ParseOptions;
+    use parser::Span::{Emphasis, Text};
+
+    #[test]
+    fn finds_div() {
+        assert_eq!(
+            parse_div(
+                &["::: warning", "Some *text*", ":::", "More text"],
+                &ParseOptions::default()
+            ),
+            Some((
+                Div(
+                    vec!["warning".to_owned()],
+                    vec![Paragraph(vec![
+                        Text("Some ".to_owned()),
+                        Emphasis(vec![Text("text".to_owned())])
+                    ])]
+                ),
+                3
+            ))
+        );
+
+        assert_eq!(
+            parse_div(
+                &[":::: {#tip .note .wide} ::::", "", "Text", "", "::::::"],
+                &ParseOptions::default()
+            ),
+            Some((
+                Div(
+                    vec!["note".to_owned(), "wide".to_owned()],
+                    vec![Paragraph(vec![Text("Text".to_owned())])]
+                ),
+                5
+            ))
+        );
+    }
+
+    #[test]
+    fn finds_nested_divs() {
+        assert_eq!(
+            parse_div(
+                &[
+                    ":::: outer",
+                    "::: inner",
+                    "Some text",
+                    ":::",
+                    "After",
+                    "::::"
+                ],
+                &ParseOptions::default()
+            ),
+            Some((
+                Div(
+                    vec!["outer".to_owned()],
+                    vec![
+                        Div(
+                            vec!["inner".to_owned()],
+                            vec![Paragraph(vec![Text("Some text".to_owned())])]
+                        ),
+                        Paragraph(vec![Text("After".to_owned())])
+                    ]
+                ),
+                6
+            ))
+        );
+    }
+
+    #[test]
+    fn no_false_positives() {
+        let options = ParseOptions::default();
+        assert_eq!(parse_div(&["::: warning", "Some text"], &options), None);
+        assert_eq!(parse_div(&[":::", "Some text", ":::"], &options), None);
+        assert_eq!(parse_div(&[":: warning", "::"], &options), None);
+        assert_eq!(parse_div(&["    ::: warning", ":::"], &options), None);
+        assert_eq!(parse_div(&["::: two words", ":::"], &options), None);
+    }
+}
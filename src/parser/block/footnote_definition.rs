@@ -0,0 +1,103 @@
+use alloc::borrow::ToOwned;
+use parser::block::{is_blank, parse_blocks};
+use parser::Block;
+use parser::Block::FootnoteDefinition;
+use parser::ParseOptions;
+use regex::Regex;
+
+pub fn parse_footnote_definition(lines: &[&str], options: &ParseOptions) -> Option<(Block, usize)> {
+    lazy_static! {
+        static ref FOOTNOTE_BEGIN: Regex =
+            Regex::new(r"^ {0,3}\[\^(?P<id>[^\]\s]+)\]:[ \t]*(?P<content>.*)$").unwrap();
+        static ref INDENTED: Regex = Regex::new(r"^(?: {1,4}|\t)(?P<content>.*)$").unwrap();
+    }
+
+    let caps = FOOTNOTE_BEGIN.captures(lines[0])?;
+    let mut content = vec![caps.name("content").unwrap().as_str()];
+
+    // the definition continues on indented lines,
+    // blank lines only belong to it if another indented line follows
+    let mut i = 1;
+    while i < lines.len() {
+        if is_blank(lines[i]) {
+            match lines.get(i + 1) {
+                Some(next) if !is_blank(next) && INDENTED.is_match(next) => content.push(""),
+                _ => break,
+            }
+        } else if let Some(caps) = INDENTED.captures(lines[i]) {
+            content.push(caps.name("content").unwrap().as_str());
+        } else {
+            break;
+        }
+        i += 1;
+    }
+
+    Some((
+        FootnoteDefinition(
+            caps.name("id").unwrap().as_str().to_owned(),
+            parse_blocks(&content.join("\n"), options),
+        ),
+        i,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_footnote_definition;
+    use parser::Block::{FootnoteDefinition, Paragraph};
+    use parser::ParseOptions;
+    use parser::Span::Text;
+
+    #[test]
+    fn finds_footnote_definition() {
+        assert_eq!(
+            parse_footnote_definition(&["[^1]: A note", "text"], &ParseOptions::default()),
+            Some((
+                FootnoteDefinition(
+                    "1".to_owned(),
+                    vec![Paragraph(vec![Text("A note".to_owned())])]
+                ),
+                1
+            ))
+        );
+    }
+
+    #[test]
+    fn finds_multi_paragraph_footnote_definition() {
+        assert_eq!(
+            parse_footnote_definition(
+                &["[^note]: First", "    line", "", "    Second", "", "text"],
+                &ParseOptions::default()
+            ),
+            Some((
+                FootnoteDefinition(
+                    "note".to_owned(),
+                    vec![
+                        Paragraph(vec![
+                            Text("First".to_owned()),
+                            Text("\n".to_owned()),
+                            Text("line".to_owned())
+                        ]),
+                        Paragraph(vec![Text("Second".to_owned())])
+                    ]
+                ),
+                4
+            ))
+        );
+    }
+
+    #[test]
+    fn no_false_positives() {
+        let options = ParseOptions::default();
+        assert_eq!(parse_footnote_definition(&["[1]: A note"], &options), None);
+        assert_eq!(parse_footnote_definition(&["[^1] A note"], &options), None);
+        assert_eq!(
+            parse_footnote_definition(&["[^a b]: A note"], &options),
+            None
+        );
+        assert_eq!(
+            parse_footnote_definition(&["text [^1]: A note"], &options),
+            None
+        );
+    }
+}
@@ -4,7 +4,10 @@ use regex::Regex;
 
 pub fn parse_hr(lines: &[&str]) -> Option<(Block, usize)> {
     lazy_static! {
-        static ref HORIZONTAL_RULE: Regex = Regex::new(r"^(===+)$|^(---+)$").unwrap();
+        // three or more of the same character, optionally separated by spaces
+        static ref HORIZONTAL_RULE: Regex =
+            Regex::new(r"^(===+)$|^ {0,3}(?:(?:-[ \t]*){3,}|(?:\*[ \t]*){3,}|(?:_[ \t]*){3,})$")
+                .unwrap();
     }
 
     if HORIZONTAL_RULE.is_match(lines[0]) {
@@ -20,33 +23,60 @@ mod test {
 
     #[test]
     fn finds_hr() {
-        assert_eq!(parse_hr(&vec!["-------"]).unwrap(), (Hr, 1));
-        assert_eq!(parse_hr(&vec!["---"]).unwrap(), (Hr, 1));
+        assert_eq!(parse_hr(&["-------"]).unwrap(), (Hr, 1));
+        assert_eq!(parse_hr(&["---"]).unwrap(), (Hr, 1));
         assert_eq!(
-            parse_hr(&vec!["----------------------------"]).unwrap(),
+            parse_hr(&["----------------------------"]).unwrap(),
             (Hr, 1)
         );
-        assert_eq!(parse_hr(&vec!["-------", "abc"]).unwrap(), (Hr, 1));
+        assert_eq!(parse_hr(&["-------", "abc"]).unwrap(), (Hr, 1));
 
-        assert_eq!(parse_hr(&vec!["======="]).unwrap(), (Hr, 1));
-        assert_eq!(parse_hr(&vec!["==="]).unwrap(), (Hr, 1));
+        assert_eq!(parse_hr(&["======="]).unwrap(), (Hr, 1));
+        assert_eq!(parse_hr(&["==="]).unwrap(), (Hr, 1));
         assert_eq!(
-            parse_hr(&vec!["============================"]).unwrap(),
+            parse_hr(&["============================"]).unwrap(),
             (Hr, 1)
         );
-        assert_eq!(parse_hr(&vec!["=======", "abc"]).unwrap(), (Hr, 1));
+        assert_eq!(parse_hr(&["=======", "abc"]).unwrap(), (Hr, 1));
+    }
+
+    #[test]
+    fn finds_hr_of_any_character() {
+        assert_eq!(parse_hr(&["***"]).unwrap(), (Hr, 1));
+        assert_eq!(parse_hr(&["___"]).unwrap(), (Hr, 1));
+        assert_eq!(parse_hr(&["*****"]).unwrap(), (Hr, 1));
+        assert_eq!(parse_hr(&["_____", "abc"]).unwrap(), (Hr, 1));
+    }
+
+    #[test]
+    fn finds_spaced_hr() {
+        assert_eq!(parse_hr(&["* * *"]).unwrap(), (Hr, 1));
+        assert_eq!(parse_hr(&["- - -"]).unwrap(), (Hr, 1));
+        assert_eq!(parse_hr(&["_ _ _"]).unwrap(), (Hr, 1));
+        assert_eq!(parse_hr(&["*  *  *  *"]).unwrap(), (Hr, 1));
+        assert_eq!(parse_hr(&["   ***  "]).unwrap(), (Hr, 1));
+    }
+
+    #[test]
+    fn needs_three_characters() {
+        assert_eq!(parse_hr(&["**"]), None);
+        assert_eq!(parse_hr(&["--"]), None);
+        assert_eq!(parse_hr(&["__"]), None);
+        assert_eq!(parse_hr(&["* *"]), None);
+        assert_eq!(parse_hr(&["*-*"]), None);
+        assert_eq!(parse_hr(&["    ***"]), None);
     }
 
     #[test]
     fn no_false_positives() {
-        assert_eq!(parse_hr(&vec!["a-------"]), None);
-        assert_eq!(parse_hr(&vec!["--- a"]), None);
-        assert_eq!(parse_hr(&vec!["--a-"]), None);
-        assert_eq!(parse_hr(&vec!["-------====--------------"]), None);
-
-        assert_eq!(parse_hr(&vec!["a======"]), None);
-        assert_eq!(parse_hr(&vec!["=== a"]), None);
-        assert_eq!(parse_hr(&vec!["==a="]), None);
-        assert_eq!(parse_hr(&vec!["=======---================="]), None);
+        assert_eq!(parse_hr(&["a-------"]), None);
+        assert_eq!(parse_hr(&["--- a"]), None);
+        assert_eq!(parse_hr(&["--a-"]), None);
+        assert_eq!(parse_hr(&["-------====--------------"]), None);
+
+        assert_eq!(parse_hr(&["a======"]), None);
+        assert_eq!(parse_hr(&["=== a"]), None);
+        assert_eq!(parse_hr(&["==a="]), None);
+        assert_eq!(parse_hr(&["=======---================="]), None);
     }
 }
@@ -0,0 +1,78 @@
+use parser::span::parse_spans;
+use parser::Block;
+use parser::Block::LineBlock;
+use parser::ParseOptions;
+use regex::Regex;
+
+// Consecutive lines starting with `| ` keep their line breaks, e.g. for poetry.
+// Lines ending with a `|` look like a table row instead, so a block
+// containing one isn't a line block.
+pub fn parse_line_block(lines: &[&str], options: &ParseOptions) -> Option<(Block, usize)> {
+    lazy_static! {
+        static ref LINE: Regex = Regex::new(r"^\|(?: (?P<content>.*))?$").unwrap();
+    }
+
+    let mut content = vec![];
+    for line in lines {
+        match LINE.captures(line) {
+            Some(caps) => content.push(caps.name("content").map_or("", |c| c.as_str())),
+            None => break,
+        }
+    }
+
+    if content.is_empty() || content.iter().any(|line| line.trim_end().ends_with('|')) {
+        return None;
+    }
+
+    let consumed = content.len();
+    Some((
+        LineBlock(
+            content
+                .into_iter()
+                .map(|line| parse_spans(line, options))
+                .collect(),
+        ),
+        consumed,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_line_block;
+    use parser::Block::LineBlock;
+    use parser::ParseOptions;
+    use parser::Span::{Emphasis, Text};
+
+    #[test]
+    fn finds_line_block() {
+        assert_eq!(
+            parse_line_block(
+                &["| The *first* line", "|", "| the last", "text"],
+                &ParseOptions::default()
+            ),
+            Some((
+                LineBlock(vec![
+                    vec![
+                        Text("The ".to_owned()),
+                        Emphasis(vec![Text("first".to_owned())]),
+                        Text(" line".to_owned())
+                    ],
+                    vec![],
+                    vec![Text("the last".to_owned())]
+                ]),
+                3
+            ))
+        );
+    }
+
+    #[test]
+    fn no_false_positives() {
+        let options = ParseOptions::default();
+        assert_eq!(parse_line_block(&["|no space"], &options), None);
+        assert_eq!(parse_line_block(&["text | a"], &options), None);
+        assert_eq!(
+            parse_line_block(&["| a | b |", "| c | d |"], &options),
+            None
+        );
+    }
+}
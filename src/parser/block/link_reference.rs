@@ -1,3 +1,6 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
 use parser::Block;
 use parser::Block::LinkReference;
 use regex::Regex;
@@ -9,39 +12,61 @@ pub fn parse_link_reference(lines: &[&str]) -> Option<(Block, usize)> {
         static ref LINK_REFERENCE_SECOND_LINE: Regex = Regex::new("\\s*(?P<url>\\S+)(?:\\s+(?:'(?P<title1>.*)'|\"(?P<title2>.*)\"|\\((?P<title3>.*?)\\)))?\n?").unwrap();
     }
 
-    if LINK_REFERENCE_SINGLE_LINE.is_match(lines[0]) {
-        let caps = LINK_REFERENCE_SINGLE_LINE.captures(lines[0]).unwrap();
-        return Some((
-            LinkReference(
-                caps.name("id").unwrap().as_str().to_lowercase(),
-                caps.name("url").unwrap().as_str().to_owned(),
-                caps.name("title1")
-                    .or_else(|| caps.name("title2"))
-                    .or_else(|| caps.name("title3"))
-                    .map(|s| s.as_str().to_owned()),
-            ),
-            1,
-        ));
-    }
+    // the url is either on the same line as the id or on the line after it
+    let (caps, url_line) = match LINK_REFERENCE_SINGLE_LINE.captures(lines[0]) {
+        Some(caps) => (caps, 0),
+        None if LINK_REFERENCE_FIRST_LINE.is_match(lines[0]) => {
+            (LINK_REFERENCE_SECOND_LINE.captures(lines.get(1)?)?, 1)
+        }
+        None => return None,
+    };
+    let id = LINK_REFERENCE_FIRST_LINE.captures(lines[0]).unwrap()["id"].to_lowercase();
+    let url = caps.name("url").unwrap();
 
-    if LINK_REFERENCE_FIRST_LINE.is_match(lines[0]) && LINK_REFERENCE_SECOND_LINE.is_match(lines[1])
+    let mut consumed = url_line + 1;
+    let title = match caps
+        .name("title1")
+        .or_else(|| caps.name("title2"))
+        .or_else(|| caps.name("title3"))
     {
-        let caps1 = LINK_REFERENCE_FIRST_LINE.captures(lines[0]).unwrap();
-        let caps2 = LINK_REFERENCE_SECOND_LINE.captures(lines[1]).unwrap();
-        return Some((
-            LinkReference(
-                caps1.name("id").unwrap().as_str().to_lowercase(),
-                caps2.name("url").unwrap().as_str().to_owned(),
-                caps2
-                    .name("title1")
-                    .or_else(|| caps2.name("title2"))
-                    .or_else(|| caps2.name("title3"))
-                    .map(|s| s.as_str().to_owned()),
-            ),
-            2,
-        ));
-    }
+        Some(title) => Some(title.as_str().to_owned()),
+        None => match parse_wrapped_title(&lines[url_line][url.end()..], &lines[consumed..]) {
+            Some((title, len)) => {
+                consumed += len;
+                Some(title)
+            }
+            None => None,
+        },
+    };
+
+    Some((LinkReference(id, url.as_str().to_owned(), title), consumed))
+}
 
+// Parses a title that is opened after the url but only closed on one of the
+// following lines, up to a blank line. The line breaks inside of the title
+// are turned into spaces. Returns the title and the number of following lines.
+fn parse_wrapped_title(rest: &str, following: &[&str]) -> Option<(String, usize)> {
+    let rest = rest.trim();
+    let close = match rest.chars().next()? {
+        '"' => '"',
+        '\'' => '\'',
+        '(' => ')',
+        _ => return None,
+    };
+
+    let mut parts = vec![&rest[1..]];
+    for (i, line) in following.iter().enumerate() {
+        let line = line.trim();
+        if line.is_empty() {
+            return None;
+        }
+        if let Some(last) = line.strip_suffix(close) {
+            parts.push(last);
+            let parts: Vec<&str> = parts.into_iter().filter(|part| !part.is_empty()).collect();
+            return Some((parts.join(" "), i + 1));
+        }
+        parts.push(line);
+    }
     None
 }
 
@@ -53,7 +78,7 @@ mod test {
     #[test]
     fn finds_link_reference() {
         assert_eq!(
-            parse_link_reference(&vec!["[Test]: https://example.com"]).unwrap(),
+            parse_link_reference(&["[Test]: https://example.com"]).unwrap(),
             (
                 LinkReference("test".to_owned(), "https://example.com".to_owned(), None),
                 1
@@ -61,7 +86,7 @@ mod test {
         );
 
         assert_eq!(
-            parse_link_reference(&vec!["[Test]: https://example.com \"example\""]).unwrap(),
+            parse_link_reference(&["[Test]: https://example.com \"example\""]).unwrap(),
             (
                 LinkReference(
                     "test".to_owned(),
@@ -73,7 +98,7 @@ mod test {
         );
 
         assert_eq!(
-            parse_link_reference(&vec!["[Test]: https://example.com (example)"]).unwrap(),
+            parse_link_reference(&["[Test]: https://example.com (example)"]).unwrap(),
             (
                 LinkReference(
                     "test".to_owned(),
@@ -85,7 +110,7 @@ mod test {
         );
 
         assert_eq!(
-            parse_link_reference(&vec!["[Test]: https://example.com 'example'"]).unwrap(),
+            parse_link_reference(&["[Test]: https://example.com 'example'"]).unwrap(),
             (
                 LinkReference(
                     "test".to_owned(),
@@ -97,8 +122,7 @@ mod test {
         );
 
         assert_eq!(
-            parse_link_reference(&vec!["[Test]:     https://example.com        'example'"])
-                .unwrap(),
+            parse_link_reference(&["[Test]:     https://example.com        'example'"]).unwrap(),
             (
                 LinkReference(
                     "test".to_owned(),
@@ -110,7 +134,7 @@ mod test {
         );
 
         assert_eq!(
-            parse_link_reference(&vec!["[Test]:", "https://example.com \"example\""]).unwrap(),
+            parse_link_reference(&["[Test]:", "https://example.com \"example\""]).unwrap(),
             (
                 LinkReference(
                     "test".to_owned(),
@@ -121,4 +145,42 @@ mod test {
             )
         );
     }
+
+    #[test]
+    fn finds_wrapped_titles() {
+        assert_eq!(
+            parse_link_reference(&["[id]: /url \"A long", "  title\"", "text"]).unwrap(),
+            (
+                LinkReference(
+                    "id".to_owned(),
+                    "/url".to_owned(),
+                    Some("A long title".to_owned())
+                ),
+                2
+            )
+        );
+
+        assert_eq!(
+            parse_link_reference(&["[id]:", "/url (one", "two", "three)"]).unwrap(),
+            (
+                LinkReference(
+                    "id".to_owned(),
+                    "/url".to_owned(),
+                    Some("one two three".to_owned())
+                ),
+                4
+            )
+        );
+
+        // a blank line ends the title before it's closed
+        assert_eq!(
+            parse_link_reference(&["[id]: /url \"A long", "", "title\""]).unwrap(),
+            (LinkReference("id".to_owned(), "/url".to_owned(), None), 1)
+        );
+    }
+
+    #[test]
+    fn missing_url() {
+        assert_eq!(parse_link_reference(&["[id]:"]), None);
+    }
 }
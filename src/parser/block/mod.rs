@@ -1,98 +1,279 @@
-use parser::span::parse_spans;
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::ops::Range;
+use parser::span::{parse_spans, wrapped_lines};
 use parser::Block;
-use parser::Block::Paragraph;
+use parser::Block::{CodeBlock, Header, Hr, LeadParagraph, OrderedList, Paragraph, UnorderedList};
+use parser::Span;
 use parser::Span::{Break, Text};
+use parser::{ListItem, TaskState};
+use parser::{ParseMode, ParseOptions};
+use regex::Regex;
 
 mod atx_header;
 mod blockquote;
 mod code_block;
+mod comment;
+mod details;
+mod div;
+mod footnote_definition;
 mod hr;
+mod line_block;
 mod link_reference;
 mod ordered_list;
 mod setext_header;
+mod table;
 mod unordered_list;
 use self::atx_header::parse_atx_header;
 use self::blockquote::parse_blockquote;
 use self::code_block::parse_code_block;
+use self::comment::parse_comment;
+use self::details::parse_details;
+use self::div::parse_div;
+use self::footnote_definition::parse_footnote_definition;
 use self::hr::parse_hr;
+use self::line_block::parse_line_block;
 use self::link_reference::parse_link_reference;
 use self::ordered_list::parse_ordered_list;
 use self::setext_header::parse_setext_header;
+use self::table::parse_table;
 use self::unordered_list::parse_unordered_list;
 
-pub fn parse_blocks(md: &str) -> Vec<Block> {
+pub fn parse_blocks(md: &str, options: &ParseOptions) -> Vec<Block> {
+    parse_blocks_with_lines(md, options)
+        .into_iter()
+        .map(|(block, _)| block)
+        .collect()
+}
+
+// Parses the blocks along with the range of lines of `md` each of them spans.
+pub fn parse_blocks_with_lines(md: &str, options: &ParseOptions) -> Vec<(Block, Range<usize>)> {
     let mut blocks = vec![];
     let mut t = vec![];
+    // the line the current paragraph started on
+    let mut start = 0;
+    // whether the current paragraph started with the `^^^ ` lead marker
+    let mut lead = false;
+    let paragraph = |t: Vec<Span>, lead: bool| if lead { LeadParagraph(t) } else { Paragraph(t) };
     let lines: Vec<&str> = md.lines().collect();
     let mut i = 0;
     while i < lines.len() {
-        match parse_block(&lines[i..lines.len()]) {
+        let block = parse_custom_block(lines[i], options).or_else(|| {
+            parse_block(&lines[i..lines.len()], options).filter(|(block, _)| {
+                t.is_empty() || can_interrupt_paragraph(block, lines[i], options)
+            })
+        });
+        match block {
+            // a setext underline turns the whole paragraph above it into the
+            // header, only setext headers span two lines
+            Some((Header(spans, level), 2)) if !t.is_empty() => {
+                push_line(&mut t, spans);
+                blocks.push((Header(t, level), start..i + 2));
+                t = Vec::new();
+                i += 2;
+            }
             // if a block is found
             Some((block, consumed_lines)) => {
                 // the current paragraph has ended,
                 // push it to our blocks
                 if !t.is_empty() {
-                    blocks.push(Paragraph(t));
+                    blocks.push((paragraph(t, lead), start..i));
                     t = Vec::new();
                 }
-                blocks.push(block);
+                blocks.push((block, i..i + consumed_lines));
                 i += consumed_lines;
             }
             // no known element, let's make this a paragraph
             None => {
-                // empty linebreak => new paragraph
-                if lines[i].is_empty() && !t.is_empty() {
-                    blocks.push(Paragraph(t));
-                    t = Vec::new();
+                // empty linebreak (or a line of only spaces and tabs) => new paragraph
+                if is_blank(lines[i]) {
+                    if !t.is_empty() {
+                        blocks.push((paragraph(t, lead), start..i));
+                        t = Vec::new();
+                    }
+                    i += 1;
+                    continue;
                 }
 
-                let spans = parse_spans(lines[i]);
-
-                // add a newline between linebreaks
-                // except when we have a break element or nothing
-                match (t.last(), spans.first()) {
-                    (Some(&Break), _) => {}
-                    (_, None) => {}
-                    (None, _) => {}
-                    _ => t.push(Text("\n".to_owned())),
+                // an image or link can continue on the next lines
+                let consumed_lines = 1 + wrapped_lines(&lines[i..], options);
+                let mut text = lines[i..i + consumed_lines].join("\n");
+                if t.is_empty() {
+                    lead = options.lead_paragraphs && text.starts_with("^^^ ");
+                    if lead {
+                        text.drain(..4);
+                    }
                 }
+                let spans = parse_spans(&text, options);
 
-                t.extend_from_slice(&spans);
-                i += 1;
+                if t.is_empty() {
+                    start = i;
+                }
+                push_line(&mut t, spans);
+                i += consumed_lines;
             }
         }
     }
     if !t.is_empty() {
-        blocks.push(Paragraph(t));
+        blocks.push((paragraph(t, lead), start..i));
     }
     blocks
 }
 
-fn parse_block(lines: &[&str]) -> Option<(Block, usize)> {
-    pipe_opt!(
-    lines
-    => parse_hr
-    => parse_atx_header
-    => parse_code_block
-    => parse_blockquote
-    => parse_unordered_list
-    => parse_ordered_list
-    => parse_link_reference
-    // Must not match before anything else. See: https://spec.commonmark.org/0.29/#setext-headings
-    => parse_setext_header
-    )
+// Adds the spans of a line to those of the lines before it, with a newline
+// in between, except after a break element or when either of them is empty
+fn push_line(t: &mut Vec<Span>, spans: Vec<Span>) {
+    match (t.last(), spans.first()) {
+        (Some(&Break), _) => {}
+        (_, None) => {}
+        (None, _) => {}
+        _ => t.push(Text("\n".to_owned())),
+    }
+    t.extend(spans);
+}
+
+// Like in CommonMark, only spaces and tabs make a line blank, a line of other
+// whitespace like no-break spaces (U+00A0) is content.
+pub fn is_blank(line: &str) -> bool {
+    line.chars().all(|c| c == ' ' || c == '\t')
+}
+
+// Headers, fenced code, blockquotes and horizontal rules end a paragraph
+// without a blank line in between. Indented code can't interrupt a paragraph,
+// an indented line is just another line of the paragraph.
+// An ordered list can only interrupt a paragraph if it starts at the
+// beginning, to avoid creating a list from a line like `2019. What a year.`
+// that happens to be wrapped inside of a paragraph.
+// In strict mode lists can't interrupt a paragraph at all. Without setext
+// headers, a `===` underline is text rather than a horizontal rule.
+fn can_interrupt_paragraph(block: &Block, line: &str, options: &ParseOptions) -> bool {
+    lazy_static! {
+        static ref FIRST_LIST_ITEM: Regex =
+            Regex::new(r"^ *(?:1(?:\.[0-9]+)*|a|A|i|I)\. ").unwrap();
+    }
+
+    match (block, options.mode) {
+        (&OrderedList(_, _, _), ParseMode::Strict) | (&UnorderedList(_, _), ParseMode::Strict) => {
+            false
+        }
+        (&OrderedList(_, _, _), ParseMode::Relaxed) => FIRST_LIST_ITEM.is_match(line),
+        // fenced code blocks always have an info string, even if it's empty
+        (&CodeBlock(None, _), _) => false,
+        (&Hr, _) => options.setext_headers || !line.starts_with('='),
+        _ => true,
+    }
+}
+
+// Returns the options the items of a list are parsed with, which allow one
+// level of nesting less, or `None` if no list may be nested this deeply
+pub fn list_item_options(options: &ParseOptions) -> Option<ParseOptions> {
+    let max_list_depth = options.max_list_depth.checked_sub(1)?;
+    Some(ParseOptions {
+        max_list_depth,
+        ..options.clone()
+    })
+}
+
+// A tight list item starting with `[ ]`, `[x]` or `[-]` is a task
+pub fn parse_task_item(content: &str, options: &ParseOptions) -> Option<ListItem> {
+    lazy_static! {
+        static ref TASK_MARKER: Regex = Regex::new(r"^\[(?P<state>[ xX-])\][ \t]+").unwrap();
+    }
+
+    let caps = TASK_MARKER.captures(content)?;
+    let state = match &caps["state"] {
+        " " => TaskState::Unchecked,
+        "-" => TaskState::Indeterminate,
+        _ => TaskState::Checked,
+    };
+
+    match parse_blocks(&content[caps[0].len()..], options).as_slice() {
+        [Paragraph(spans)] => Some(ListItem::Task(state, spans.clone())),
+        _ => None,
+    }
+}
+
+// Returns the width of the indentation of `line` in columns,
+// a tab advances it to the next tab stop
+pub fn indent_width(line: &str, options: &ParseOptions) -> usize {
+    let tab_width = options.tab_width.max(1);
+    let mut width = 0;
+    for c in line.chars() {
+        match c {
+            ' ' => width += 1,
+            '\t' => width += tab_width - width % tab_width,
+            _ => break,
+        }
+    }
+    width
+}
+
+// Removes up to `columns` columns of indentation from `line`, a tab that is
+// only partly removed leaves its remaining columns as spaces. Tabs after the
+// removed indentation are expanded as well if they'd end at another column.
+pub fn strip_indent(line: &str, columns: usize, options: &ParseOptions) -> String {
+    let tab_width = options.tab_width.max(1);
+    let mut width = 0;
+    for (i, c) in line.char_indices() {
+        if width == columns {
+            let rest = &line[i..];
+            let content = rest.trim_start_matches([' ', '\t']);
+            if width % tab_width != 0 && rest[..rest.len() - content.len()].contains('\t') {
+                return " ".repeat(indent_width(line, options) - width) + content;
+            }
+            return rest.to_owned();
+        }
+        width = match c {
+            ' ' => width + 1,
+            '\t' => width + tab_width - width % tab_width,
+            _ => return line[i..].to_owned(),
+        };
+        if width > columns {
+            return " ".repeat(width - columns) + &line[i + 1..];
+        }
+    }
+    String::new()
+}
+
+fn parse_custom_block(line: &str, options: &ParseOptions) -> Option<(Block, usize)> {
+    let hook = options.block_hook.as_ref()?;
+    hook.call(line).map(|block| (block, 1))
+}
+
+fn parse_block(lines: &[&str], options: &ParseOptions) -> Option<(Block, usize)> {
+    parse_hr(lines)
+        .or_else(|| parse_atx_header(lines, options))
+        .or_else(|| parse_code_block(lines, options))
+        .or_else(|| parse_comment(lines))
+        .or_else(|| parse_details(lines, options))
+        .or_else(|| parse_div(lines, options))
+        .or_else(|| parse_blockquote(lines, options))
+        .or_else(|| parse_unordered_list(lines, options))
+        .or_else(|| parse_ordered_list(lines, options))
+        .or_else(|| parse_footnote_definition(lines, options))
+        .or_else(|| parse_link_reference(lines))
+        .or_else(|| parse_table(lines, options))
+        .or_else(|| parse_line_block(lines, options))
+        // Must not match before anything else. See: https://spec.commonmark.org/0.29/#setext-headings
+        .or_else(|| parse_setext_header(lines, options))
 }
 
 #[cfg(test)]
 mod test {
-    use super::parse_blocks;
-    use parser::Block::{Blockquote, CodeBlock, Header, Hr, Paragraph};
-    use parser::Span::Text;
+    use super::{indent_width, parse_blocks, strip_indent};
+    use parser::Block::{
+        Blockquote, CodeBlock, Comment, Header, Hr, LeadParagraph, OrderedList, Paragraph, Raw,
+        UnorderedList,
+    };
+    use parser::Span::{Break, Emphasis, Highlight, Image, Link, Strong, Text};
+    use parser::{Block, ParseMode, ParseOptions};
+    use parser::{ListItem, OrderedListType, TaskState, UnorderedListType};
 
     #[test]
     fn finds_atx_header() {
         assert_eq!(
-            parse_blocks("### Test"),
+            parse_blocks("### Test", &ParseOptions::default()),
             vec![Header(vec![Text("Test".to_owned())], 3)]
         );
     }
@@ -100,30 +281,135 @@ mod test {
     #[test]
     fn finds_setext_header() {
         assert_eq!(
-            parse_blocks("Test\n-------"),
+            parse_blocks("Test\n-------", &ParseOptions::default()),
             vec![Header(vec![Text("Test".to_owned())], 2)]
         );
         assert_eq!(
-            parse_blocks("Test\n======="),
+            parse_blocks("Test\n=======", &ParseOptions::default()),
             vec![Header(vec![Text("Test".to_owned())], 1)]
         );
+
+        assert_eq!(
+            parse_blocks("A  \nlong\ntitle\n---", &ParseOptions::default()),
+            vec![Header(
+                vec![
+                    Text("A".to_owned()),
+                    Break,
+                    Text("long".to_owned()),
+                    Text("\n".to_owned()),
+                    Text("title".to_owned())
+                ],
+                2
+            )]
+        );
+    }
+
+    #[test]
+    fn disables_setext_headers() {
+        let options = ParseOptions::new().setext_headers(false).build();
+        assert_eq!(
+            parse_blocks("Title\n===", &options),
+            vec![Paragraph(vec![
+                Text("Title".to_owned()),
+                Text("\n".to_owned()),
+                Text("===".to_owned())
+            ])]
+        );
+        assert_eq!(
+            parse_blocks("Title\n---", &options),
+            vec![Paragraph(vec![Text("Title".to_owned())]), Hr]
+        );
+    }
+
+    #[test]
+    fn only_spaces_and_tabs_make_a_line_blank() {
+        assert_eq!(
+            parse_blocks("a\n \t\nb", &ParseOptions::default()),
+            vec![
+                Paragraph(vec![Text("a".to_owned())]),
+                Paragraph(vec![Text("b".to_owned())])
+            ]
+        );
+        assert_eq!(
+            parse_blocks("a\n\u{a0}\u{a0}\nb", &ParseOptions::default()),
+            vec![Paragraph(vec![
+                Text("a".to_owned()),
+                Text("\n".to_owned()),
+                Text("\u{a0}\u{a0}".to_owned()),
+                Text("\n".to_owned()),
+                Text("b".to_owned())
+            ])]
+        );
+        assert_eq!(
+            parse_blocks("\u{a0}\n\na", &ParseOptions::default()),
+            vec![
+                Paragraph(vec![Text("\u{a0}".to_owned())]),
+                Paragraph(vec![Text("a".to_owned())])
+            ]
+        );
+    }
+
+    #[test]
+    fn finds_lead_paragraphs() {
+        let options = ParseOptions::new().lead_paragraphs(true).build();
+        assert_eq!(
+            parse_blocks("^^^ The *lead*\n^^^ text\n\nNot ^^^ lead", &options),
+            vec![
+                LeadParagraph(vec![
+                    Text("The ".to_owned()),
+                    Emphasis(vec![Text("lead".to_owned())]),
+                    Text("\n".to_owned()),
+                    Text("^^^ text".to_owned())
+                ]),
+                Paragraph(vec![Text("Not ^^^ lead".to_owned())])
+            ]
+        );
+        assert_eq!(
+            parse_blocks("^^^ Not a lead", &ParseOptions::default()),
+            vec![Paragraph(vec![Text("^^^ Not a lead".to_owned())])]
+        );
     }
 
     #[test]
     fn finds_hr() {
-        assert_eq!(parse_blocks("-------"), vec![Hr]);
-        assert_eq!(parse_blocks("======="), vec![Hr]);
+        assert_eq!(parse_blocks("-------", &ParseOptions::default()), vec![Hr]);
+        assert_eq!(parse_blocks("=======", &ParseOptions::default()), vec![Hr]);
+        assert_eq!(parse_blocks("* * *", &ParseOptions::default()), vec![Hr]);
+        assert_eq!(parse_blocks("- - -", &ParseOptions::default()), vec![Hr]);
+        assert_eq!(parse_blocks("___", &ParseOptions::default()), vec![Hr]);
+    }
+
+    #[test]
+    fn hr_after_paragraph() {
+        assert_eq!(
+            parse_blocks("Test\n---", &ParseOptions::default()),
+            vec![Header(vec![Text("Test".to_owned())], 2)]
+        );
+        assert_eq!(
+            parse_blocks("Test\n\n---", &ParseOptions::default()),
+            vec![Paragraph(vec![Text("Test".to_owned())]), Hr]
+        );
+        assert_eq!(
+            parse_blocks("Test\n***", &ParseOptions::default()),
+            vec![Paragraph(vec![Text("Test".to_owned())]), Hr]
+        );
     }
 
     #[test]
     fn finds_code_block() {
         assert_eq!(
-            parse_blocks("    this is code\n    and this as well"),
+            parse_blocks(
+                "    this is code\n    and this as well",
+                &ParseOptions::default()
+            ),
             vec![CodeBlock(None, "this is code\nand this as well".to_owned())]
         );
 
         assert_eq!(
-            parse_blocks("```\nthis is code\nand this as well\n```"),
+            parse_blocks(
+                "```\nthis is code\nand this as well\n```",
+                &ParseOptions::default()
+            ),
             vec![CodeBlock(
                 Some(String::new()),
                 "this is code\nand this as well".to_owned()
@@ -131,10 +417,209 @@ mod test {
         );
     }
 
+    #[test]
+    fn finds_comment() {
+        assert_eq!(
+            parse_blocks("text\n<!-- a\ncomment -->\ntext", &ParseOptions::default()),
+            vec![
+                Paragraph(vec![Text("text".to_owned())]),
+                Comment(" a\ncomment ".to_owned()),
+                Paragraph(vec![Text("text".to_owned())])
+            ]
+        );
+    }
+
+    #[test]
+    fn blocks_interrupting_paragraphs() {
+        let options = ParseOptions::default();
+        let text = || Paragraph(vec![Text("text".to_owned())]);
+        assert_eq!(
+            parse_blocks("text\n# Header", &options),
+            vec![text(), Header(vec![Text("Header".to_owned())], 1)]
+        );
+        assert_eq!(
+            parse_blocks("text\n```\ncode\n```", &options),
+            vec![text(), CodeBlock(Some("".to_owned()), "code".to_owned())]
+        );
+        assert_eq!(
+            parse_blocks("text\n> quote", &options),
+            vec![
+                text(),
+                Blockquote(vec![Paragraph(vec![Text("quote".to_owned())])])
+            ]
+        );
+        assert_eq!(parse_blocks("text\n***", &options), vec![text(), Hr]);
+        assert_eq!(parse_blocks("text\n___", &options), vec![text(), Hr]);
+    }
+
+    #[test]
+    fn indented_code_not_interrupting_paragraphs() {
+        assert_eq!(
+            parse_blocks("text\n    more text", &ParseOptions::default()),
+            vec![Paragraph(vec![
+                Text("text".to_owned()),
+                Text("\n".to_owned()),
+                Text("more text".to_owned())
+            ])]
+        );
+    }
+
+    #[test]
+    fn lists_interrupting_paragraphs() {
+        assert_eq!(
+            parse_blocks("Hello\n1. world", &ParseOptions::default()),
+            vec![
+                Paragraph(vec![Text("Hello".to_owned())]),
+                OrderedList(
+                    vec![ListItem::Simple(vec![Text("world".to_owned())])],
+                    OrderedListType::Numeric,
+                    vec!["1".to_owned()]
+                )
+            ]
+        );
+
+        assert_eq!(
+            parse_blocks("Hello\n* world", &ParseOptions::default()),
+            vec![
+                Paragraph(vec![Text("Hello".to_owned())]),
+                UnorderedList(
+                    vec![ListItem::Simple(vec![Text("world".to_owned())])],
+                    UnorderedListType::Asterisk
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn lists_not_interrupting_paragraphs() {
+        assert_eq!(
+            parse_blocks("Hello\n2. world", &ParseOptions::default()),
+            vec![Paragraph(vec![
+                Text("Hello".to_owned()),
+                Text("\n".to_owned()),
+                Text("2. world".to_owned())
+            ])]
+        );
+
+        assert_eq!(
+            parse_blocks("Hello\n\n2. world", &ParseOptions::default()),
+            vec![
+                Paragraph(vec![Text("Hello".to_owned())]),
+                OrderedList(
+                    vec![ListItem::Simple(vec![Text("world".to_owned())])],
+                    OrderedListType::Numeric,
+                    vec!["2".to_owned()]
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn strict_lists_need_a_blank_line() {
+        let text = "Hello\n* world";
+        assert_eq!(
+            parse_blocks(text, &ParseOptions::default()),
+            vec![
+                Paragraph(vec![Text("Hello".to_owned())]),
+                UnorderedList(
+                    vec![ListItem::Simple(vec![Text("world".to_owned())])],
+                    UnorderedListType::Asterisk
+                )
+            ]
+        );
+        assert_eq!(
+            parse_blocks(text, &ParseOptions::new().mode(ParseMode::Strict).build()),
+            vec![Paragraph(vec![
+                Text("Hello".to_owned()),
+                Text("\n".to_owned()),
+                Text("* world".to_owned())
+            ])]
+        );
+    }
+
+    #[test]
+    fn strict_lists_have_no_lazy_continuation() {
+        let text = "* Hello\nworld";
+        assert_eq!(
+            parse_blocks(text, &ParseOptions::default()),
+            vec![UnorderedList(
+                vec![ListItem::Simple(vec![
+                    Text("Hello".to_owned()),
+                    Text("\n".to_owned()),
+                    Text("world".to_owned())
+                ])],
+                UnorderedListType::Asterisk
+            )]
+        );
+        assert_eq!(
+            parse_blocks(text, &ParseOptions::new().mode(ParseMode::Strict).build()),
+            vec![
+                UnorderedList(
+                    vec![ListItem::Simple(vec![Text("Hello".to_owned())])],
+                    UnorderedListType::Asterisk
+                ),
+                Paragraph(vec![Text("world".to_owned())])
+            ]
+        );
+    }
+
+    #[test]
+    fn uses_block_hook() {
+        let options = ParseOptions::new()
+            .block_hook(|line| {
+                let file = line.strip_prefix("@@include ")?.strip_suffix("@@")?;
+                Some(Raw(format!("<!-- include {} -->", file)))
+            })
+            .build();
+        assert_eq!(
+            parse_blocks(
+                "Text\n@@include intro.md@@\n\n> @@include quote.md@@",
+                &options
+            ),
+            vec![
+                Paragraph(vec![Text("Text".to_owned())]),
+                Raw("<!-- include intro.md -->".to_owned()),
+                Blockquote(vec![Raw("<!-- include quote.md -->".to_owned())])
+            ]
+        );
+        assert_eq!(
+            parse_blocks("@@include intro.md@@", &ParseOptions::default()),
+            vec![Paragraph(vec![Text("@@include intro.md@@".to_owned())])]
+        );
+    }
+
+    #[test]
+    fn finds_task_list() {
+        assert_eq!(
+            parse_blocks(
+                "* [ ] to do\n* [x] done\n* [X] **done**\n* [-] in progress",
+                &ParseOptions::default()
+            ),
+            vec![UnorderedList(
+                vec![
+                    ListItem::Task(TaskState::Unchecked, vec![Text("to do".to_owned())]),
+                    ListItem::Task(TaskState::Checked, vec![Text("done".to_owned())]),
+                    ListItem::Task(
+                        TaskState::Checked,
+                        vec![Strong(vec![Text("done".to_owned())])]
+                    ),
+                    ListItem::Task(
+                        TaskState::Indeterminate,
+                        vec![Text("in progress".to_owned())]
+                    )
+                ],
+                UnorderedListType::Asterisk
+            )]
+        );
+    }
+
     #[test]
     fn finds_blockquotes() {
         assert_eq!(
-            parse_blocks("> One Paragraph\n>\n> ## H2 \n>\n"),
+            parse_blocks(
+                "> One Paragraph\n>\n> ## H2 \n>\n",
+                &ParseOptions::default()
+            ),
             vec![Blockquote(vec![
                 Paragraph(vec![Text("One Paragraph".to_owned())]),
                 Header(vec![Text("H2".to_owned())], 2)
@@ -142,7 +627,10 @@ mod test {
         );
 
         assert_eq!(
-            parse_blocks("> One Paragraph\n>\n> > Another blockquote\n>\n"),
+            parse_blocks(
+                "> One Paragraph\n>\n> > Another blockquote\n>\n",
+                &ParseOptions::default()
+            ),
             vec![Blockquote(vec![
                 Paragraph(vec![Text("One Paragraph".to_owned())]),
                 Blockquote(vec![Paragraph(vec![Text("Another blockquote".to_owned())])])
@@ -150,7 +638,10 @@ mod test {
         );
 
         assert_eq!(
-            parse_blocks("> > One Paragraph\n> >\n> > Another blockquote\n>\n"),
+            parse_blocks(
+                "> > One Paragraph\n> >\n> > Another blockquote\n>\n",
+                &ParseOptions::default()
+            ),
             vec![Blockquote(vec![Blockquote(vec![
                 Paragraph(vec![Text("One Paragraph".to_owned())]),
                 Paragraph(vec![Text("Another blockquote".to_owned())])
@@ -158,18 +649,241 @@ mod test {
         );
 
         assert_eq!(
-            parse_blocks("> One Paragraph, just > text \n>\n"),
+            parse_blocks(
+                "> One Paragraph, just > text \n>\n",
+                &ParseOptions::default()
+            ),
             vec![Blockquote(vec![Paragraph(vec![Text(
                 "One Paragraph, just > text".to_owned()
             )])])]
         );
 
         assert_eq!(
-            parse_blocks("> One Paragraph\n>\n> just > text \n>\n"),
+            parse_blocks(
+                "> One Paragraph\n>\n> just > text \n>\n",
+                &ParseOptions::default()
+            ),
             vec![Blockquote(vec![
                 Paragraph(vec![Text("One Paragraph".to_owned())]),
                 Paragraph(vec![Text("just > text".to_owned())])
             ])]
         );
     }
+
+    #[test]
+    fn malformed_tables_are_paragraphs() {
+        assert_eq!(
+            parse_blocks("| a | b |\n| x | y |", &ParseOptions::default()),
+            vec![Paragraph(vec![
+                Text("| a | b |".to_owned()),
+                Text("\n".to_owned()),
+                Text("| x | y |".to_owned())
+            ])]
+        );
+
+        assert_eq!(
+            parse_blocks("| a | b |\n|---|", &ParseOptions::default()),
+            vec![Paragraph(vec![
+                Text("| a | b |".to_owned()),
+                Text("\n".to_owned()),
+                Text("|---|".to_owned())
+            ])]
+        );
+    }
+
+    #[test]
+    fn tab_width_decides_nesting() {
+        let md = "- a\n  - b\n\t- c";
+        let item = |text: &str| Paragraph(vec![Text(text.to_owned())]);
+
+        assert_eq!(
+            parse_blocks(md, &ParseOptions::new().tab_width(4).build()),
+            vec![UnorderedList(
+                vec![ListItem::Paragraph(vec![
+                    item("a"),
+                    UnorderedList(
+                        vec![ListItem::Paragraph(vec![
+                            item("b"),
+                            UnorderedList(
+                                vec![ListItem::Simple(vec![Text("c".to_owned())])],
+                                UnorderedListType::Dash
+                            )
+                        ])],
+                        UnorderedListType::Dash
+                    )
+                ])],
+                UnorderedListType::Dash
+            )]
+        );
+
+        assert_eq!(
+            parse_blocks(md, &ParseOptions::new().tab_width(2).build()),
+            vec![UnorderedList(
+                vec![ListItem::Paragraph(vec![
+                    item("a"),
+                    UnorderedList(
+                        vec![
+                            ListItem::Simple(vec![Text("b".to_owned())]),
+                            ListItem::Simple(vec![Text("c".to_owned())])
+                        ],
+                        UnorderedListType::Dash
+                    )
+                ])],
+                UnorderedListType::Dash
+            )]
+        );
+    }
+
+    #[test]
+    fn tab_width_decides_indented_code() {
+        assert_eq!(
+            parse_blocks("\tcode", &ParseOptions::default()),
+            vec![CodeBlock(None, "code".to_owned())]
+        );
+        assert_eq!(
+            parse_blocks("\tcode", &ParseOptions::new().tab_width(2).build()),
+            vec![Paragraph(vec![Text("code".to_owned())])]
+        );
+        assert_eq!(
+            parse_blocks("\t\tcode", &ParseOptions::new().tab_width(2).build()),
+            vec![CodeBlock(None, "code".to_owned())]
+        );
+    }
+
+    #[test]
+    fn strips_indentation() {
+        let options = ParseOptions::default();
+        assert_eq!(indent_width("  \tx", &options), 4);
+        assert_eq!(indent_width("\t x", &options), 5);
+        assert_eq!(strip_indent("\t\tx", 4, &options), "\tx");
+        assert_eq!(strip_indent("  \tx", 2, &options), "  x");
+        assert_eq!(strip_indent("  x", 4, &options), "x");
+        assert_eq!(strip_indent("    \tx", 4, &options), "\tx");
+        assert_eq!(
+            strip_indent("\tx", 1, &ParseOptions::new().tab_width(2).build()),
+            " x"
+        );
+    }
+
+    #[test]
+    fn highlight_is_inline_only() {
+        assert_eq!(
+            parse_blocks("Title\n===\n\n==a==", &ParseOptions::default()),
+            vec![
+                Header(vec![Text("Title".to_owned())], 1),
+                Paragraph(vec![Highlight(vec![Text("a".to_owned())])])
+            ]
+        );
+    }
+
+    #[test]
+    fn joins_wrapped_images_and_links() {
+        assert_eq!(
+            parse_blocks(
+                "before\n![an image](\n  example.com/image.png\n  \"Title\"\n) after\nnext",
+                &ParseOptions::default()
+            ),
+            vec![Paragraph(vec![
+                Text("before".to_owned()),
+                Text("\n".to_owned()),
+                Image(
+                    "an image".to_owned(),
+                    "example.com/image.png".to_owned(),
+                    Some("Title".to_owned()),
+                    None
+                ),
+                Text(" after".to_owned()),
+                Text("\n".to_owned()),
+                Text("next".to_owned())
+            ])]
+        );
+        assert_eq!(
+            parse_blocks("[a link](/url\n\"Title\")", &ParseOptions::default()),
+            vec![Paragraph(vec![Link(
+                vec![Text("a link".to_owned())],
+                "/url".to_owned(),
+                Some("Title".to_owned())
+            )])]
+        );
+    }
+
+    #[test]
+    fn wrapped_destinations_stop_at_blank_lines() {
+        assert_eq!(
+            parse_blocks("![a](\n\nexample.com)", &ParseOptions::default()).len(),
+            2
+        );
+    }
+
+    #[test]
+    fn bullet_changes_split_lists() {
+        assert_eq!(
+            parse_blocks("- a\n* b", &ParseOptions::default()),
+            vec![
+                UnorderedList(
+                    vec![ListItem::Simple(vec![Text("a".to_owned())])],
+                    UnorderedListType::Dash
+                ),
+                UnorderedList(
+                    vec![ListItem::Simple(vec![Text("b".to_owned())])],
+                    UnorderedListType::Asterisk
+                )
+            ]
+        );
+    }
+
+    #[test]
+    fn limits_list_depth() {
+        let options = ParseOptions::new().max_list_depth(1).build();
+        assert_eq!(
+            parse_blocks("- a\n  - b\n\n1. c", &options),
+            vec![
+                UnorderedList(
+                    vec![ListItem::Simple(vec![
+                        Text("a".to_owned()),
+                        Text("\n".to_owned()),
+                        Text("- b".to_owned())
+                    ])],
+                    UnorderedListType::Dash
+                ),
+                OrderedList(
+                    vec![ListItem::Simple(vec![Text("c".to_owned())])],
+                    OrderedListType::Numeric,
+                    vec!["1".to_owned()]
+                )
+            ]
+        );
+        assert_eq!(
+            parse_blocks("- a", &ParseOptions::new().max_list_depth(0).build()),
+            vec![Paragraph(vec![Text("- a".to_owned())])]
+        );
+
+        fn depth(blocks: &[Block]) -> usize {
+            blocks
+                .iter()
+                .map(|block| match block {
+                    UnorderedList(items, _) | OrderedList(items, _, _) => {
+                        1 + items
+                            .iter()
+                            .map(|item| match item {
+                                ListItem::Paragraph(blocks) => depth(blocks),
+                                _ => 0,
+                            })
+                            .max()
+                            .unwrap_or(0)
+                    }
+                    _ => 0,
+                })
+                .max()
+                .unwrap_or(0)
+        }
+
+        let md: String = (0..100)
+            .map(|i| format!("{}- {}\n", "  ".repeat(i), i))
+            .collect();
+        let blocks = parse_blocks(&md, &ParseOptions::default());
+        assert_eq!(depth(&blocks), 32);
+        let blocks = parse_blocks(&md, &ParseOptions::new().max_list_depth(5).build());
+        assert_eq!(depth(&blocks), 5);
+    }
 }
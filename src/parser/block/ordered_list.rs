@@ -1,21 +1,26 @@
-use parser::block::parse_blocks;
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use parser::block::{indent_width, list_item_options, parse_blocks, parse_task_item, strip_indent};
 use parser::Block;
 use parser::Block::{OrderedList, Paragraph};
 use parser::{ListItem, OrderedListType};
+use parser::{ParseMode, ParseOptions};
 use regex::Regex;
 
-pub fn parse_ordered_list(lines: &[&str]) -> Option<(Block, usize)> {
+pub fn parse_ordered_list(lines: &[&str], options: &ParseOptions) -> Option<(Block, usize)> {
     lazy_static! {
-        static ref LIST_BEGIN: Regex =
-            Regex::new(r"^(?P<indent> *)(?P<numbering>[0-9.]+|[aAiI]+\.) (?P<content>.*)").unwrap();
-        static ref NEW_PARAGRAPH: Regex = Regex::new(r"^ +").unwrap();
-        static ref INDENTED: Regex = Regex::new(r"^ {0,4}(?P<content>.*)").unwrap();
+        static ref LIST_BEGIN: Regex = Regex::new(
+            r"^(?P<indent>[ \t]*)(?P<numbering>[0-9]+(?:\.[0-9]+)*|[a-zA-Z]|[ivxlcdm]+|[IVXLCDM]+)\. (?P<content>.*)"
+        )
+        .unwrap();
+        static ref NEW_PARAGRAPH: Regex = Regex::new(r"^[ \t]+").unwrap();
     }
 
     // if the beginning doesn't match a list don't even bother
     if !LIST_BEGIN.is_match(lines[0]) {
         return None;
     }
+    let item_options = list_item_options(options)?;
 
     // a vec holding the contents and indentation
     // of each list item
@@ -28,7 +33,7 @@ pub fn parse_ordered_list(lines: &[&str]) -> Option<(Block, usize)> {
 
     let mut line_iter = lines.iter();
     let mut line = line_iter.next();
-    let mut list_num_opt = None;
+    let mut numberings = vec![];
 
     // loop for list items
     loop {
@@ -43,11 +48,15 @@ pub fn parse_ordered_list(lines: &[&str]) -> Option<(Block, usize)> {
         let caps = LIST_BEGIN.captures(line.unwrap()).unwrap();
 
         let mut content = caps.name("content").unwrap().as_str().to_owned();
-        let last_indent = caps.name("indent").unwrap().as_str().len();
-        //We use the first list type found
-        // TODO: utf-8 safe?
-        list_num_opt = list_num_opt
-            .or_else(|| Some(caps.name("numbering").unwrap().as_str()[0..1].to_owned()));
+        let last_indent = indent_width(line.unwrap(), options);
+        // continuation lines are stripped of the indentation up to the content
+        // of the item, so that code blocks keep their relative indentation
+        let spaces = content.len() - content.trim_start_matches(' ').len();
+        let content_indent = last_indent
+            + caps.name("content").unwrap().start()
+            + if spaces < 4 { spaces } else { 0 }
+            - caps.name("indent").unwrap().end();
+        numberings.push(caps.name("numbering").unwrap().as_str().to_owned());
         i += 1;
 
         // parse additional lines of the listitem
@@ -58,119 +67,257 @@ pub fn parse_ordered_list(lines: &[&str]) -> Option<(Block, usize)> {
                 break;
             }
 
+            // strict mode doesn't allow lazy continuation lines
+            if options.mode == ParseMode::Strict
+                && !line.unwrap().is_empty()
+                && !NEW_PARAGRAPH.is_match(line.unwrap())
+            {
+                break;
+            }
+
             if LIST_BEGIN.is_match(line.unwrap()) {
-                let caps = LIST_BEGIN.captures(line.unwrap()).unwrap();
-                let indent = caps.name("indent").unwrap().as_str().len();
+                let indent = indent_width(line.unwrap(), options);
                 if indent < 2 || indent <= last_indent {
                     break;
                 }
             }
 
             // newline means we start a new paragraph
-            if line.unwrap().is_empty() {
-                prev_newline = true;
-            } else {
-                prev_newline = false;
-            }
+            prev_newline = line.unwrap().is_empty();
 
             content.push('\n');
-            let caps = INDENTED.captures(line.unwrap()).unwrap();
-            content.push_str(&caps.name("content").unwrap().as_str());
+            content.push_str(&strip_indent(line.unwrap(), content_indent, options));
 
             i += 1;
         }
-        contents.push(parse_blocks(&content));
+        contents.push(content);
     }
 
     let mut list_contents = vec![];
 
-    for c in contents {
+    for content in contents {
+        let c = parse_blocks(&content, &item_options);
         if is_paragraph || c.len() > 1 {
             list_contents.push(ListItem::Paragraph(c));
-        } else if let Paragraph(content) = c[0].clone() {
-            list_contents.push(ListItem::Simple(content));
+        } else if let Some(task) = parse_task_item(&content, &item_options) {
+            list_contents.push(task);
+        } else if let Some(Paragraph(content)) = c.first() {
+            list_contents.push(ListItem::Simple(content.clone()));
+        } else if c.is_empty() {
+            list_contents.push(ListItem::Simple(vec![]));
+        } else {
+            // the item starts with another block, like a blockquote or a code block
+            list_contents.push(ListItem::Paragraph(c));
         }
     }
 
     if i > 0 {
-        let list_num = list_num_opt.unwrap_or("1".to_string());
-        return Some((
-            OrderedList(list_contents, OrderedListType::from_str(&list_num)),
-            i,
-        ));
+        let num_type = list_type(&numberings);
+        return Some((OrderedList(list_contents, num_type, numberings), i));
     }
 
     None
 }
 
+// We use the list type of the first item. A single `i` or `I` is ambiguous,
+// it's treated as roman unless the second item continues the alphabet.
+fn list_type(numberings: &[String]) -> OrderedListType {
+    lazy_static! {
+        static ref ROMAN: Regex = Regex::new(r"^(?:[ivxlcdm]+|[IVXLCDM]+)$").unwrap();
+    }
+
+    let first = &numberings[0];
+    let type_str = if first.starts_with(|c: char| c.is_ascii_digit()) {
+        "1"
+    } else if first == "i" || first == "I" {
+        match numberings.get(1).map(|n| n.as_str()) {
+            Some("j") => "a",
+            Some("J") => "A",
+            _ => first,
+        }
+    } else if first.len() > 1 && ROMAN.is_match(first) {
+        if first.starts_with(|c: char| c.is_lowercase()) {
+            "i"
+        } else {
+            "I"
+        }
+    } else if first.starts_with(|c: char| c.is_lowercase()) {
+        "a"
+    } else {
+        "A"
+    };
+
+    OrderedListType::from_str(type_str)
+}
+
 #[cfg(test)]
 mod test {
     use super::parse_ordered_list;
-    use parser::Block::OrderedList;
+    use parser::Block::{Blockquote, CodeBlock, OrderedList};
     use parser::ListItem::Paragraph;
     use parser::OrderedListType;
+    use parser::ParseOptions;
+    use parser::Span::Text;
 
     #[test]
     fn finds_list() {
-        match parse_ordered_list(&vec!["1. A list", "2. is good"]) {
-            Some((OrderedList(_, lt), 2)) if lt == OrderedListType::Numeric => (),
+        match parse_ordered_list(&["1. A list", "2. is good"], &ParseOptions::default()) {
+            Some((OrderedList(_, OrderedListType::Numeric, _), 2)) => (),
             x => panic!("Found {:?}", x),
         }
 
-        match parse_ordered_list(&vec!["a. A list", "b. is good", "laksjdnflakdsjnf"]) {
-            Some((OrderedList(_, lt), 3)) if lt == OrderedListType::Lowercase => (),
+        match parse_ordered_list(
+            &["a. A list", "b. is good", "laksjdnflakdsjnf"],
+            &ParseOptions::default(),
+        ) {
+            Some((OrderedList(_, OrderedListType::Lowercase, _), 3)) => (),
             x => panic!("Found {:?}", x),
         }
 
-        match parse_ordered_list(&vec!["A. A list", "B. is good", "laksjdnflakdsjnf"]) {
-            Some((OrderedList(_, lt), 3)) if lt == OrderedListType::Uppercase => (),
+        match parse_ordered_list(
+            &["A. A list", "B. is good", "laksjdnflakdsjnf"],
+            &ParseOptions::default(),
+        ) {
+            Some((OrderedList(_, OrderedListType::Uppercase, _), 3)) => (),
             x => panic!("Found {:?}", x),
         }
     }
 
     #[test]
     fn knows_when_to_stop() {
-        match parse_ordered_list(&vec!["i. A list", "ii. is good", "", "laksjdnflakdsjnf"]) {
-            Some((OrderedList(_, lt), 3)) if lt == OrderedListType::LowercaseRoman => (),
+        match parse_ordered_list(
+            &["i. A list", "ii. is good", "", "laksjdnflakdsjnf"],
+            &ParseOptions::default(),
+        ) {
+            Some((OrderedList(_, OrderedListType::LowercaseRoman, _), 3)) => (),
+            x => panic!("Found {:?}", x),
+        }
+
+        match parse_ordered_list(
+            &["I. A list", "", "laksjdnflakdsjnf"],
+            &ParseOptions::default(),
+        ) {
+            Some((OrderedList(_, OrderedListType::UppercaseRoman, _), 2)) => (),
+            x => panic!("Found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn finds_list_types() {
+        match parse_ordered_list(&["1. A list", "2. is good"], &ParseOptions::default()) {
+            Some((OrderedList(_, OrderedListType::Numeric, _), 2)) => (),
+            x => panic!("Found {:?}", x),
+        }
+
+        match parse_ordered_list(&["c. A list", "d. is good"], &ParseOptions::default()) {
+            Some((OrderedList(_, OrderedListType::Lowercase, _), 2)) => (),
+            x => panic!("Found {:?}", x),
+        }
+
+        match parse_ordered_list(&["X. A list", "Y. is good"], &ParseOptions::default()) {
+            Some((OrderedList(_, OrderedListType::Uppercase, _), 2)) => (),
+            x => panic!("Found {:?}", x),
+        }
+
+        match parse_ordered_list(&["iv. A list", "v. is good"], &ParseOptions::default()) {
+            Some((OrderedList(_, OrderedListType::LowercaseRoman, _), 2)) => (),
             x => panic!("Found {:?}", x),
         }
 
-        match parse_ordered_list(&vec!["I. A list", "", "laksjdnflakdsjnf"]) {
-            Some((OrderedList(_, lt), 2)) if lt == OrderedListType::UppercaseRoman => (),
+        match parse_ordered_list(&["III. A list", "IV. is good"], &ParseOptions::default()) {
+            Some((OrderedList(_, OrderedListType::UppercaseRoman, _), 2)) => (),
+            x => panic!("Found {:?}", x),
+        }
+    }
+
+    #[test]
+    fn disambiguates_roman_and_alpha() {
+        match parse_ordered_list(&["i. A list", "ii. is good"], &ParseOptions::default()) {
+            Some((OrderedList(_, OrderedListType::LowercaseRoman, _), 2)) => (),
+            x => panic!("Found {:?}", x),
+        }
+
+        match parse_ordered_list(&["i. A list", "j. is good"], &ParseOptions::default()) {
+            Some((OrderedList(_, OrderedListType::Lowercase, _), 2)) => (),
+            x => panic!("Found {:?}", x),
+        }
+
+        match parse_ordered_list(&["I. A list"], &ParseOptions::default()) {
+            Some((OrderedList(_, OrderedListType::UppercaseRoman, _), 1)) => (),
+            x => panic!("Found {:?}", x),
+        }
+
+        match parse_ordered_list(&["I. A list", "J. is good"], &ParseOptions::default()) {
+            Some((OrderedList(_, OrderedListType::Uppercase, _), 2)) => (),
             x => panic!("Found {:?}", x),
         }
     }
 
     #[test]
     fn multi_level_list() {
-        match parse_ordered_list(&vec![
-            "1. A list",
-            "     1.1. One point one",
-            "     1.2. One point two",
-        ]) {
-            Some((OrderedList(ref items, lt), 3)) if lt == OrderedListType::Numeric => {
-                match &items[0] {
-                    &Paragraph(ref items) => match &items[1] {
-                        &OrderedList(_, ref lt1) if lt1 == &OrderedListType::Numeric => (),
-                        x => panic!("Found {:?}", x),
-                    },
+        match parse_ordered_list(
+            &[
+                "1. A list",
+                "     1.1. One point one",
+                "     1.2. One point two",
+            ],
+            &ParseOptions::default(),
+        ) {
+            Some((OrderedList(ref items, OrderedListType::Numeric, _), 3)) => match &items[0] {
+                Paragraph(items) => match &items[1] {
+                    OrderedList(_, OrderedListType::Numeric, _) => (),
                     x => panic!("Found {:?}", x),
-                }
-            }
+                },
+                x => panic!("Found {:?}", x),
+            },
             x => panic!("Found {:?}", x),
         }
     }
 
     #[test]
     fn no_false_positives() {
-        assert_eq!(parse_ordered_list(&vec!["test 1. test"]), None);
+        assert_eq!(
+            parse_ordered_list(&["test 1. test"], &ParseOptions::default()),
+            None
+        );
+        assert_eq!(
+            parse_ordered_list(&["2019 was a year"], &ParseOptions::default()),
+            None
+        );
+        assert_eq!(
+            parse_ordered_list(&["ab. test"], &ParseOptions::default()),
+            None
+        );
     }
 
     #[test]
     fn no_early_matching() {
         assert_eq!(
-            parse_ordered_list(&vec!["test", "1. not", "2. a list"]),
+            parse_ordered_list(&["test", "1. not", "2. a list"], &ParseOptions::default()),
             None
         );
     }
+
+    #[test]
+    fn parses_items_starting_with_a_block() {
+        assert_eq!(
+            parse_ordered_list(
+                &["1. > quote", "2. ```", "   code", "   ```"],
+                &ParseOptions::default()
+            ),
+            Some((
+                OrderedList(
+                    vec![
+                        Paragraph(vec![Blockquote(vec![::parser::Block::Paragraph(vec![
+                            Text("quote".to_owned())
+                        ])])]),
+                        Paragraph(vec![CodeBlock(Some(String::new()), "code".to_owned())])
+                    ],
+                    OrderedListType::Numeric,
+                    vec!["1".to_owned(), "2".to_owned()]
+                ),
+                4
+            ))
+        );
+    }
 }
@@ -1,19 +1,24 @@
 use parser::span::parse_spans;
 use parser::Block;
 use parser::Block::Header;
+use parser::ParseOptions;
 use regex::Regex;
 
-pub fn parse_setext_header(lines: &[&str]) -> Option<(Block, usize)> {
+pub fn parse_setext_header(lines: &[&str], options: &ParseOptions) -> Option<(Block, usize)> {
     lazy_static! {
         static ref HORIZONTAL_RULE_1: Regex = Regex::new(r"^===+$").unwrap();
         static ref HORIZONTAL_RULE_2: Regex = Regex::new(r"^---+$").unwrap();
     }
 
+    if !options.setext_headers {
+        return None;
+    }
+
     if lines.len() > 1 && !lines[0].is_empty() {
         if HORIZONTAL_RULE_1.is_match(lines[1]) {
-            return Some((Header(parse_spans(lines[0]), 1), 2));
+            return Some((Header(parse_spans(lines[0], options), 1), 2));
         } else if HORIZONTAL_RULE_2.is_match(lines[1]) {
-            return Some((Header(parse_spans(lines[0]), 2), 2));
+            return Some((Header(parse_spans(lines[0], options), 2), 2));
         }
     }
     None
@@ -23,27 +28,28 @@ pub fn parse_setext_header(lines: &[&str]) -> Option<(Block, usize)> {
 mod test {
     use super::parse_setext_header;
     use parser::Block::Header;
+    use parser::ParseOptions;
     use parser::Span::Text;
 
     #[test]
     fn finds_atx_header() {
         assert_eq!(
-            parse_setext_header(&vec!["Test", "=========="]).unwrap(),
+            parse_setext_header(&["Test", "=========="], &ParseOptions::default()).unwrap(),
             (Header(vec![Text("Test".to_owned())], 1), 2)
         );
 
         assert_eq!(
-            parse_setext_header(&vec!["Test", "----------"]).unwrap(),
+            parse_setext_header(&["Test", "----------"], &ParseOptions::default()).unwrap(),
             (Header(vec![Text("Test".to_owned())], 2), 2)
         );
 
         assert_eq!(
-            parse_setext_header(&vec!["This is a test", "==="]).unwrap(),
+            parse_setext_header(&["This is a test", "==="], &ParseOptions::default()).unwrap(),
             (Header(vec![Text("This is a test".to_owned())], 1), 2)
         );
 
         assert_eq!(
-            parse_setext_header(&vec!["This is a test", "---"]).unwrap(),
+            parse_setext_header(&["This is a test", "---"], &ParseOptions::default()).unwrap(),
             (Header(vec![Text("This is a test".to_owned())], 2), 2)
         );
     }
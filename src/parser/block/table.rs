@@ -0,0 +1,247 @@
+use alloc::vec::Vec;
+use parser::block::is_blank;
+use parser::span::parse_spans;
+use parser::Block::Table;
+use parser::{Alignment, Block, ParseOptions, Span};
+use regex::Regex;
+
+pub fn parse_table(lines: &[&str], options: &ParseOptions) -> Option<(Block, usize)> {
+    lazy_static! {
+        static ref DELIMITER_CELL: Regex = Regex::new(r"^(:?)-+(:?)$").unwrap();
+    }
+
+    // a table needs a header row and a delimiter row below it
+    if lines.len() < 2 || !lines[0].contains('|') {
+        return None;
+    }
+
+    let header = split_row(lines[0]);
+    let alignments = split_row(lines[1])
+        .iter()
+        .map(|cell| {
+            let caps = DELIMITER_CELL.captures(cell)?;
+            Some(match (&caps[1], &caps[2]) {
+                (":", ":") => Alignment::Center,
+                (":", _) => Alignment::Left,
+                (_, ":") => Alignment::Right,
+                _ => Alignment::None,
+            })
+        })
+        .collect::<Option<Vec<Alignment>>>()?;
+
+    // like in GFM, the delimiter row has to match the header cell for cell,
+    // otherwise the lines are left to be parsed as a paragraph
+    if alignments.len() != header.len() {
+        return None;
+    }
+
+    // the table ends at the first blank line or line without a pipe
+    let mut i = 2;
+    let mut rows = vec![];
+    while i < lines.len() && lines[i].contains('|') && !is_blank(lines[i]) {
+        rows.push(parse_row(lines[i], header.len(), options));
+        i += 1;
+    }
+
+    Some((
+        Table(
+            alignments,
+            header
+                .iter()
+                .map(|cell| parse_spans(cell, options))
+                .collect(),
+            rows,
+        ),
+        i,
+    ))
+}
+
+// Rows with fewer cells than the header are filled up with empty cells,
+// excess cells are dropped.
+fn parse_row(line: &str, columns: usize, options: &ParseOptions) -> Vec<Vec<Span>> {
+    let mut row: Vec<Vec<Span>> = split_row(line)
+        .iter()
+        .take(columns)
+        .map(|cell| parse_spans(cell, options))
+        .collect();
+    row.resize(columns, vec![]);
+    row
+}
+
+// Splits a row at every pipe that isn't escaped or inside of a code span,
+// ignoring the optional pipes at the start and end of the row.
+fn split_row(line: &str) -> Vec<&str> {
+    let line = line.trim();
+    let line = line.strip_prefix('|').unwrap_or(line);
+    let line = match line.strip_suffix('|') {
+        Some(rest) if !rest.ends_with('\\') => rest,
+        _ => line,
+    };
+
+    let mut cells = vec![];
+    let mut start = 0;
+    let mut in_code = false;
+    let mut escaped = false;
+    for (i, c) in line.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '`' => in_code = !in_code,
+            '|' if !in_code => {
+                cells.push(line[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    cells.push(line[start..].trim());
+    cells
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_table, split_row};
+    use parser::Alignment;
+    use parser::Block::Table;
+    use parser::ParseOptions;
+    use parser::Span::{Code, Literal, Text};
+
+    #[test]
+    fn finds_table() {
+        assert_eq!(
+            parse_table(
+                &["| a | b |", "|:--|--:|", "| 1 | 2 |", "text"],
+                &ParseOptions::default()
+            ),
+            Some((
+                Table(
+                    vec![Alignment::Left, Alignment::Right],
+                    vec![vec![Text("a".to_owned())], vec![Text("b".to_owned())]],
+                    vec![vec![vec![Text("1".to_owned())], vec![Text("2".to_owned())]]]
+                ),
+                3
+            ))
+        );
+    }
+
+    #[test]
+    fn finds_table_without_outer_pipes() {
+        assert_eq!(
+            parse_table(&["a | b", ":-: | ---", ""], &ParseOptions::default()),
+            Some((
+                Table(
+                    vec![Alignment::Center, Alignment::None],
+                    vec![vec![Text("a".to_owned())], vec![Text("b".to_owned())]],
+                    vec![]
+                ),
+                2
+            ))
+        );
+    }
+
+    #[test]
+    fn finds_empty_cells() {
+        assert_eq!(
+            parse_table(
+                &["| a || c |", "|---|---|---|", "|| 2 |"],
+                &ParseOptions::default()
+            ),
+            Some((
+                Table(
+                    vec![Alignment::None, Alignment::None, Alignment::None],
+                    vec![
+                        vec![Text("a".to_owned())],
+                        vec![],
+                        vec![Text("c".to_owned())]
+                    ],
+                    vec![vec![vec![], vec![Text("2".to_owned())], vec![]]]
+                ),
+                3
+            ))
+        );
+    }
+
+    #[test]
+    fn outer_pipes_are_optional_on_every_row() {
+        assert_eq!(
+            parse_table(
+                &["a | b |", "--- | ---", "| 1 | 2", "3 | 4"],
+                &ParseOptions::default()
+            ),
+            Some((
+                Table(
+                    vec![Alignment::None, Alignment::None],
+                    vec![vec![Text("a".to_owned())], vec![Text("b".to_owned())]],
+                    vec![
+                        vec![vec![Text("1".to_owned())], vec![Text("2".to_owned())]],
+                        vec![vec![Text("3".to_owned())], vec![Text("4".to_owned())]]
+                    ]
+                ),
+                4
+            ))
+        );
+    }
+
+    #[test]
+    fn splits_cells() {
+        assert_eq!(split_row("| a | b |"), vec!["a", "b"]);
+        assert_eq!(split_row("a|b"), vec!["a", "b"]);
+        assert_eq!(split_row("| a \\| b |"), vec!["a \\| b"]);
+        assert_eq!(split_row("| `a | b` | c |"), vec!["`a | b`", "c"]);
+        assert_eq!(split_row("| |"), vec![""]);
+        assert_eq!(split_row("| a || c |"), vec!["a", "", "c"]);
+    }
+
+    #[test]
+    fn parses_cell_content() {
+        assert_eq!(
+            parse_table(
+                &["| `a|b` | c\\|d |", "|---|---|"],
+                &ParseOptions::default()
+            ),
+            Some((
+                Table(
+                    vec![Alignment::None, Alignment::None],
+                    vec![
+                        vec![Code("a|b".to_owned())],
+                        vec![Text("c".to_owned()), Literal('|'), Text("d".to_owned())]
+                    ],
+                    vec![]
+                ),
+                2
+            ))
+        );
+    }
+
+    #[test]
+    fn no_false_positives() {
+        let options = ParseOptions::default();
+        assert_eq!(parse_table(&["| a | b |"], &options), None);
+        assert_eq!(parse_table(&["a", "---"], &options), None);
+        assert_eq!(parse_table(&["| a | b |", "| x | y |"], &options), None);
+        assert_eq!(parse_table(&["| a | b |", "|---|"], &options), None);
+        assert_eq!(parse_table(&["| a |", "|---|---|"], &options), None);
+        assert_eq!(parse_table(&["a | b", "---"], &options), None);
+    }
+
+    #[test]
+    fn normalizes_row_length() {
+        assert_eq!(
+            parse_table(
+                &["| a | b |", "|---|---|", "| 1 |", "| 1 | 2 | 3 |"],
+                &ParseOptions::default()
+            ),
+            Some((
+                Table(
+                    vec![Alignment::None, Alignment::None],
+                    vec![vec![Text("a".to_owned())], vec![Text("b".to_owned())]],
+                    vec![
+                        vec![vec![Text("1".to_owned())], vec![]],
+                        vec![vec![Text("1".to_owned())], vec![Text("2".to_owned())]]
+                    ]
+                ),
+                4
+            ))
+        );
+    }
+}
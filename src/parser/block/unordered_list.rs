@@ -1,21 +1,24 @@
-use parser::block::parse_blocks;
+use alloc::borrow::ToOwned;
+use parser::block::{indent_width, list_item_options, parse_blocks, parse_task_item, strip_indent};
 use parser::Block;
 use parser::Block::{Paragraph, UnorderedList};
-use parser::ListItem;
+use parser::{ListItem, UnorderedListType};
+use parser::{ParseMode, ParseOptions};
 use regex::Regex;
 
-pub fn parse_unordered_list(lines: &[&str]) -> Option<(Block, usize)> {
+pub fn parse_unordered_list(lines: &[&str], options: &ParseOptions) -> Option<(Block, usize)> {
     lazy_static! {
         static ref LIST_BEGIN: Regex =
-            Regex::new(r"^(?P<indent> *)(-|\+|\*) (?P<content>.*)").unwrap();
-        static ref NEW_PARAGRAPH: Regex = Regex::new(r"^ +").unwrap();
-        static ref INDENTED: Regex = Regex::new(r"^ {0,4}(?P<content>.*)").unwrap();
+            Regex::new(r"^(?P<indent>[ \t]*)(?P<bullet>[-+*]) (?P<content>.*)").unwrap();
+        static ref NEW_PARAGRAPH: Regex = Regex::new(r"^[ \t]+").unwrap();
     }
 
     // if the beginning doesn't match a list don't even bother
-    if !LIST_BEGIN.is_match(lines[0]) {
-        return None;
-    }
+    let bullet = match LIST_BEGIN.captures(lines[0]) {
+        Some(caps) => caps["bullet"].chars().next().unwrap(),
+        None => return None,
+    };
+    let item_options = list_item_options(options)?;
 
     // a vec holding the contents and indentation
     // of each list item
@@ -31,8 +34,10 @@ pub fn parse_unordered_list(lines: &[&str]) -> Option<(Block, usize)> {
 
     // loop for list items
     loop {
-        if line.is_none() || !LIST_BEGIN.is_match(line.unwrap()) {
-            break;
+        // an item with a different bullet starts a new list
+        match line.and_then(|line| LIST_BEGIN.captures(line)) {
+            Some(ref caps) if caps["bullet"].starts_with(bullet) => {}
+            _ => break,
         }
         if prev_newline {
             is_paragraph = true;
@@ -42,7 +47,14 @@ pub fn parse_unordered_list(lines: &[&str]) -> Option<(Block, usize)> {
         let caps = LIST_BEGIN.captures(line.unwrap()).unwrap();
 
         let mut content = caps.name("content").unwrap().as_str().to_owned();
-        let last_indent = caps.name("indent").unwrap().as_str().len();
+        let last_indent = indent_width(line.unwrap(), options);
+        // continuation lines are stripped of the indentation up to the content
+        // of the item, so that code blocks keep their relative indentation
+        let spaces = content.len() - content.trim_start_matches(' ').len();
+        let content_indent = last_indent
+            + caps.name("content").unwrap().start()
+            + if spaces < 4 { spaces } else { 0 }
+            - caps.name("indent").unwrap().end();
         i += 1;
 
         // parse additional lines of the listitem
@@ -53,42 +65,55 @@ pub fn parse_unordered_list(lines: &[&str]) -> Option<(Block, usize)> {
                 break;
             }
 
+            // strict mode doesn't allow lazy continuation lines
+            if options.mode == ParseMode::Strict
+                && !line.unwrap().is_empty()
+                && !NEW_PARAGRAPH.is_match(line.unwrap())
+            {
+                break;
+            }
+
             if LIST_BEGIN.is_match(line.unwrap()) {
-                let caps = LIST_BEGIN.captures(line.unwrap()).unwrap();
-                let indent = caps.name("indent").unwrap().as_str().len();
+                let indent = indent_width(line.unwrap(), options);
                 if indent < 2 || indent <= last_indent {
                     break;
                 }
             }
 
             // newline means we start a new paragraph
-            if line.unwrap().is_empty() {
-                prev_newline = true;
-            } else {
-                prev_newline = false;
-            }
+            prev_newline = line.unwrap().is_empty();
 
             content.push('\n');
-            let caps = INDENTED.captures(line.unwrap()).unwrap();
-            content.push_str(&caps.name("content").unwrap().as_str());
+            content.push_str(&strip_indent(line.unwrap(), content_indent, options));
 
             i += 1;
         }
-        contents.push(parse_blocks(&content));
+        contents.push(content);
     }
 
     let mut list_contents = vec![];
 
-    for c in contents {
+    for content in contents {
+        let c = parse_blocks(&content, &item_options);
         if is_paragraph || c.len() > 1 {
             list_contents.push(ListItem::Paragraph(c));
-        } else if let Paragraph(content) = c[0].clone() {
-            list_contents.push(ListItem::Simple(content));
+        } else if let Some(task) = parse_task_item(&content, &item_options) {
+            list_contents.push(task);
+        } else if let Some(Paragraph(content)) = c.first() {
+            list_contents.push(ListItem::Simple(content.clone()));
+        } else if c.is_empty() {
+            list_contents.push(ListItem::Simple(vec![]));
+        } else {
+            // the item starts with another block, like a blockquote or a code block
+            list_contents.push(ListItem::Paragraph(c));
         }
     }
 
     if i > 0 {
-        return Some((UnorderedList(list_contents), i));
+        return Some((
+            UnorderedList(list_contents, UnorderedListType::from_char(bullet)),
+            i,
+        ));
     }
 
     None
@@ -97,44 +122,187 @@ pub fn parse_unordered_list(lines: &[&str]) -> Option<(Block, usize)> {
 #[cfg(test)]
 mod test {
     use super::parse_unordered_list;
-    use parser::Block::UnorderedList;
+    use parser::Block::{Blockquote, CodeBlock, Paragraph, UnorderedList};
+    use parser::Span::Text;
+    use parser::{ListItem, ParseOptions, UnorderedListType};
 
     #[test]
     fn finds_list() {
-        match parse_unordered_list(&vec!["* A list", "* is good"]) {
-            Some((UnorderedList(_), 2)) => (),
+        match parse_unordered_list(&["* A list", "* is good"], &ParseOptions::default()) {
+            Some((UnorderedList(..), 2)) => (),
             x => panic!("Found {:?}", x),
         }
 
-        match parse_unordered_list(&vec!["* A list", "* is good", "laksjdnflakdsjnf"]) {
-            Some((UnorderedList(_), 3)) => (),
+        match parse_unordered_list(
+            &["* A list", "* is good", "laksjdnflakdsjnf"],
+            &ParseOptions::default(),
+        ) {
+            Some((UnorderedList(..), 3)) => (),
             x => panic!("Found {:?}", x),
         }
     }
 
     #[test]
     fn knows_when_to_stop() {
-        match parse_unordered_list(&vec!["* A list", "* is good", "", "laksjdnflakdsjnf"]) {
-            Some((UnorderedList(_), 3)) => (),
+        match parse_unordered_list(
+            &["* A list", "* is good", "", "laksjdnflakdsjnf"],
+            &ParseOptions::default(),
+        ) {
+            Some((UnorderedList(..), 3)) => (),
             x => panic!("Found {:?}", x),
         }
 
-        match parse_unordered_list(&vec!["* A list", "", "laksjdnflakdsjnf"]) {
-            Some((UnorderedList(_), 2)) => (),
+        match parse_unordered_list(
+            &["* A list", "", "laksjdnflakdsjnf"],
+            &ParseOptions::default(),
+        ) {
+            Some((UnorderedList(..), 2)) => (),
             x => panic!("Found {:?}", x),
         }
     }
 
+    #[test]
+    fn strips_list_indentation_from_fenced_code() {
+        assert_eq!(
+            parse_unordered_list(
+                &[
+                    "- item",
+                    "",
+                    "  ```rust",
+                    "  fn main() {",
+                    "      x",
+                    "  }",
+                    "  ```"
+                ],
+                &ParseOptions::default()
+            ),
+            Some((
+                UnorderedList(
+                    vec![ListItem::Paragraph(vec![
+                        Paragraph(vec![Text("item".to_owned())]),
+                        CodeBlock(Some("rust".to_owned()), "fn main() {\n    x\n}".to_owned())
+                    ])],
+                    UnorderedListType::Dash
+                ),
+                7
+            ))
+        );
+
+        assert_eq!(
+            parse_unordered_list(
+                &["*   item", "", "    ```", "    a", "      b", "    ```"],
+                &ParseOptions::default()
+            ),
+            Some((
+                UnorderedList(
+                    vec![ListItem::Paragraph(vec![
+                        Paragraph(vec![Text("item".to_owned())]),
+                        CodeBlock(Some(String::new()), "a\n  b".to_owned())
+                    ])],
+                    UnorderedListType::Asterisk
+                ),
+                6
+            ))
+        );
+    }
+
     #[test]
     fn no_false_positives() {
-        assert_eq!(parse_unordered_list(&vec!["test * test"]), None);
+        assert_eq!(
+            parse_unordered_list(&["test * test"], &ParseOptions::default()),
+            None
+        );
     }
 
     #[test]
     fn no_early_matching() {
         assert_eq!(
-            parse_unordered_list(&vec!["test", "* whot", "* a list"]),
+            parse_unordered_list(&["test", "* whot", "* a list"], &ParseOptions::default()),
             None
         );
     }
+
+    #[test]
+    fn different_bullets_start_a_new_list() {
+        let options = ParseOptions::default();
+        assert_eq!(
+            parse_unordered_list(&["- a", "* b"], &options),
+            Some((
+                UnorderedList(
+                    vec![ListItem::Simple(vec![Text("a".to_owned())])],
+                    UnorderedListType::Dash
+                ),
+                1
+            ))
+        );
+        assert_eq!(
+            parse_unordered_list(&["+ a", "+ b", "- c"], &options),
+            Some((
+                UnorderedList(
+                    vec![
+                        ListItem::Simple(vec![Text("a".to_owned())]),
+                        ListItem::Simple(vec![Text("b".to_owned())])
+                    ],
+                    UnorderedListType::Plus
+                ),
+                2
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_items_starting_with_a_block() {
+        assert_eq!(
+            parse_unordered_list(&["- > quote", "- b"], &ParseOptions::default()),
+            Some((
+                UnorderedList(
+                    vec![
+                        ListItem::Paragraph(vec![Blockquote(vec![Paragraph(vec![Text(
+                            "quote".to_owned()
+                        )])])]),
+                        ListItem::Simple(vec![Text("b".to_owned())])
+                    ],
+                    UnorderedListType::Dash
+                ),
+                2
+            ))
+        );
+
+        assert_eq!(
+            parse_unordered_list(
+                &["* ```rust", "  let a = 1;", "  ```", "* b"],
+                &ParseOptions::default()
+            ),
+            Some((
+                UnorderedList(
+                    vec![
+                        ListItem::Paragraph(vec![CodeBlock(
+                            Some("rust".to_owned()),
+                            "let a = 1;".to_owned()
+                        )]),
+                        ListItem::Simple(vec![Text("b".to_owned())])
+                    ],
+                    UnorderedListType::Asterisk
+                ),
+                4
+            ))
+        );
+    }
+
+    #[test]
+    fn parses_empty_items() {
+        assert_eq!(
+            parse_unordered_list(&["- ", "- b"], &ParseOptions::default()),
+            Some((
+                UnorderedList(
+                    vec![
+                        ListItem::Simple(vec![]),
+                        ListItem::Simple(vec![Text("b".to_owned())])
+                    ],
+                    UnorderedListType::Dash
+                ),
+                2
+            ))
+        );
+    }
 }
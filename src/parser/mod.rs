@@ -1,3 +1,11 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+use core::fmt;
+use core::ops::Range;
+use regex::Regex;
+
 mod block;
 mod span;
 
@@ -33,19 +41,76 @@ impl OrderedListType {
     }
 }
 
+/// The bullet character of an unordered list
+///
+/// Like in CommonMark, a list ends when the next item uses a different bullet.
+#[allow(missing_docs)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum UnorderedListType {
+    Dash,
+    Asterisk,
+    Plus,
+}
+
+impl UnorderedListType {
+    /// Returns the type of a bullet character, defaulting to `Dash`
+    pub fn from_char(bullet: char) -> UnorderedListType {
+        match bullet {
+            '*' => UnorderedListType::Asterisk,
+            '+' => UnorderedListType::Plus,
+            _ => UnorderedListType::Dash,
+        }
+    }
+
+    /// Returns the bullet character
+    pub fn to_char(self) -> char {
+        match self {
+            UnorderedListType::Dash => '-',
+            UnorderedListType::Asterisk => '*',
+            UnorderedListType::Plus => '+',
+        }
+    }
+}
+
 #[allow(missing_docs)]
 #[derive(Debug, PartialEq, Clone)]
 pub enum Block {
     Header(Vec<Span>, usize),
     Paragraph(Vec<Span>),
+    /** A paragraph starting with `^^^ `, see `ParseOptions::lead_paragraphs` **/
+    LeadParagraph(Vec<Span>),
     Blockquote(Vec<Block>),
     CodeBlock(Option<String>, String),
+    /** An HTML comment, holding the text between `<!--` and `-->`, which doesn't contain `--` **/
+    Comment(String),
+    /** A collapsible `<details>` block with the fields: (summary, content) **/
+    Details(Vec<Span>, Vec<Block>),
+    /** A Pandoc-style fenced div like `::: warning` with the fields: (classes, content) **/
+    Div(Vec<String>, Vec<Block>),
+    /** A footnote definition like `[^id]: text` with the fields: (id, content) **/
+    FootnoteDefinition(String, Vec<Block>),
     /** A link reference with the fields: (id, url, [title]) **/
     LinkReference(String, String, Option<String>),
-    OrderedList(Vec<ListItem>, OrderedListType),
-    UnorderedList(Vec<ListItem>),
+    /** An ordered list with the fields: (items, numbering type, the number of each item as
+    it's written, e.g. `3`, `c` or `iv`) **/
+    OrderedList(Vec<ListItem>, OrderedListType, Vec<String>),
+    UnorderedList(Vec<ListItem>, UnorderedListType),
     Raw(String),
     Hr,
+    /** Lines starting with `| ` whose line breaks are kept, holding each line **/
+    LineBlock(Vec<Vec<Span>>),
+    /** A table with the fields: (column alignments, header cells, rows of cells) **/
+    Table(Vec<Alignment>, Vec<Vec<Span>>, Vec<Vec<Vec<Span>>>),
+}
+
+/// The alignment of a table column, given by the colons in its delimiter row
+#[allow(missing_docs)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Alignment {
+    None,
+    Left,
+    Center,
+    Right,
 }
 
 #[allow(missing_docs)]
@@ -53,14 +118,29 @@ pub enum Block {
 pub enum ListItem {
     Simple(Vec<Span>),
     Paragraph(Vec<Block>),
+    /** A tight list item starting with a `[ ]`, `[x]` or `[-]` checkbox **/
+    Task(TaskState, Vec<Span>),
+}
+
+#[allow(missing_docs)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum TaskState {
+    Unchecked,
+    Checked,
+    Indeterminate,
 }
 
 #[allow(missing_docs)]
 #[derive(Debug, PartialEq, Clone)]
 pub enum Span {
+    /** A hard line break, written as two or more spaces at the end of a line **/
     Break,
     Text(String),
     Code(String),
+    /** An inline HTML comment, holding the text between `<!--` and `-->`, which doesn't contain `--` **/
+    Comment(String),
+    /** An inline HTML tag like `<span class="x">` or `</span>` **/
+    Html(String),
     Literal(char),
     Link(Vec<Span>, String, Option<String>),
     /**
@@ -69,12 +149,445 @@ pub enum Span {
      * markdown link if the corresponding reference is not found at render time.
      **/
     RefLink(Vec<Span>, String, String),
-    Image(String, String, Option<String>),
+    /**
+     * An image with the fields: (alt, url, \[title\], \[size\])
+     * The alt text is kept as it's written, including any markup like
+     * `**bold**`, which is stripped when rendering the `alt` attribute.
+     **/
+    Image(String, String, Option<String>, Option<ObjectSize>),
+    /**
+     * A link with a size like `[a video](movie.mp4 =640x360)`, which embeds
+     * the object it links to, with the fields: (content, url, \[title\], size)
+     * The size is written like the size of an image.
+     **/
+    Object(Vec<Span>, String, Option<String>, ObjectSize),
+    /** A reference to a footnote like `[^id]`, holding the id **/
+    FootnoteReference(String),
+    /** A footnote written inline like `^[a note]`, holding its content **/
+    InlineFootnote(Vec<Span>),
+    /** Keyboard input like `[[Ctrl+C]]`, holding the keys, see `ParseOptions::kbd` **/
+    Kbd(String),
 
     Emphasis(Vec<Span>),
     Strong(Vec<Span>),
+    /** Highlighted text like `==marked==` **/
+    Highlight(Vec<Span>),
+    /** Struck through text like `~~gone~~`, see `ParseOptions::single_tilde_strikethrough` **/
+    Strikethrough(Vec<Span>),
+}
+
+/// The dimensions of an image or an object, given as `=WIDTHxHEIGHT` after its url or as
+/// attributes like `{width=100 height=50}` following the image
+///
+/// Either dimension may be left out, e.g. `=100x` only sets the width.
+#[allow(missing_docs)]
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub struct ObjectSize {
+    pub width: Option<Dimension>,
+    pub height: Option<Dimension>,
+}
+
+/// A single dimension of an image, e.g. `100` or `50%`
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Dimension {
+    /// A number of pixels, written as `100` or `100px`
+    Pixels(u32),
+    /// A percentage, written as `50%`
+    Percent(u32),
+}
+
+impl fmt::Display for Dimension {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            Dimension::Pixels(value) => write!(f, "{}", value),
+            Dimension::Percent(value) => write!(f, "{}%", value),
+        }
+    }
+}
+
+/// How ambiguous constructs that CommonMark and older Markdown
+/// implementations disagree on are parsed
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum ParseMode {
+    /// Follow the stricter rules:
+    ///
+    /// - underscores inside of a word (`snake_case_name`) don't start or end emphasis
+    /// - every continuation line of a list item has to be indented
+    /// - a list needs a blank line before it when it follows a paragraph
+    Strict,
+    /// Accept the more lenient syntax a lot of existing documents rely on:
+    ///
+    /// - underscores inside of a word can start and end emphasis
+    /// - unindented lines directly following a list item continue it
+    /// - a list can start on the line right after a paragraph
+    #[default]
+    Relaxed,
+}
+
+/// The text shown for a bare URL that is linked, see `ParseOptions::autolinks`
+///
+/// Either way the link points to the full URL, e.g. `http://www.example.com`
+/// for `www.example.com`.
+#[derive(Debug, Default, PartialEq, Eq, Clone, Copy)]
+pub enum AutolinkText {
+    /// Leave out the `http://` or `https://` scheme, e.g. `example.com/a`
+    #[default]
+    Short,
+    /// The URL as it's written in the document, e.g. `https://example.com/a`
+    Original,
+}
+
+/// Options that control how Markdown is parsed
+///
+/// The default options parse documents the same way as `tokenize`.
+#[derive(Debug, PartialEq, Clone)]
+pub struct ParseOptions {
+    /// How ambiguous constructs are parsed
+    pub mode: ParseMode,
+    /// Replace known GitHub-style shortcodes like `:smile:` with their emoji
+    ///
+    /// Unknown shortcodes are kept as they are.
+    pub emoji: bool,
+    /// Parse keyboard input written in double brackets, e.g. `[[Ctrl+C]]`,
+    /// as `Span::Kbd`
+    ///
+    /// Otherwise the brackets are kept as text. The keys can't contain
+    /// brackets or start or end with whitespace.
+    pub kbd: bool,
+    /// Parse paragraphs starting with `^^^ ` as `Block::LeadParagraph`, which
+    /// is rendered as `<p class="lead">`
+    ///
+    /// The marker only counts at the start of a paragraph, elsewhere it's text.
+    pub lead_paragraphs: bool,
+    /// Link issue references like `#42` using this URL template, in which
+    /// `{n}` is replaced with the issue number, e.g.
+    /// `https://github.com/org/repo/issues/{n}`
+    pub issue_url: Option<String>,
+    /// Link commit hashes of 7 to 40 lowercase hex digits like `a1b2c3d`
+    /// using this URL template, in which `{sha}` is replaced with the hash
+    ///
+    /// Only hashes with both digits and letters are linked, so neither
+    /// numbers nor words like `deadbeef` are taken for one.
+    pub commit_url: Option<String>,
+    /// Link bare URLs starting with `http://`, `https://` or `www.`
+    ///
+    /// Punctuation at the end of a URL, like the period ending a sentence or
+    /// the parenthesis closing a remark, is kept as text after the link.
+    pub autolinks: bool,
+    /// The text shown for bare URLs that are linked, see `AutolinkText`
+    pub autolink_text: AutolinkText,
+    /// Also strike through text between single tildes like `~gone~`, not
+    /// only between double ones like `~~gone~~`
+    ///
+    /// The runs of tildes around the text have to be equally long, so a
+    /// double tilde is never closed by a single one or the other way around,
+    /// e.g. `~~gone~` is left as text.
+    pub single_tilde_strikethrough: bool,
+    /// Parse setext headers, i.e. a line of text underlined with `===` or
+    /// `---`, enabled by default
+    ///
+    /// Without them a `---` line is always a horizontal rule and a `===`
+    /// line directly following a paragraph is text of the paragraph.
+    pub setext_headers: bool,
+    /// A hook for custom block syntax, see `BlockHook`
+    pub block_hook: Option<BlockHook>,
+    /// The number of columns between tab stops, 4 by default
+    ///
+    /// A tab in the indentation of a line advances it to the next tab stop,
+    /// which decides whether the line is indented code or how deeply
+    /// a list item is nested.
+    pub tab_width: usize,
+    /// How deeply lists may be nested, 32 by default
+    ///
+    /// A list nested deeper than this isn't parsed as a list, its items are
+    /// kept as text of the item they're nested in. This keeps over-indented
+    /// or malicious input from producing huge trees. With `0` no lists are
+    /// parsed at all.
+    pub max_list_depth: usize,
+    /// A hook for custom inline syntax, see `SpanHook`
+    pub span_hook: Option<SpanHook>,
+}
+
+/// A kind of Markdown construct, used to restrict the syntax a document may use
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub enum Feature {
+    /// ATX and setext headers
+    Headers,
+    /// Blockquotes
+    Blockquotes,
+    /// Fenced and indented code blocks
+    CodeBlocks,
+    /// Ordered and unordered lists
+    Lists,
+    /// List items starting with a checkbox
+    TaskLists,
+    /// Tables
+    Tables,
+    /// Horizontal rules
+    HorizontalRules,
+    /// Line blocks
+    LineBlocks,
+    /// Collapsible `<details>` blocks
+    Details,
+    /// Fenced divs like `::: warning`
+    Divs,
+    /// Footnote definitions, references and inline footnotes
+    Footnotes,
+    /// Raw HTML, inline HTML tags and HTML comments
+    Html,
+    /// Inline and reference-style links, as well as link references
+    Links,
+    /// Images
+    Images,
+    /// Inline code
+    InlineCode,
+    /// Emphasis and strong emphasis
+    Emphasis,
+    /// Highlighted text
+    Highlights,
+    /// Keyboard input
+    Kbd,
+    /// Struck through text
+    Strikethrough,
+}
+
+impl Default for ParseOptions {
+    fn default() -> ParseOptions {
+        ParseOptions {
+            mode: ParseMode::default(),
+            emoji: false,
+            kbd: false,
+            lead_paragraphs: false,
+            issue_url: None,
+            commit_url: None,
+            autolinks: false,
+            autolink_text: AutolinkText::default(),
+            single_tilde_strikethrough: false,
+            setext_headers: true,
+            block_hook: None,
+            tab_width: 4,
+            max_list_depth: 32,
+            span_hook: None,
+        }
+    }
+}
+
+/// A function that can turn a line into a custom block
+///
+/// The hook is called with every line that could start a new block,
+/// before any of the built-in block parsers are tried, including lines
+/// nested in blockquotes and list items. It sees the raw text of the line,
+/// with inline markup and indentation untouched. Returning `Some(block)`
+/// replaces the line with `block` and ends an open paragraph, returning
+/// `None` leaves the line to the built-in parsers.
+#[derive(Clone)]
+pub struct BlockHook(Arc<BlockHookFn>);
+
+type BlockHookFn = dyn Fn(&str) -> Option<Block> + Send + Sync;
+
+impl BlockHook {
+    /// Wraps a function as a block hook
+    pub fn new<F>(hook: F) -> BlockHook
+    where
+        F: Fn(&str) -> Option<Block> + Send + Sync + 'static,
+    {
+        BlockHook(Arc::new(hook))
+    }
+
+    fn call(&self, line: &str) -> Option<Block> {
+        (self.0)(line)
+    }
+}
+
+impl fmt::Debug for BlockHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("BlockHook")
+    }
+}
+
+// hooks are only equal if they are the same function
+impl PartialEq for BlockHook {
+    fn eq(&self, other: &BlockHook) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+/// A function that can turn the start of some text into a custom span
+///
+/// The hook is called at every position of the text of paragraphs, headers,
+/// table cells and other inline content, before any of the built-in span
+/// parsers are tried, e.g. to parse `@mentions` as links. It sees the rest
+/// of the text from that position on. Returning `Some((span, len))` replaces
+/// the first `len` bytes with `span`, returning `None` leaves the text to the
+/// built-in parsers. A length of `0`, or one that doesn't end at a character
+/// boundary of the text, is ignored.
+#[derive(Clone)]
+pub struct SpanHook(Arc<SpanHookFn>);
+
+type SpanHookFn = dyn Fn(&str) -> Option<(Span, usize)> + Send + Sync;
+
+impl SpanHook {
+    /// Wraps a function as a span hook
+    pub fn new<F>(hook: F) -> SpanHook
+    where
+        F: Fn(&str) -> Option<(Span, usize)> + Send + Sync + 'static,
+    {
+        SpanHook(Arc::new(hook))
+    }
+
+    fn call(&self, text: &str) -> Option<(Span, usize)> {
+        (self.0)(text).filter(|&(_, len)| len > 0 && text.is_char_boundary(len))
+    }
+}
+
+impl fmt::Debug for SpanHook {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str("SpanHook")
+    }
+}
+
+// hooks are only equal if they are the same function
+impl PartialEq for SpanHook {
+    fn eq(&self, other: &SpanHook) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl ParseOptions {
+    /// Starts building a set of options from the defaults
+    #[allow(clippy::new_ret_no_self)]
+    pub fn new() -> ParseOptionsBuilder {
+        ParseOptionsBuilder::default()
+    }
+}
+
+/// A builder for `ParseOptions`, created with `ParseOptions::new()`
+#[derive(Debug, Default, Clone)]
+pub struct ParseOptionsBuilder {
+    options: ParseOptions,
+}
+
+impl ParseOptionsBuilder {
+    /// Sets how ambiguous constructs are parsed
+    pub fn mode(mut self, mode: ParseMode) -> Self {
+        self.options.mode = mode;
+        self
+    }
+
+    /// Sets whether emoji shortcodes are replaced with their emoji
+    pub fn emoji(mut self, emoji: bool) -> Self {
+        self.options.emoji = emoji;
+        self
+    }
+
+    /// Sets whether keyboard input like `[[Ctrl+C]]` is parsed
+    pub fn kbd(mut self, kbd: bool) -> Self {
+        self.options.kbd = kbd;
+        self
+    }
+
+    /// Sets whether paragraphs starting with `^^^ ` are lead paragraphs
+    pub fn lead_paragraphs(mut self, lead_paragraphs: bool) -> Self {
+        self.options.lead_paragraphs = lead_paragraphs;
+        self
+    }
+
+    /// Sets the URL template issue references like `#42` are linked with
+    pub fn issue_url(mut self, template: &str) -> Self {
+        self.options.issue_url = Some(template.to_owned());
+        self
+    }
+
+    /// Sets the URL template commit hashes are linked with
+    pub fn commit_url(mut self, template: &str) -> Self {
+        self.options.commit_url = Some(template.to_owned());
+        self
+    }
+
+    /// Sets whether bare URLs are linked
+    pub fn autolinks(mut self, autolinks: bool) -> Self {
+        self.options.autolinks = autolinks;
+        self
+    }
+
+    /// Sets the text shown for bare URLs that are linked
+    pub fn autolink_text(mut self, autolink_text: AutolinkText) -> Self {
+        self.options.autolink_text = autolink_text;
+        self
+    }
+
+    /// Sets whether text between single tildes is struck through
+    pub fn single_tilde_strikethrough(mut self, single_tilde_strikethrough: bool) -> Self {
+        self.options.single_tilde_strikethrough = single_tilde_strikethrough;
+        self
+    }
+
+    /// Sets whether setext headers are parsed
+    pub fn setext_headers(mut self, setext_headers: bool) -> Self {
+        self.options.setext_headers = setext_headers;
+        self
+    }
+
+    /// Sets the number of columns between tab stops
+    pub fn tab_width(mut self, tab_width: usize) -> Self {
+        self.options.tab_width = tab_width;
+        self
+    }
+
+    /// Sets how deeply lists may be nested
+    pub fn max_list_depth(mut self, max_list_depth: usize) -> Self {
+        self.options.max_list_depth = max_list_depth;
+        self
+    }
+
+    /// Sets a hook for custom block syntax, see `BlockHook`
+    pub fn block_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&str) -> Option<Block> + Send + Sync + 'static,
+    {
+        self.options.block_hook = Some(BlockHook::new(hook));
+        self
+    }
+
+    /// Sets a hook for custom inline syntax, see `SpanHook`
+    pub fn span_hook<F>(mut self, hook: F) -> Self
+    where
+        F: Fn(&str) -> Option<(Span, usize)> + Send + Sync + 'static,
+    {
+        self.options.span_hook = Some(SpanHook::new(hook));
+        self
+    }
+
+    /// Returns the finished options
+    pub fn build(self) -> ParseOptions {
+        self.options
+    }
 }
 
 pub fn parse(md: &str) -> Vec<Block> {
-    block::parse_blocks(md)
+    parse_with_options(md, &ParseOptions::default())
+}
+
+pub fn parse_with_options(md: &str, options: &ParseOptions) -> Vec<Block> {
+    block::parse_blocks(md, options)
+}
+
+// parses inline markup only, e.g. the alt text of an image
+pub fn parse_inline(text: &str, options: &ParseOptions) -> Vec<Span> {
+    span::parse_spans(text, options)
+}
+
+// matches the emoji shortcodes are replaced with
+pub fn emoji_regex() -> &'static Regex {
+    span::emoji_regex()
+}
+
+// whether text can be written as an HTML comment, see `Span::Comment`
+pub fn is_comment_text(text: &str) -> bool {
+    span::is_comment_text(text)
+}
+
+// parses the top-level blocks along with the range of lines each of them spans
+pub fn parse_with_lines(md: &str, options: &ParseOptions) -> Vec<(Block, Range<usize>)> {
+    block::parse_blocks_with_lines(md, options)
 }
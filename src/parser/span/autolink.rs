@@ -0,0 +1,111 @@
+use alloc::borrow::ToOwned;
+use parser::Span;
+use parser::Span::{Link, Text};
+use parser::{AutolinkText, ParseOptions};
+use regex::Regex;
+
+// Turns a bare URL starting with `http://`, `https://` or `www.` into a link.
+// Like on GitHub, punctuation at the end of the URL and a closing parenthesis
+// without an opening one in the URL are left out of it, so they stay text
+// following the link. The caller makes sure the URL doesn't start in the
+// middle of a word.
+pub fn parse_autolink(text: &str, options: &ParseOptions) -> Option<(Span, usize)> {
+    lazy_static! {
+        static ref URL: Regex = Regex::new(r"^(?P<scheme>https?://|www\.)[^\s<]+").unwrap();
+    }
+
+    if !options.autolinks {
+        return None;
+    }
+    let caps = URL.captures(text)?;
+    let scheme = caps.name("scheme").unwrap().as_str();
+    let url = trim_trailing_punctuation(&caps[0]);
+    if url.len() == scheme.len() {
+        return None;
+    }
+
+    let href = if scheme == "www." {
+        format!("http://{}", url)
+    } else {
+        url.to_owned()
+    };
+    let shown = match options.autolink_text {
+        AutolinkText::Short if scheme != "www." => &url[scheme.len()..],
+        _ => url,
+    };
+    Some((Link(vec![Text(shown.to_owned())], href, None), url.len()))
+}
+
+fn trim_trailing_punctuation(mut url: &str) -> &str {
+    loop {
+        let trimmed =
+            url.trim_end_matches(['?', '!', '.', ',', ':', ';', '*', '_', '~', '\'', '"']);
+        let trimmed = match trimmed.strip_suffix(')') {
+            Some(rest) if trimmed.matches(')').count() > trimmed.matches('(').count() => rest,
+            _ => trimmed,
+        };
+        if trimmed.len() == url.len() {
+            return url;
+        }
+        url = trimmed;
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_autolink;
+    use parser::Span::{Link, Text};
+    use parser::{AutolinkText, ParseOptions};
+
+    fn link(text: &str, url: &str) -> ::parser::Span {
+        Link(vec![Text(text.to_owned())], url.to_owned(), None)
+    }
+
+    #[test]
+    fn finds_autolinks() {
+        let options = ParseOptions::new().autolinks(true).build();
+        assert_eq!(
+            parse_autolink("https://example.com/a?b=c d", &options),
+            Some((link("example.com/a?b=c", "https://example.com/a?b=c"), 25))
+        );
+        assert_eq!(
+            parse_autolink("www.example.com", &options),
+            Some((link("www.example.com", "http://www.example.com"), 15))
+        );
+        assert_eq!(parse_autolink("https:// a", &options), None);
+        assert_eq!(parse_autolink("ftp://example.com", &options), None);
+        assert_eq!(
+            parse_autolink("https://example.com", &ParseOptions::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn leaves_out_trailing_punctuation() {
+        let options = ParseOptions::new().autolinks(true).build();
+        assert_eq!(
+            parse_autolink("http://example.com/a.", &options),
+            Some((link("example.com/a", "http://example.com/a"), 20))
+        );
+        assert_eq!(
+            parse_autolink("http://example.com/a).", &options),
+            Some((link("example.com/a", "http://example.com/a"), 20))
+        );
+        assert_eq!(
+            parse_autolink("http://example.com/(a)!", &options),
+            Some((link("example.com/(a)", "http://example.com/(a)"), 22))
+        );
+    }
+
+    #[test]
+    fn keeps_original_text() {
+        let options = ParseOptions::new()
+            .autolinks(true)
+            .autolink_text(AutolinkText::Original)
+            .build();
+        assert_eq!(
+            parse_autolink("https://example.com/a.", &options),
+            Some((link("https://example.com/a", "https://example.com/a"), 21))
+        );
+    }
+}
@@ -2,13 +2,15 @@ use parser::Span;
 use parser::Span::Break;
 use regex::Regex;
 
+// Two or more spaces at the end of a line are a hard line break,
+// a single trailing space is trimmed along with the rest of the line
 pub fn parse_break(text: &str) -> Option<(Span, usize)> {
     lazy_static! {
-        static ref BR: Regex = Regex::new(r"^ {2}$").unwrap();
+        static ref BR: Regex = Regex::new(r"^ {2,}$").unwrap();
     }
 
     if BR.is_match(text) {
-        return Some((Break, 2));
+        return Some((Break, text.len()));
     }
     None
 }
@@ -21,6 +23,7 @@ mod test {
     #[test]
     fn finds_breaks() {
         assert_eq!(parse_break("  "), Some((Break, 2)));
+        assert_eq!(parse_break("   "), Some((Break, 3)));
     }
 
     #[test]
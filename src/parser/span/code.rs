@@ -1,3 +1,4 @@
+use alloc::borrow::ToOwned;
 use parser::Span;
 use parser::Span::Code;
 use regex::Regex;
@@ -0,0 +1,79 @@
+use alloc::borrow::ToOwned;
+use parser::Span;
+use parser::Span::Comment;
+use regex::Regex;
+
+pub fn parse_comment(text: &str) -> Option<(Span, usize)> {
+    lazy_static! {
+        static ref COMMENT: Regex = Regex::new(r"^<!--(?P<text>.*?)-->").unwrap();
+    }
+
+    if COMMENT.is_match(text) {
+        let caps = COMMENT.captures(text).unwrap();
+        let t = caps.name("text").unwrap().as_str();
+        if !is_comment_text(t) {
+            return None;
+        }
+        return Some((Comment(t.to_owned()), t.len() + 7));
+    }
+    None
+}
+
+// Whether text can be the content of an HTML comment, which must not start
+// with `>` or `->`, end with `-` or contain `--`. Browsers end a comment early
+// at `-->`, `--!>` and a leading `>`, so any other text could end it.
+pub fn is_comment_text(text: &str) -> bool {
+    !text.starts_with('>')
+        && !text.starts_with("->")
+        && !text.ends_with('-')
+        && !text.contains("--")
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_comment;
+    use parser::Span::Comment;
+
+    #[test]
+    fn finds_comment() {
+        assert_eq!(
+            parse_comment("<!-- a comment --> test"),
+            Some((Comment(" a comment ".to_owned()), 18))
+        );
+
+        assert_eq!(
+            parse_comment("<!----> test"),
+            Some((Comment("".to_owned()), 7))
+        );
+
+        assert_eq!(
+            parse_comment("<!-- a --> comment --> test"),
+            Some((Comment(" a ".to_owned()), 10))
+        );
+    }
+
+    #[test]
+    fn no_false_positives() {
+        assert_eq!(parse_comment("<!-- unterminated"), None);
+        assert_eq!(parse_comment("<! -- a comment -->"), None);
+    }
+
+    #[test]
+    fn rejects_malformed_comments() {
+        assert_eq!(
+            parse_comment("<!-- a --!><img src=x onerror=alert(1)> --> y"),
+            None
+        );
+        assert_eq!(parse_comment("<!-- a -- b -->"), None);
+        assert_eq!(parse_comment("<!-->"), None);
+        assert_eq!(parse_comment("<!--->"), None);
+        assert_eq!(parse_comment("<!--> a -->"), None);
+        assert_eq!(parse_comment("<!---> a -->"), None);
+        assert_eq!(parse_comment("<!-- a --->"), None);
+    }
+
+    #[test]
+    fn no_early_matching() {
+        assert_eq!(parse_comment("were <!-- a comment --> test"), None);
+    }
+}
@@ -0,0 +1,249 @@
+use parser::{ParseMode, ParseOptions};
+
+// An opening delimiter run that is still waiting for a closing one
+struct Opener {
+    // the number of delimiters that haven't been paired yet
+    remaining: usize,
+    // the length of the whole run, for the "rule of 3"
+    length: usize,
+    // whether the run could close emphasis as well
+    both: bool,
+}
+
+// Pairs the run of `delimiter`s at the start of `text` with the closing runs
+// following it, the way CommonMark does: closing runs are processed from left
+// to right, each one pairs with the nearest opening run before it and uses up
+// its delimiters from the inner end, two at a time if both runs have at least
+// two left and one at a time otherwise. This way `***a***` is an emphasis
+// containing strong emphasis and `****a**` is `**` followed by `**a**`.
+//
+// Returns the pair that uses the very first delimiter of `text` as a tuple of
+// (number of delimiters on either side, byte length including the closing ones),
+// i.e. 1 for an emphasis and 2 for a strong emphasis spanning the returned length.
+pub fn pair_delimiter_run(
+    text: &str,
+    delimiter: char,
+    options: &ParseOptions,
+) -> Option<(usize, usize)> {
+    let opening = text.chars().take_while(|c| *c == delimiter).count();
+    // all delimiters are ASCII, so the byte offset is the number of characters
+    let content = &text[opening..];
+
+    // the opening run has to be followed by something other than whitespace
+    if content.chars().next().is_none_or(char::is_whitespace) {
+        return None;
+    }
+
+    let intraword_allowed = delimiter == '*' || options.mode == ParseMode::Relaxed;
+    let mut stack = vec![Opener {
+        remaining: opening,
+        length: opening,
+        both: false,
+    }];
+    let mut prev = Some(delimiter);
+    let mut i = 0;
+    while i < content.len() {
+        let rest = &content[i..];
+        let next = rest.chars().next().unwrap();
+
+        if next == '\\' {
+            // skip escaped characters
+            let escaped = rest.chars().nth(1).map_or(0, char::len_utf8);
+            prev = rest[1..].chars().next();
+            i += 1 + escaped;
+            continue;
+        } else if next == '`' {
+            // code spans bind tighter than emphasis
+            let run = rest.chars().take_while(|c| *c == '`').count();
+            if let Some(end) = rest[run..].find(&"`".repeat(run)) {
+                i += run + end + run;
+                prev = Some('`');
+                continue;
+            }
+        } else if next == delimiter {
+            let run = rest.chars().take_while(|c| *c == delimiter).count();
+            let after = rest[run..].chars().next();
+            // a run next to punctuation only opens if it's preceded by whitespace
+            // or punctuation as well and only closes if it's followed by them,
+            // so that `*__*a*__*` isn't closed by the `*` in front of the `a`
+            let (left_flanking, right_flanking) = flanking(prev, after);
+            let before_punctuation = prev.is_some_and(is_punctuation);
+            let after_punctuation = after.is_some_and(is_punctuation);
+
+            // without intraword emphasis, a run inside of a word can't open or close
+            let can_close = i > 0
+                && right_flanking
+                && (intraword_allowed || !left_flanking || after_punctuation);
+            let can_open =
+                left_flanking && (intraword_allowed || !right_flanking || before_punctuation);
+
+            // a run can't be paired with another run if either of them can both
+            // open and close and their lengths add up to a multiple of three,
+            // so that `*a**b**c*` is an emphasis containing `**b**`
+            let both = can_open && can_close;
+            let mismatched = |opener: &Opener| {
+                (both || opener.both)
+                    && (opener.length + run).is_multiple_of(3)
+                    && !(opener.length.is_multiple_of(3) && run.is_multiple_of(3))
+            };
+
+            let mut remaining = run;
+            if can_close {
+                // the runs in between the closing run and the opening run it pairs
+                // with are left as literal text
+                while remaining > 0 {
+                    let k = match stack.iter().rposition(|opener| !mismatched(opener)) {
+                        Some(k) => k,
+                        None => break,
+                    };
+                    stack.truncate(k + 1);
+                    let used = if stack[k].remaining >= 2 && remaining >= 2 {
+                        2
+                    } else {
+                        1
+                    };
+                    remaining -= used;
+                    stack[k].remaining -= used;
+                    if stack[k].remaining == 0 {
+                        if k == 0 {
+                            return Some((used, opening + i + run - remaining));
+                        }
+                        stack.pop();
+                    }
+                }
+            }
+            if can_open && remaining > 0 {
+                stack.push(Opener {
+                    remaining,
+                    length: run,
+                    both,
+                });
+            }
+
+            prev = Some(delimiter);
+            i += run;
+            continue;
+        }
+
+        prev = Some(next);
+        i += next.len_utf8();
+    }
+    None
+}
+
+// Whether the run of delimiters at the start of `text` can open emphasis,
+// given the character before it, `None` being the start of the text. Like in
+// CommonMark the run has to be left-flanking, so the `*` in `a*"foo"*` can't
+// open an emphasis while the ones in `"*foo*"` and `**(foo)**` can.
+pub fn can_open(prev: Option<char>, text: &str, delimiter: char, options: &ParseOptions) -> bool {
+    let run = text.chars().take_while(|c| *c == delimiter).count();
+    let (left_flanking, right_flanking) = flanking(prev, text[run..].chars().next());
+    let intraword_allowed = delimiter == '*' || options.mode == ParseMode::Relaxed;
+    left_flanking && (intraword_allowed || !right_flanking || prev.is_some_and(is_punctuation))
+}
+
+// Returns whether a delimiter run between `prev` and `after` is left-flanking
+// and whether it is right-flanking. A run is left-flanking if it's not followed
+// by whitespace and, if it's followed by punctuation, preceded by whitespace or
+// punctuation, and right-flanking the other way around.
+pub fn flanking(prev: Option<char>, after: Option<char>) -> (bool, bool) {
+    let before_space = prev.is_none_or(char::is_whitespace);
+    let after_space = after.is_none_or(char::is_whitespace);
+    let before_punctuation = prev.is_some_and(is_punctuation);
+    let after_punctuation = after.is_some_and(is_punctuation);
+    (
+        !after_space && (!after_punctuation || before_space || before_punctuation),
+        !before_space && (!before_punctuation || after_space || after_punctuation),
+    )
+}
+
+fn is_punctuation(c: char) -> bool {
+    !c.is_alphanumeric() && !c.is_whitespace()
+}
+
+#[cfg(test)]
+mod test {
+    use super::{can_open, pair_delimiter_run};
+    use parser::{ParseMode, ParseOptions};
+
+    #[test]
+    fn opens_left_flanking_runs() {
+        let options = ParseOptions::default();
+        assert!(can_open(None, "**(foo)**", '*', &options));
+        assert!(can_open(Some('"'), "*quoted*\"", '*', &options));
+        assert!(can_open(Some(' '), "*foo*, bar", '*', &options));
+        assert!(can_open(Some('a'), "*b*", '*', &options));
+        assert!(!can_open(Some('a'), "*\"foo\"*", '*', &options));
+        assert!(!can_open(Some('a'), "** b**", '*', &options));
+        assert!(!can_open(None, "*", '*', &options));
+
+        let strict = ParseOptions::new().mode(ParseMode::Strict).build();
+        assert!(!can_open(Some('a'), "_b_", '_', &strict));
+        assert!(can_open(Some('('), "_b_", '_', &strict));
+    }
+
+    #[test]
+    fn pairs_delimiter_runs() {
+        let options = ParseOptions::default();
+        assert_eq!(pair_delimiter_run("*a* b", '*', &options), Some((1, 3)));
+        assert_eq!(pair_delimiter_run("**a** b", '*', &options), Some((2, 5)));
+        assert_eq!(
+            pair_delimiter_run("*a**b**c* d", '*', &options),
+            Some((1, 9))
+        );
+        assert_eq!(
+            pair_delimiter_run("*a *b* c* d", '*', &options),
+            Some((1, 9))
+        );
+        assert_eq!(
+            pair_delimiter_run("**a *b* c** d", '*', &options),
+            Some((2, 11))
+        );
+        assert_eq!(pair_delimiter_run("*a `*` b*", '*', &options), Some((1, 9)));
+        assert_eq!(pair_delimiter_run("*a \\* b*", '*', &options), Some((1, 8)));
+    }
+
+    #[test]
+    fn pairs_long_delimiter_runs() {
+        let options = ParseOptions::default();
+        assert_eq!(pair_delimiter_run("***a***", '*', &options), Some((1, 7)));
+        assert_eq!(pair_delimiter_run("****a****", '*', &options), Some((2, 9)));
+        assert_eq!(
+            pair_delimiter_run("_____a_____", '_', &options),
+            Some((1, 11))
+        );
+        assert_eq!(
+            pair_delimiter_run("******a******", '*', &options),
+            Some((2, 13))
+        );
+        assert_eq!(
+            pair_delimiter_run("***a** b***", '*', &options),
+            Some((1, 9))
+        );
+        assert_eq!(
+            pair_delimiter_run("******a*********b", '*', &options),
+            Some((2, 13))
+        );
+        assert_eq!(pair_delimiter_run("****a*", '*', &options), None);
+        assert_eq!(pair_delimiter_run("*****a**", '*', &options), None);
+    }
+
+    #[test]
+    fn no_closing_delimiter() {
+        let options = ParseOptions::default();
+        assert_eq!(pair_delimiter_run("* a*", '*', &options), None);
+        assert_eq!(pair_delimiter_run("*a *", '*', &options), None);
+        assert_eq!(pair_delimiter_run("*a**b", '*', &options), None);
+        assert_eq!(pair_delimiter_run("*", '*', &options), None);
+    }
+
+    #[test]
+    fn intraword_underscores() {
+        let strict = ParseOptions::new().mode(ParseMode::Strict).build();
+        assert_eq!(pair_delimiter_run("_a_b_ c", '_', &strict), Some((1, 5)));
+        assert_eq!(
+            pair_delimiter_run("_a_b_ c", '_', &ParseOptions::default()),
+            Some((1, 3))
+        );
+    }
+}
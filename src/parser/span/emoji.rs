@@ -0,0 +1,117 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use core::cmp::Reverse;
+use parser::ParseOptions;
+use parser::Span;
+use parser::Span::Text;
+use regex::{self, Regex};
+
+// GitHub shortcodes for commonly used emoji, sorted by name
+static EMOJI: &[(&str, &str)] = &[
+    ("+1", "👍"),
+    ("-1", "👎"),
+    ("100", "💯"),
+    ("angry", "😠"),
+    ("bug", "🐛"),
+    ("clap", "👏"),
+    ("confused", "😕"),
+    ("construction", "🚧"),
+    ("cry", "😢"),
+    ("eyes", "👀"),
+    ("fire", "🔥"),
+    ("grin", "😁"),
+    ("grinning", "😀"),
+    ("heart", "❤️"),
+    ("heavy_check_mark", "✔️"),
+    ("hourglass", "⌛"),
+    ("joy", "😂"),
+    ("laughing", "😆"),
+    ("memo", "📝"),
+    ("ok_hand", "👌"),
+    ("party", "🥳"),
+    ("pray", "🙏"),
+    ("question", "❓"),
+    ("rocket", "🚀"),
+    ("sad", "😞"),
+    ("smile", "😄"),
+    ("smiley", "😃"),
+    ("sparkles", "✨"),
+    ("star", "⭐"),
+    ("sunglasses", "😎"),
+    ("tada", "🎉"),
+    ("thinking", "🤔"),
+    ("thumbsdown", "👎"),
+    ("thumbsup", "👍"),
+    ("warning", "⚠️"),
+    ("wave", "👋"),
+    ("white_check_mark", "✅"),
+    ("wink", "😉"),
+    ("x", "❌"),
+    ("zap", "⚡"),
+];
+
+pub fn parse_emoji(text: &str, options: &ParseOptions) -> Option<(Span, usize)> {
+    lazy_static! {
+        static ref SHORTCODE: Regex = Regex::new(r"^:(?P<name>[a-z0-9_+\-]+):").unwrap();
+    }
+
+    if !options.emoji {
+        return None;
+    }
+
+    let caps = SHORTCODE.captures(text)?;
+    let index = EMOJI
+        .binary_search_by_key(&&caps["name"], |&(name, _)| name)
+        .ok()?;
+    Some((Text(EMOJI[index].1.to_owned()), caps[0].len()))
+}
+
+// Matches any of the emoji above, longer ones first, so that an emoji ending
+// in a variation selector isn't matched without it
+pub fn emoji_regex() -> &'static Regex {
+    lazy_static! {
+        static ref ANY_EMOJI: Regex = {
+            let mut emoji: Vec<&str> = EMOJI.iter().map(|&(_, emoji)| emoji).collect();
+            emoji.sort_by_key(|emoji| Reverse(emoji.len()));
+            let alternatives: Vec<String> =
+                emoji.iter().map(|emoji| regex::escape(emoji)).collect();
+            Regex::new(&alternatives.join("|")).unwrap()
+        };
+    }
+    &ANY_EMOJI
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_emoji, EMOJI};
+    use parser::ParseOptions;
+    use parser::Span::Text;
+
+    #[test]
+    fn finds_emoji() {
+        let options = ParseOptions::new().emoji(true).build();
+        assert_eq!(
+            parse_emoji(":smile: test", &options),
+            Some((Text("😄".to_owned()), 7))
+        );
+        assert_eq!(
+            parse_emoji(":+1:", &options),
+            Some((Text("👍".to_owned()), 4))
+        );
+    }
+
+    #[test]
+    fn no_false_positives() {
+        let options = ParseOptions::new().emoji(true).build();
+        assert_eq!(parse_emoji(":not_an_emoji:", &options), None);
+        assert_eq!(parse_emoji(":smile", &options), None);
+        assert_eq!(parse_emoji("test :smile:", &options), None);
+        assert_eq!(parse_emoji(":smile:", &ParseOptions::default()), None);
+    }
+
+    #[test]
+    fn table_is_sorted() {
+        assert!(EMOJI.windows(2).all(|w| w[0].0 < w[1].0));
+    }
+}
@@ -1,73 +1,91 @@
+use parser::span::delimiter::pair_delimiter_run;
 use parser::span::parse_spans;
+use parser::ParseOptions;
 use parser::Span;
 use parser::Span::Emphasis;
-use regex::Regex;
 
-pub fn parse_emphasis(text: &str) -> Option<(Span, usize)> {
-    lazy_static! {
-        static ref EMPHASIS_UNDERSCORE: Regex = Regex::new(r"^_(?P<text>.+?)_").unwrap();
-        static ref EMPHASIS_STAR: Regex = Regex::new(r"^\*(?P<text>.+?)\*").unwrap();
+pub fn parse_emphasis(text: &str, options: &ParseOptions) -> Option<(Span, usize)> {
+    let delimiter = match text.chars().next() {
+        Some(c @ '*') | Some(c @ '_') => c,
+        _ => return None,
+    };
+    match pair_delimiter_run(text, delimiter, options)? {
+        (1, len) => Some((Emphasis(parse_spans(&text[1..len - 1], options)), len)),
+        _ => None,
     }
-
-    if EMPHASIS_UNDERSCORE.is_match(text) {
-        let caps = EMPHASIS_UNDERSCORE.captures(text).unwrap();
-        let t = caps.name("text").unwrap().as_str();
-        return Some((Emphasis(parse_spans(t)), t.len() + 2));
-    } else if EMPHASIS_STAR.is_match(text) {
-        let caps = EMPHASIS_STAR.captures(text).unwrap();
-        let t = caps.name("text").unwrap().as_str();
-        return Some((Emphasis(parse_spans(t)), t.len() + 2));
-    }
-    None
 }
 
 #[cfg(test)]
 mod test {
     use super::parse_emphasis;
     use parser::Span::{Emphasis, Text};
+    use parser::{ParseMode, ParseOptions};
 
     #[test]
     fn finds_emphasis() {
         assert_eq!(
-            parse_emphasis("_testing things_ test"),
+            parse_emphasis("_testing things_ test", &ParseOptions::default()),
             Some((Emphasis(vec![Text("testing things".to_owned())]), 16))
         );
 
         assert_eq!(
-            parse_emphasis("*testing things* test"),
+            parse_emphasis("*testing things* test", &ParseOptions::default()),
             Some((Emphasis(vec![Text("testing things".to_owned())]), 16))
         );
 
         assert_eq!(
-            parse_emphasis("_testing things_ things_ test"),
+            parse_emphasis("_testing things_ things_ test", &ParseOptions::default()),
             Some((Emphasis(vec![Text("testing things".to_owned())]), 16))
         );
 
         assert_eq!(
-            parse_emphasis("_w_ things_ test"),
+            parse_emphasis("_w_ things_ test", &ParseOptions::default()),
             Some((Emphasis(vec![Text("w".to_owned())]), 3))
         );
 
         assert_eq!(
-            parse_emphasis("*w* things* test"),
+            parse_emphasis("*w* things* test", &ParseOptions::default()),
             Some((Emphasis(vec![Text("w".to_owned())]), 3))
         );
 
         assert_eq!(
-            parse_emphasis("_w__ testing things test"),
+            parse_emphasis("_w__ testing things test", &ParseOptions::default()),
             Some((Emphasis(vec![Text("w".to_owned())]), 3))
         );
     }
 
+    #[test]
+    fn intraword_underscores_in_strict_mode() {
+        let strict = ParseOptions::new().mode(ParseMode::Strict).build();
+        assert_eq!(parse_emphasis("_foo_bar", &strict), None);
+        assert_eq!(
+            parse_emphasis("_foo_bar_ baz", &strict),
+            Some((Emphasis(vec![Text("foo_bar".to_owned())]), 9))
+        );
+        assert_eq!(
+            parse_emphasis("_foo_bar", &ParseOptions::default()),
+            Some((Emphasis(vec![Text("foo".to_owned())]), 5))
+        );
+    }
+
     #[test]
     fn no_false_positives() {
-        assert_eq!(parse_emphasis("__ testing things test"), None);
-        assert_eq!(parse_emphasis("_ test"), None);
+        assert_eq!(
+            parse_emphasis("__ testing things test", &ParseOptions::default()),
+            None
+        );
+        assert_eq!(parse_emphasis("_ test", &ParseOptions::default()), None);
     }
 
     #[test]
     fn no_early_matching() {
-        assert_eq!(parse_emphasis("were _testing things_ test"), None);
-        assert_eq!(parse_emphasis("were *testing things* test"), None);
+        assert_eq!(
+            parse_emphasis("were _testing things_ test", &ParseOptions::default()),
+            None
+        );
+        assert_eq!(
+            parse_emphasis("were *testing things* test", &ParseOptions::default()),
+            None
+        );
     }
 }
@@ -0,0 +1,87 @@
+use alloc::borrow::ToOwned;
+use parser::span::image::find_closing_bracket;
+use parser::span::parse_spans;
+use parser::Span::{FootnoteReference, InlineFootnote};
+use parser::{ParseOptions, Span};
+use regex::Regex;
+
+pub fn parse_footnote_reference(text: &str) -> Option<(Span, usize)> {
+    lazy_static! {
+        static ref FOOTNOTE_REFERENCE: Regex = Regex::new(r"^\[\^(?P<id>[^\]\s]+)\]").unwrap();
+    }
+
+    let caps = FOOTNOTE_REFERENCE.captures(text)?;
+    Some((
+        FootnoteReference(caps.name("id").unwrap().as_str().to_owned()),
+        caps[0].len(),
+    ))
+}
+
+// An inline footnote like `^[a note]`, its content can't span multiple paragraphs
+pub fn parse_inline_footnote(text: &str, options: &ParseOptions) -> Option<(Span, usize)> {
+    let content = text.strip_prefix("^[")?;
+    let end = find_closing_bracket(content)?;
+    Some((
+        InlineFootnote(parse_spans(&content[..end], options)),
+        end + 3,
+    ))
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_footnote_reference, parse_inline_footnote};
+    use parser::ParseOptions;
+    use parser::Span::{Emphasis, FootnoteReference, InlineFootnote, RefLink, Text};
+
+    #[test]
+    fn finds_footnote_reference() {
+        assert_eq!(
+            parse_footnote_reference("[^1] test"),
+            Some((FootnoteReference("1".to_owned()), 4))
+        );
+        assert_eq!(
+            parse_footnote_reference("[^note]"),
+            Some((FootnoteReference("note".to_owned()), 7))
+        );
+    }
+
+    #[test]
+    fn no_false_positives() {
+        assert_eq!(parse_footnote_reference("[1]"), None);
+        assert_eq!(parse_footnote_reference("[^]"), None);
+        assert_eq!(parse_footnote_reference("[^a b]"), None);
+        assert_eq!(parse_footnote_reference("test [^1]"), None);
+    }
+
+    #[test]
+    fn finds_inline_footnote() {
+        assert_eq!(
+            parse_inline_footnote("^[a *note*] test", &ParseOptions::default()),
+            Some((
+                InlineFootnote(vec![
+                    Text("a ".to_owned()),
+                    Emphasis(vec![Text("note".to_owned())])
+                ]),
+                11
+            ))
+        );
+        assert_eq!(
+            parse_inline_footnote("^[see [1]] test", &ParseOptions::default()),
+            Some((
+                InlineFootnote(vec![
+                    Text("see ".to_owned()),
+                    RefLink(vec![Text("1".to_owned())], "1".to_owned(), "[1]".to_owned())
+                ]),
+                10
+            ))
+        );
+    }
+
+    #[test]
+    fn no_unclosed_inline_footnote() {
+        let options = ParseOptions::default();
+        assert_eq!(parse_inline_footnote("^[a note", &options), None);
+        assert_eq!(parse_inline_footnote("^ [a note]", &options), None);
+        assert_eq!(parse_inline_footnote("test ^[a note]", &options), None);
+    }
+}
@@ -0,0 +1,57 @@
+use parser::span::delimiter::pair_delimiter_run;
+use parser::span::parse_spans;
+use parser::ParseOptions;
+use parser::Span;
+use parser::Span::Highlight;
+
+pub fn parse_highlight(text: &str, options: &ParseOptions) -> Option<(Span, usize)> {
+    if !text.starts_with("==") {
+        return None;
+    }
+    match pair_delimiter_run(text, '=', options)? {
+        (2, len) => Some((Highlight(parse_spans(&text[2..len - 2], options)), len)),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_highlight;
+    use parser::ParseOptions;
+    use parser::Span::{Highlight, Strong, Text};
+
+    #[test]
+    fn finds_highlight() {
+        assert_eq!(
+            parse_highlight("==hi== there", &ParseOptions::default()),
+            Some((Highlight(vec![Text("hi".to_owned())]), 6))
+        );
+
+        assert_eq!(
+            parse_highlight("==**bold**== there", &ParseOptions::default()),
+            Some((Highlight(vec![Strong(vec![Text("bold".to_owned())])]), 12))
+        );
+
+        assert_eq!(
+            parse_highlight("==a = b== c", &ParseOptions::default()),
+            Some((Highlight(vec![Text("a = b".to_owned())]), 9))
+        );
+    }
+
+    #[test]
+    fn no_false_positives() {
+        let options = ParseOptions::default();
+        assert_eq!(parse_highlight("==unterminated", &options), None);
+        assert_eq!(parse_highlight("=single=", &options), None);
+        assert_eq!(parse_highlight("== spaced==", &options), None);
+        assert_eq!(parse_highlight("===", &options), None);
+    }
+
+    #[test]
+    fn no_early_matching() {
+        assert_eq!(
+            parse_highlight("were ==testing things== test", &ParseOptions::default()),
+            None
+        );
+    }
+}
@@ -0,0 +1,56 @@
+use alloc::borrow::ToOwned;
+use parser::Span;
+use parser::Span::Html;
+use regex::Regex;
+
+// An opening tag with optional attributes, like `<span class="x">` or `<br/>`,
+// or a closing tag like `</span>`
+pub fn parse_html_tag(text: &str) -> Option<(Span, usize)> {
+    lazy_static! {
+        static ref HTML_TAG: Regex = Regex::new(concat!(
+            r#"^(?:<[a-zA-Z][a-zA-Z0-9-]*"#,
+            r#"(?:\s+[a-zA-Z_:][a-zA-Z0-9_.:-]*(?:\s*=\s*(?:[^\s"'=<>`]+|'[^']*'|"[^"]*"))?)*"#,
+            r#"\s*/?>|</[a-zA-Z][a-zA-Z0-9-]*\s*>)"#
+        ))
+        .unwrap();
+    }
+
+    let tag = HTML_TAG.find(text)?.as_str();
+    Some((Html(tag.to_owned()), tag.len()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_html_tag;
+    use parser::Span::Html;
+
+    #[test]
+    fn finds_html_tags() {
+        assert_eq!(
+            parse_html_tag("<span> test"),
+            Some((Html("<span>".to_owned()), 6))
+        );
+        assert_eq!(
+            parse_html_tag("</span> test"),
+            Some((Html("</span>".to_owned()), 7))
+        );
+        assert_eq!(
+            parse_html_tag("<a href=\"x y\" title='z' hidden>"),
+            Some((Html("<a href=\"x y\" title='z' hidden>".to_owned()), 31))
+        );
+        assert_eq!(parse_html_tag("<br/>"), Some((Html("<br/>".to_owned()), 5)));
+    }
+
+    #[test]
+    fn no_false_positives() {
+        assert_eq!(parse_html_tag("< span>"), None);
+        assert_eq!(parse_html_tag("<http://example.com>"), None);
+        assert_eq!(parse_html_tag("<a href=\"x>"), None);
+        assert_eq!(parse_html_tag("a < b > c"), None);
+    }
+
+    #[test]
+    fn no_early_matching() {
+        assert_eq!(parse_html_tag("test <span>"), None);
+    }
+}
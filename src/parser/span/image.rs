@@ -1,61 +1,257 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
 use parser::Span;
 use parser::Span::Image;
+use parser::{Dimension, ObjectSize};
 use regex::Regex;
 
+// Parses `![alt](destination "title" =WxH)`, where the title and the size are
+// optional and can be given in either order. The size can also be given as
+// attributes like `{width=100 height=50%}` right after the image.
 pub fn parse_image(text: &str) -> Option<(Span, usize)> {
-    lazy_static! {
-        static ref IMAGE: Regex =
-            Regex::new("^!\\[(?P<text>.*?)\\]\\((?P<url>.*?)(?:\\s\"(?P<title>.*?)\")?\\)")
-                .unwrap();
+    if !text.starts_with("![") {
+        return None;
     }
+    let alt_end = 2 + find_closing_bracket(&text[2..])?;
+    let (url, title, size, len) = parse_resource(&text[alt_end + 1..])?;
 
-    if IMAGE.is_match(text) {
-        let caps = IMAGE.captures(text).unwrap();
-        let text = if let Some(mat) = caps.name("text") {
-            mat.as_str().to_owned()
-        } else {
-            "".to_owned()
-        };
-        let url = if let Some(mat) = caps.name("url") {
-            mat.as_str().to_owned()
-        } else {
-            "".to_owned()
-        };
-        let title = if let Some(mat) = caps.name("title") {
-            Some(mat.as_str().to_owned())
+    Some((
+        Image(text[2..alt_end].to_owned(), url, title, size),
+        alt_end + 1 + len,
+    ))
+}
+
+// Parses the `(destination "title" =WxH)` following the text of an image or
+// a link, along with a size given as attributes like `{width=100}` after it
+pub fn parse_resource(text: &str) -> Option<(String, Option<String>, Option<ObjectSize>, usize)> {
+    if !text.starts_with('(') {
+        return None;
+    }
+
+    let mut i = skip_whitespace(text, 1);
+    let (url, len) = parse_destination(&text[i..])?;
+    i += len;
+
+    let mut title = None;
+    let mut size = None;
+    loop {
+        let start = skip_whitespace(text, i);
+        let rest = &text[start..];
+        if rest.starts_with(')') {
+            i = start + 1;
+            break;
+        }
+        // the attributes have to be separated by whitespace
+        if start == i {
+            return None;
+        }
+        if let (None, Some((t, len))) = (&title, parse_title(rest)) {
+            title = Some(t);
+            i = start + len;
+        } else if let (None, Some((s, len))) = (&size, parse_size(rest)) {
+            size = Some(s);
+            i = start + len;
         } else {
-            None
-        };
-        // TODO correctly get whitespace length between url and title
-        let len = text.len() + url.len() + 5 + title.clone().map_or(0, |t| t.len() + 3);
-        return Some((Image(text, url, title), len));
+            return None;
+        }
+    }
+
+    if size.is_none() {
+        if let Some((s, len)) = parse_attributes(&text[i..]) {
+            size = Some(s);
+            i += len;
+        }
+    }
+
+    Some((url, title, size, i))
+}
+
+fn skip_whitespace(text: &str, i: usize) -> usize {
+    i + text[i..].len() - text[i..].trim_start().len()
+}
+
+// returns the position of the `]` closing an already opened `[`
+pub fn find_closing_bracket(text: &str) -> Option<usize> {
+    let mut depth = 0;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '[' => depth += 1,
+            ']' if depth == 0 => return Some(i),
+            ']' => depth -= 1,
+            _ => {}
+        }
+    }
+    None
+}
+
+// Parses a destination that is either wrapped in `<>`, in which case it may
+// contain spaces, or that ends at the first whitespace or unbalanced `)`.
+fn parse_destination(text: &str) -> Option<(String, usize)> {
+    if let Some(rest) = text.strip_prefix('<') {
+        let end = rest.find(['>', '<', '\n'])?;
+        if !rest[end..].starts_with('>') {
+            return None;
+        }
+        return Some((rest[..end].to_owned(), end + 2));
+    }
+
+    let mut depth = 0;
+    let mut escaped = false;
+    for (i, c) in text.char_indices() {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            '(' => depth += 1,
+            ')' if depth == 0 => return Some((text[..i].to_owned(), i)),
+            ')' => depth -= 1,
+            _ if c.is_whitespace() => {
+                return Some((text[..i].to_owned(), i));
+            }
+            _ => {}
+        }
     }
     None
 }
 
+// parses a title wrapped in `"`, `'` or `()`
+fn parse_title(text: &str) -> Option<(String, usize)> {
+    let close = match text.chars().next()? {
+        '"' => '"',
+        '\'' => '\'',
+        '(' => ')',
+        _ => return None,
+    };
+
+    let mut escaped = false;
+    for (i, c) in text.char_indices().skip(1) {
+        match c {
+            _ if escaped => escaped = false,
+            '\\' => escaped = true,
+            _ if c == close => return Some((text[1..i].to_owned(), i + 1)),
+            _ => {}
+        }
+    }
+    None
+}
+
+// Parses a size like `=100x200`, where either dimension can be left out and
+// dimensions can be given in pixels or percent. Spaces are allowed around the
+// dimensions, as in `= 100 x 50% `.
+fn parse_size(text: &str) -> Option<(ObjectSize, usize)> {
+    lazy_static! {
+        static ref SIZE: Regex = Regex::new(
+            r"^=[ \t]*(?P<width>[0-9]+(?:px|%)?)?[ \t]*x(?:[ \t]*(?P<height>[0-9]+(?:px|%)?))?"
+        )
+        .unwrap();
+    }
+
+    let caps = SIZE.captures(text)?;
+    let len = caps.get(0).unwrap().end();
+    if !text[len..].starts_with(|c: char| c.is_whitespace() || c == ')') {
+        return None;
+    }
+    let dimension = |name: &str| match caps.name(name) {
+        Some(d) => parse_dimension(d.as_str()).map(Some),
+        None => Some(None),
+    };
+    let size = ObjectSize {
+        width: dimension("width")?,
+        height: dimension("height")?,
+    };
+    if size.width.is_none() && size.height.is_none() {
+        return None;
+    }
+
+    Some((size, len))
+}
+
+// Parses a size given as attributes like `{width=100 height=50%}`, values may
+// be quoted. Other attributes aren't supported, so they leave the `{...}` as text.
+fn parse_attributes(text: &str) -> Option<(ObjectSize, usize)> {
+    if !text.starts_with('{') {
+        return None;
+    }
+    let end = text.find(['}', '\n'])?;
+    if !text[end..].starts_with('}') {
+        return None;
+    }
+
+    let mut size = ObjectSize {
+        width: None,
+        height: None,
+    };
+    for attribute in text[1..end].split_whitespace() {
+        let (key, value) = attribute.split_once('=')?;
+        let value = value
+            .strip_prefix('"')
+            .and_then(|value| value.strip_suffix('"'))
+            .unwrap_or(value);
+        match key {
+            "width" => size.width = Some(parse_dimension(value)?),
+            "height" => size.height = Some(parse_dimension(value)?),
+            _ => return None,
+        }
+    }
+    if size.width.is_none() && size.height.is_none() {
+        return None;
+    }
+    Some((size, end + 1))
+}
+
+// parses a number of pixels like `100` or `100px`, or a percentage like `50%`
+fn parse_dimension(text: &str) -> Option<Dimension> {
+    let (digits, percent) = match text.strip_suffix('%') {
+        Some(digits) => (digits, true),
+        None => (text.strip_suffix("px").unwrap_or(text), false),
+    };
+    if digits.is_empty() || !digits.chars().all(|c| c.is_ascii_digit()) {
+        return None;
+    }
+    let value = digits.parse().ok()?;
+    Some(if percent {
+        Dimension::Percent(value)
+    } else {
+        Dimension::Pixels(value)
+    })
+}
+
 #[test]
 fn finds_image() {
     assert_eq!(
         parse_image("![an example](example.com) test"),
         Some((
-            Image("an example".to_owned(), "example.com".to_owned(), None),
+            Image(
+                "an example".to_owned(),
+                "example.com".to_owned(),
+                None,
+                None
+            ),
             26
         ))
     );
 
     assert_eq!(
         parse_image("![](example.com) test"),
-        Some((Image("".to_owned(), "example.com".to_owned(), None), 16))
+        Some((
+            Image("".to_owned(), "example.com".to_owned(), None, None),
+            16
+        ))
     );
 
     assert_eq!(
         parse_image("![an example]() test"),
-        Some((Image("an example".to_owned(), "".to_owned(), None), 15))
+        Some((
+            Image("an example".to_owned(), "".to_owned(), None, None),
+            15
+        ))
     );
 
     assert_eq!(
         parse_image("![]() test"),
-        Some((Image("".to_owned(), "".to_owned(), None), 5))
+        Some((Image("".to_owned(), "".to_owned(), None, None), 5))
     );
 
     assert_eq!(
@@ -64,7 +260,8 @@ fn finds_image() {
             Image(
                 "an example".to_owned(),
                 "example.com".to_owned(),
-                Some("Title".to_owned())
+                Some("Title".to_owned()),
+                None
             ),
             34
         ))
@@ -73,19 +270,282 @@ fn finds_image() {
     assert_eq!(
         parse_image("![an example](example.com) test [a link](example.com)"),
         Some((
-            Image("an example".to_owned(), "example.com".to_owned(), None),
+            Image(
+                "an example".to_owned(),
+                "example.com".to_owned(),
+                None,
+                None
+            ),
             26
         ))
     );
 }
 
+#[test]
+fn finds_image_size() {
+    let size = Some(ObjectSize {
+        width: Some(Dimension::Pixels(111)),
+        height: Some(Dimension::Pixels(222)),
+    });
+
+    assert_eq!(
+        parse_image("![a](example.com =111x222) test"),
+        Some((
+            Image("a".to_owned(), "example.com".to_owned(), None, size),
+            26
+        ))
+    );
+
+    assert_eq!(
+        parse_image("![a](example.com =111x) test"),
+        Some((
+            Image(
+                "a".to_owned(),
+                "example.com".to_owned(),
+                None,
+                Some(ObjectSize {
+                    width: Some(Dimension::Pixels(111)),
+                    height: None
+                })
+            ),
+            23
+        ))
+    );
+
+    assert_eq!(
+        parse_image("![a](example.com =x222) test"),
+        Some((
+            Image(
+                "a".to_owned(),
+                "example.com".to_owned(),
+                None,
+                Some(ObjectSize {
+                    width: None,
+                    height: Some(Dimension::Pixels(222))
+                })
+            ),
+            23
+        ))
+    );
+}
+
+#[test]
+fn finds_image_size_in_attributes() {
+    assert_eq!(
+        parse_image("![a](x.png){width=100 height=50} test"),
+        Some((
+            Image(
+                "a".to_owned(),
+                "x.png".to_owned(),
+                None,
+                Some(ObjectSize {
+                    width: Some(Dimension::Pixels(100)),
+                    height: Some(Dimension::Pixels(50))
+                })
+            ),
+            32
+        ))
+    );
+
+    assert_eq!(
+        parse_image("![a](x.png \"Title\"){width=\"50%\"}"),
+        Some((
+            Image(
+                "a".to_owned(),
+                "x.png".to_owned(),
+                Some("Title".to_owned()),
+                Some(ObjectSize {
+                    width: Some(Dimension::Percent(50)),
+                    height: None
+                })
+            ),
+            32
+        ))
+    );
+
+    // unsupported attributes and a size given twice are left as text
+    assert_eq!(
+        parse_image("![a](x.png){.wide}"),
+        Some((Image("a".to_owned(), "x.png".to_owned(), None, None), 11))
+    );
+    assert_eq!(
+        parse_image("![a](x.png =10x){width=20}"),
+        Some((
+            Image(
+                "a".to_owned(),
+                "x.png".to_owned(),
+                None,
+                Some(ObjectSize {
+                    width: Some(Dimension::Pixels(10)),
+                    height: None
+                })
+            ),
+            16
+        ))
+    );
+}
+
+#[test]
+fn finds_image_size_and_title_in_either_order() {
+    let image = Image(
+        "a".to_owned(),
+        "example.com".to_owned(),
+        Some("Title".to_owned()),
+        Some(ObjectSize {
+            width: Some(Dimension::Pixels(111)),
+            height: Some(Dimension::Pixels(222)),
+        }),
+    );
+
+    assert_eq!(
+        parse_image("![a](example.com =111x222 \"Title\") test"),
+        Some((image.clone(), 34))
+    );
+
+    assert_eq!(
+        parse_image("![a](example.com \"Title\" =111x222) test"),
+        Some((image, 34))
+    );
+}
+
+#[test]
+fn finds_image_titles() {
+    let image = |title: &str| {
+        Image(
+            "a".to_owned(),
+            "example.com".to_owned(),
+            Some(title.to_owned()),
+            None,
+        )
+    };
+
+    assert_eq!(
+        parse_image("![a](example.com 'Title') test"),
+        Some((image("Title"), 25))
+    );
+    assert_eq!(
+        parse_image("![a](example.com (Title)) test"),
+        Some((image("Title"), 25))
+    );
+    assert_eq!(
+        parse_image("![a](example.com \"a (b) c)\") test"),
+        Some((image("a (b) c)"), 28))
+    );
+    assert_eq!(
+        parse_image("![a](example.com \"say \\\"hi\\\"\") test"),
+        Some((image("say \\\"hi\\\""), 30))
+    );
+}
+
+#[test]
+fn finds_image_destinations() {
+    let image = |url: &str| Image("a".to_owned(), url.to_owned(), None, None);
+
+    assert_eq!(
+        parse_image("![a](<my image.png>) test"),
+        Some((image("my image.png"), 20))
+    );
+    assert_eq!(
+        parse_image("![a](<path with space.png>)"),
+        Some((image("path with space.png"), 27))
+    );
+    assert_eq!(
+        parse_image("![a](image_(1).png) test"),
+        Some((image("image_(1).png"), 19))
+    );
+    assert_eq!(
+        parse_image("![a](  example.com  ) test"),
+        Some((image("example.com"), 21))
+    );
+}
+
+#[test]
+fn finds_wrapped_image() {
+    assert_eq!(
+        parse_image("![a](\n  example.com\n  \"Title\"\n) test"),
+        Some((
+            Image(
+                "a".to_owned(),
+                "example.com".to_owned(),
+                Some("Title".to_owned()),
+                None
+            ),
+            31
+        ))
+    );
+}
+
+#[test]
+fn finds_nested_brackets_in_alt_text() {
+    assert_eq!(
+        parse_image("![a [b] c](example.com)"),
+        Some((
+            Image("a [b] c".to_owned(), "example.com".to_owned(), None, None),
+            23
+        ))
+    );
+}
+
 #[test]
 fn no_false_positives() {
     assert_eq!(parse_image("![()] testing things test"), None);
     assert_eq!(parse_image("!()[] testing things test"), None);
+    assert_eq!(parse_image("![a](example.com \"Title)"), None);
+    assert_eq!(parse_image("![a](example.com \"Title\" \"Title\")"), None);
+    assert_eq!(parse_image("![a](example.com =axb)"), None);
+    assert_eq!(parse_image("![a](my image.png)"), None);
+    assert_eq!(parse_image("![a](example.com"), None);
 }
 
 #[test]
 fn no_early_matching() {
     assert_eq!(parse_image("were ![an example](example.com) test"), None);
 }
+
+#[test]
+fn finds_image_size_with_spaces() {
+    let size = |width, height| Some(ObjectSize { width, height });
+
+    assert_eq!(
+        parse_image("![a](example.com = 111 x 222 ) test"),
+        Some((
+            Image(
+                "a".to_owned(),
+                "example.com".to_owned(),
+                None,
+                size(Some(Dimension::Pixels(111)), Some(Dimension::Pixels(222)))
+            ),
+            30
+        ))
+    );
+
+    assert_eq!(
+        parse_image("![a](example.com =50% x) test"),
+        Some((
+            Image(
+                "a".to_owned(),
+                "example.com".to_owned(),
+                None,
+                size(Some(Dimension::Percent(50)), None)
+            ),
+            24
+        ))
+    );
+
+    assert_eq!(
+        parse_image("![a](example.com =x 20px \"Title\") test"),
+        Some((
+            Image(
+                "a".to_owned(),
+                "example.com".to_owned(),
+                Some("Title".to_owned()),
+                size(None, Some(Dimension::Pixels(20)))
+            ),
+            33
+        ))
+    );
+
+    assert_eq!(parse_image("![a](example.com = x ) test"), None);
+    assert_eq!(parse_image("![a](example.com =1 x 2y) test"), None);
+    assert_eq!(parse_image("![a](example.com =1 2) test"), None);
+}
@@ -0,0 +1,57 @@
+use alloc::borrow::ToOwned;
+use parser::ParseOptions;
+use parser::Span;
+use parser::Span::Kbd;
+use regex::Regex;
+
+pub fn parse_kbd(text: &str, options: &ParseOptions) -> Option<(Span, usize)> {
+    lazy_static! {
+        static ref KBD: Regex =
+            Regex::new(r"^\[\[(?P<keys>[^\[\]\s](?:[^\[\]\n]*[^\[\]\s])?)\]\]").unwrap();
+    }
+
+    if !options.kbd {
+        return None;
+    }
+
+    let caps = KBD.captures(text)?;
+    Some((Kbd(caps["keys"].to_owned()), caps[0].len()))
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_kbd;
+    use parser::ParseOptions;
+    use parser::Span::Kbd;
+
+    #[test]
+    fn finds_kbd() {
+        let options = ParseOptions::new().kbd(true).build();
+        assert_eq!(
+            parse_kbd("[[Ctrl+C]] to copy", &options),
+            Some((Kbd("Ctrl+C".to_owned()), 10))
+        );
+        assert_eq!(
+            parse_kbd("[[Ctrl + Alt + Del]]", &options),
+            Some((Kbd("Ctrl + Alt + Del".to_owned()), 20))
+        );
+        assert_eq!(parse_kbd("[[A]]", &options), Some((Kbd("A".to_owned()), 5)));
+    }
+
+    #[test]
+    fn no_false_positives() {
+        let options = ParseOptions::new().kbd(true).build();
+        assert_eq!(parse_kbd("[[Ctrl+C", &options), None);
+        assert_eq!(parse_kbd("[[Ctrl+C]", &options), None);
+        assert_eq!(parse_kbd("[[]]", &options), None);
+        assert_eq!(parse_kbd("[[ Ctrl]]", &options), None);
+        assert_eq!(parse_kbd("[[a [b]]]", &options), None);
+        assert_eq!(parse_kbd("[[Ctrl+C]]", &ParseOptions::default()), None);
+    }
+
+    #[test]
+    fn no_early_matching() {
+        let options = ParseOptions::new().kbd(true).build();
+        assert_eq!(parse_kbd("press [[Ctrl+C]]", &options), None);
+    }
+}
@@ -1,12 +1,18 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use parser::span::image::parse_resource;
 use parser::span::parse_spans;
+use parser::ParseOptions;
 use parser::Span;
-use parser::Span::{Link, RefLink};
+use parser::Span::{Link, Object, RefLink};
 use regex::Regex;
 
-pub fn parse_link(text: &str) -> Option<(Span, usize)> {
+pub fn parse_link(text: &str, options: &ParseOptions) -> Option<(Span, usize)> {
     lazy_static! {
         // This is the second part of the regex, that matches the reference or url and title.
-        static ref LINK_ATTR_STR: &'static str = "(?:\\s*\\[(?P<ref>.*)\\]|\\((?P<url>.*?)(?:\\s*\"(?P<title>.*?)\")?\\s*\\))?";
+        // A url in angle brackets may contain spaces, a bare one may not.
+        static ref LINK_ATTR_STR: &'static str = "(?:\\s*\\[(?P<ref>[^\\[\\]]*)\\]|\\(\\s*(?:<(?P<bracketed_url>[^<>\\n]*)>|(?P<url>(?:[^\\s<]\\S*?)?))(?:\\s*\"(?P<title>.*?)\")?\\s*\\))?";
         // This regex does not sufficiently cover the edge case where there are brackets (e.g. for
         // images) inside a link text. It's sufficient for identifying links anyway, we'll properly
         // figure out the braces below.
@@ -37,6 +43,18 @@ pub fn parse_link(text: &str) -> Option<(Span, usize)> {
                     content.push(x);
                 }
                 continue;
+            } else if next == '`' {
+                // Code spans bind tighter than link brackets, so skip braces inside of them,
+                // e.g. `[`a]`](link)` is a link containing the code `a]`.
+                let rest = chars.as_str();
+                let run = 1 + rest.chars().take_while(|c| *c == '`').count();
+                if let Some(end) = rest[run - 1..].find(&"`".repeat(run)) {
+                    let code_len = run - 1 + end + run;
+                    content.push(next);
+                    content.push_str(&rest[..code_len]);
+                    chars = rest[code_len..].chars();
+                    continue;
+                }
             } else if next == ']' {
                 brace_level -= 1;
             } else if next == '[' {
@@ -55,45 +73,81 @@ pub fn parse_link(text: &str) -> Option<(Span, usize)> {
             return None;
         }
 
+        // a link with a size is an embedded object, the title and the size
+        // are parsed like those of an image
+        if let Some((url, title, Some(size), len)) = parse_resource(chars.as_str()) {
+            let len = 1 + content.len() + 1 + len;
+            return Some((
+                Object(parse_content(&content, options), url, title, size),
+                len,
+            ));
+        }
+
         let caps = LINK_ATTR.captures(chars.as_str()).unwrap();
 
         // Check whether we have an inline link (in which case the "url" field is captured),
         // whether there's an explicit reference provided or if we should implicitly use the link
         // content as reference.
-        if let Some(url) = caps.name("url") {
-            let url = url.as_str().trim().to_owned();
+        if let Some(url) = caps.name("bracketed_url").or_else(|| caps.name("url")) {
+            let url = url.as_str().to_owned();
             let title = caps.name("title").map(|t| t.as_str().to_owned());
             let len = 1 + content.len() + 1 + caps[0].len();
 
-            return Some((Link(parse_spans(&content), url, title), len));
+            return Some((Link(parse_content(&content, options), url, title), len));
         } else if let Some(reference) = caps.name("ref") {
-            let reference = reference.as_str().trim().to_lowercase();
+            // a collapsed reference like `[text][]` uses the link content as reference
+            let reference = match reference.as_str().trim() {
+                "" => content.trim().to_lowercase(),
+                reference => reference.to_lowercase(),
+            };
             let len = 1 + content.len() + 1 + caps[0].len();
             let raw = ["[", &content, "]", &caps[0]].join("");
 
-            return Some((RefLink(parse_spans(&content), reference, raw), len));
+            return Some((
+                RefLink(parse_content(&content, options), reference, raw),
+                len,
+            ));
         } else {
-            // Leave the reference empty, the HTML generating code will try to match both reference
-            // and slugified content.
-            let reference = "".to_owned();
+            // A shortcut reference like `[text]` uses the link content as reference.
+            // If that's not found the HTML generating code will also try the slugified content.
+            let reference = content.trim().to_lowercase();
             let len = 1 + content.len() + 1;
             let raw = ["[", &content, "]"].join("");
 
-            return Some((RefLink(parse_spans(&content), reference, raw), len));
+            return Some((
+                RefLink(parse_content(&content, options), reference, raw),
+                len,
+            ));
         }
     }
     None
 }
 
+// bare URLs, issue and commit references within the text of a link aren't
+// links themselves
+fn parse_content(content: &str, options: &ParseOptions) -> Vec<Span> {
+    if !options.autolinks && options.issue_url.is_none() && options.commit_url.is_none() {
+        return parse_spans(content, options);
+    }
+    let options = ParseOptions {
+        autolinks: false,
+        issue_url: None,
+        commit_url: None,
+        ..options.clone()
+    };
+    parse_spans(content, &options)
+}
+
 #[cfg(test)]
 mod test {
     use parser::span::parse_link;
-    use parser::Span::{Image, Link, Literal, RefLink, Text};
+    use parser::Span::{Code, Emphasis, Image, Link, Literal, Object, RefLink, Strong, Text};
+    use parser::{Dimension, ObjectSize, ParseOptions};
 
     #[test]
     fn finds_link() {
         assert_eq!(
-            parse_link("[an example](example.com) test"),
+            parse_link("[an example](example.com) test", &ParseOptions::default()),
             Some((
                 Link(
                     vec![Text("an example".to_owned())],
@@ -105,7 +159,7 @@ mod test {
         );
 
         assert_eq!(
-            parse_link("[an example][example]"),
+            parse_link("[an example][example]", &ParseOptions::default()),
             Some((
                 RefLink(
                     vec![Text("an example".to_owned())],
@@ -117,12 +171,12 @@ mod test {
         );
 
         assert_eq!(
-            parse_link("[](example.com) test"),
+            parse_link("[](example.com) test", &ParseOptions::default()),
             Some((Link(vec![], "example.com".to_owned(), None), 15))
         );
 
         assert_eq!(
-            parse_link("[an example]() test"),
+            parse_link("[an example]() test", &ParseOptions::default()),
             Some((
                 Link(vec![Text("an example".to_owned())], "".to_owned(), None),
                 14
@@ -130,16 +184,16 @@ mod test {
         );
 
         assert_eq!(
-            parse_link("[]() test"),
+            parse_link("[]() test", &ParseOptions::default()),
             Some((Link(vec![], "".to_owned(), None), 4))
         );
 
         assert_eq!(
-            parse_link("[()] test"),
+            parse_link("[()] test", &ParseOptions::default()),
             Some((
                 RefLink(
                     vec![Text("()".to_owned())],
-                    "".to_owned(),
+                    "()".to_owned(),
                     "[()]".to_owned()
                 ),
                 4
@@ -147,7 +201,10 @@ mod test {
         );
 
         assert_eq!(
-            parse_link("[an example](example.com \"Title\") test"),
+            parse_link(
+                "[an example](example.com \"Title\") test",
+                &ParseOptions::default()
+            ),
             Some((
                 Link(
                     vec![Text("an example".to_owned())],
@@ -159,7 +216,10 @@ mod test {
         );
 
         assert_eq!(
-            parse_link("[an example](example.com) test [a link](example.com)"),
+            parse_link(
+                "[an example](example.com) test [a link](example.com)",
+                &ParseOptions::default()
+            ),
             Some((
                 Link(
                     vec![Text("an example".to_owned())],
@@ -174,10 +234,13 @@ mod test {
     #[test]
     fn brackets_in_link() {
         assert_eq!(
-            parse_link("[![test](abc)](example.com) test [a link](example.com)"),
+            parse_link(
+                "[![test](abc)](example.com) test [a link](example.com)",
+                &ParseOptions::default()
+            ),
             Some((
                 Link(
-                    vec![Image("test".to_owned(), "abc".to_owned(), None)],
+                    vec![Image("test".to_owned(), "abc".to_owned(), None, None)],
                     "example.com".to_owned(),
                     None
                 ),
@@ -186,7 +249,7 @@ mod test {
         );
 
         assert_eq!(
-            parse_link("[huh[]wow](example.com)"),
+            parse_link("[huh[]wow](example.com)", &ParseOptions::default()),
             Some((
                 Link(
                     vec![
@@ -202,7 +265,7 @@ mod test {
         );
 
         assert_eq!(
-            parse_link("[huh\\[wow](example.com)"),
+            parse_link("[huh\\[wow](example.com)", &ParseOptions::default()),
             Some((
                 Link(
                     vec![Text("huh".to_owned()), Literal('['), Text("wow".to_owned())],
@@ -213,10 +276,16 @@ mod test {
             ))
         );
 
-        assert_eq!(parse_link("[huh[wow](example.com)"), None);
+        assert_eq!(
+            parse_link("[huh[wow](example.com)", &ParseOptions::default()),
+            None
+        );
 
         assert_eq!(
-            parse_link("[an example](example.com \"Title (huh!)\") test"),
+            parse_link(
+                "[an example](example.com \"Title (huh!)\") test",
+                &ParseOptions::default()
+            ),
             Some((
                 Link(
                     vec![Text("an example".to_owned())],
@@ -228,10 +297,91 @@ mod test {
         );
     }
 
+    #[test]
+    fn spans_in_link() {
+        assert_eq!(
+            parse_link("[**bold link**](example.com)", &ParseOptions::default()),
+            Some((
+                Link(
+                    vec![Strong(vec![Text("bold link".to_owned())])],
+                    "example.com".to_owned(),
+                    None
+                ),
+                28
+            ))
+        );
+
+        assert_eq!(
+            parse_link(
+                "[an *emphasized* link](example.com)",
+                &ParseOptions::default()
+            ),
+            Some((
+                Link(
+                    vec![
+                        Text("an ".to_owned()),
+                        Emphasis(vec![Text("emphasized".to_owned())]),
+                        Text(" link".to_owned())
+                    ],
+                    "example.com".to_owned(),
+                    None
+                ),
+                35
+            ))
+        );
+
+        assert_eq!(
+            parse_link("[`code` link](example.com)", &ParseOptions::default()),
+            Some((
+                Link(
+                    vec![Code("code".to_owned()), Text(" link".to_owned())],
+                    "example.com".to_owned(),
+                    None
+                ),
+                26
+            ))
+        );
+
+        assert_eq!(
+            parse_link("[`[a]` and `b]`](example.com)", &ParseOptions::default()),
+            Some((
+                Link(
+                    vec![
+                        Code("[a]".to_owned()),
+                        Text(" and ".to_owned()),
+                        Code("b]".to_owned())
+                    ],
+                    "example.com".to_owned(),
+                    None
+                ),
+                29
+            ))
+        );
+
+        assert_eq!(
+            parse_link(
+                "[an ![image](abc) link](example.com)",
+                &ParseOptions::default()
+            ),
+            Some((
+                Link(
+                    vec![
+                        Text("an ".to_owned()),
+                        Image("image".to_owned(), "abc".to_owned(), None, None),
+                        Text(" link".to_owned())
+                    ],
+                    "example.com".to_owned(),
+                    None
+                ),
+                36
+            ))
+        );
+    }
+
     #[test]
     fn space_length() {
         assert_eq!(
-            parse_link("[an example]      [example]"),
+            parse_link("[an example]      [example]", &ParseOptions::default()),
             Some((
                 RefLink(
                     vec![Text("an example".to_owned())],
@@ -243,7 +393,10 @@ mod test {
         );
 
         assert_eq!(
-            parse_link("[an example](example.com           \"Title\") test"),
+            parse_link(
+                "[an example](example.com           \"Title\") test",
+                &ParseOptions::default()
+            ),
             Some((
                 Link(
                     vec![Text("an example".to_owned())],
@@ -255,14 +408,159 @@ mod test {
         );
     }
 
+    #[test]
+    fn finds_reference_forms() {
+        let ref_link = |reference: &str, raw: &str| {
+            Some((
+                RefLink(
+                    vec![Text("Foo Bar".to_owned())],
+                    reference.to_owned(),
+                    raw.to_owned(),
+                ),
+                raw.len(),
+            ))
+        };
+        let options = ParseOptions::default();
+
+        assert_eq!(
+            parse_link("[Foo Bar][Id] test", &options),
+            ref_link("id", "[Foo Bar][Id]")
+        );
+        assert_eq!(
+            parse_link("[Foo Bar][] test", &options),
+            ref_link("foo bar", "[Foo Bar][]")
+        );
+        assert_eq!(
+            parse_link("[Foo Bar] test", &options),
+            ref_link("foo bar", "[Foo Bar]")
+        );
+    }
+
+    #[test]
+    fn reference_does_not_consume_following_brackets() {
+        let options = ParseOptions::default();
+        assert_eq!(
+            parse_link("[a][b] and [c][d]", &options),
+            Some((
+                RefLink(
+                    vec![Text("a".to_owned())],
+                    "b".to_owned(),
+                    "[a][b]".to_owned()
+                ),
+                6
+            ))
+        );
+        assert_eq!(
+            parse_link("[a] and [b]", &options),
+            Some((
+                RefLink(vec![Text("a".to_owned())], "a".to_owned(), "[a]".to_owned()),
+                3
+            ))
+        );
+    }
+
     #[test]
     fn no_false_positives() {
-        assert_eq!(parse_link("()[] testing things test"), None);
-        assert_eq!(parse_link("[[][[]] testing things test"), None);
+        assert_eq!(
+            parse_link("()[] testing things test", &ParseOptions::default()),
+            None
+        );
+        assert_eq!(
+            parse_link("[[][[]] testing things test", &ParseOptions::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn finds_link_destinations_in_angle_brackets() {
+        assert_eq!(
+            parse_link("[x](<a b>) test", &ParseOptions::default()),
+            Some((Link(vec![Text("x".to_owned())], "a b".to_owned(), None), 10))
+        );
+        assert_eq!(
+            parse_link("[x](<a b> \"Title\")", &ParseOptions::default()),
+            Some((
+                Link(
+                    vec![Text("x".to_owned())],
+                    "a b".to_owned(),
+                    Some("Title".to_owned())
+                ),
+                18
+            ))
+        );
+    }
+
+    #[test]
+    fn no_spaces_in_bare_destinations() {
+        assert_eq!(
+            parse_link("[x](a b)", &ParseOptions::default()),
+            Some((
+                RefLink(vec![Text("x".to_owned())], "x".to_owned(), "[x]".to_owned()),
+                3
+            ))
+        );
     }
 
     #[test]
     fn no_early_matching() {
-        assert_eq!(parse_link("were [an example](example.com) test"), None);
+        assert_eq!(
+            parse_link(
+                "were [an example](example.com) test",
+                &ParseOptions::default()
+            ),
+            None
+        );
+    }
+
+    #[test]
+    fn finds_objects() {
+        assert_eq!(
+            parse_link(
+                "[a *video*](movie.mp4 \"Trailer\" =640x50%) test",
+                &ParseOptions::default()
+            ),
+            Some((
+                Object(
+                    vec![
+                        Text("a ".to_owned()),
+                        Emphasis(vec![Text("video".to_owned())])
+                    ],
+                    "movie.mp4".to_owned(),
+                    Some("Trailer".to_owned()),
+                    ObjectSize {
+                        width: Some(Dimension::Pixels(640)),
+                        height: Some(Dimension::Percent(50))
+                    }
+                ),
+                41
+            ))
+        );
+        assert_eq!(
+            parse_link("[map](map.svg){width=100%}", &ParseOptions::default()),
+            Some((
+                Object(
+                    vec![Text("map".to_owned())],
+                    "map.svg".to_owned(),
+                    None,
+                    ObjectSize {
+                        width: Some(Dimension::Percent(100)),
+                        height: None
+                    }
+                ),
+                26
+            ))
+        );
+        // without a size it's a regular link
+        assert_eq!(
+            parse_link("[a](movie.mp4 \"Trailer\")", &ParseOptions::default()),
+            Some((
+                Link(
+                    vec![Text("a".to_owned())],
+                    "movie.mp4".to_owned(),
+                    Some("Trailer".to_owned())
+                ),
+                24
+            ))
+        );
     }
 }
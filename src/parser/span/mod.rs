@@ -1,31 +1,82 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use alloc::vec::Vec;
+use parser::block::is_blank;
+use parser::ParseOptions;
 use parser::Span;
-use parser::Span::{Literal, Text};
+use parser::Span::{Break, Literal, Text};
 
+mod autolink;
 mod br;
 mod code;
+mod comment;
+mod delimiter;
+mod emoji;
 mod emphasis;
+mod footnote;
+mod highlight;
+mod html_tag;
 mod image;
+mod kbd;
 mod link;
+mod reference;
+mod strikethrough;
 mod strong;
+use self::autolink::parse_autolink;
 use self::br::parse_break;
 use self::code::parse_code;
+pub use self::comment::is_comment_text;
+use self::comment::parse_comment;
+use self::delimiter::can_open;
+pub use self::emoji::emoji_regex;
+use self::emoji::parse_emoji;
 use self::emphasis::parse_emphasis;
+use self::footnote::{parse_footnote_reference, parse_inline_footnote};
+use self::highlight::parse_highlight;
+use self::html_tag::parse_html_tag;
 use self::image::parse_image;
+use self::kbd::parse_kbd;
 use self::link::parse_link;
+use self::reference::parse_reference;
+use self::strikethrough::parse_strikethrough;
 use self::strong::parse_strong;
 
-pub fn parse_spans(text: &str) -> Vec<Span> {
+// the whitespace trimmed from text, other whitespace like no-break spaces is kept
+const WHITESPACE: [char; 4] = [' ', '\t', '\n', '\r'];
+
+pub fn parse_spans(text: &str, options: &ParseOptions) -> Vec<Span> {
     let mut tokens = vec![];
     let mut t = String::new();
     let mut i = 0;
     while i < text.len() {
-        match parse_span(&text[i..text.len()]) {
+        let prev = text[..i].chars().last();
+        // a run of `*` or `_` that can't open an emphasis is just text, e.g. one
+        // after a letter and before punctuation or, in strict mode, an underscore
+        // inside of a word
+        let delimiter = text[i..].chars().next().filter(|c| *c == '*' || *c == '_');
+        if let Some(delimiter) = delimiter.filter(|d| !can_open(prev, &text[i..], *d, options)) {
+            let run = text[i..].chars().take_while(|c| *c == delimiter).count();
+            t.push_str(&text[i..i + run]);
+            i += run;
+            continue;
+        }
+        let word_start = !prev.is_some_and(char::is_alphanumeric);
+        let span = parse_span(&text[i..text.len()], options).or_else(|| {
+            parse_autolink(&text[i..], options)
+                .or_else(|| parse_reference(&text[i..], options))
+                .filter(|_| word_start)
+        });
+        match span {
             Some((span, consumed_chars)) => {
+                // the spaces of a break aren't part of the text before it
+                if span == Break {
+                    t = t.trim_end_matches(WHITESPACE).to_owned();
+                }
                 if !t.is_empty() {
                     // if this text is on the very left
                     // trim the left whitespace
                     if tokens.is_empty() {
-                        t = t.trim_start().to_owned()
+                        t = t.trim_start_matches(WHITESPACE).to_owned()
                     }
                     tokens.push(Text(t));
                 }
@@ -38,6 +89,11 @@ pub fn parse_spans(text: &str) -> Vec<Span> {
                 while !text.is_char_boundary(e) {
                     e += 1;
                 }
+                // a run of tildes that isn't struck through is text as a whole,
+                // so that `~~a~` doesn't strike through `~a~`
+                if text[i..].starts_with('~') {
+                    e = i + text[i..].chars().take_while(|c| *c == '~').count();
+                }
 
                 t.push_str(&text[i..e]);
                 i += e - i;
@@ -48,75 +104,435 @@ pub fn parse_spans(text: &str) -> Vec<Span> {
         // if this text is on the very left
         // trim the left whitespace
         if tokens.is_empty() {
-            t = t.trim_start().to_owned();
+            t = t.trim_start_matches(WHITESPACE).to_owned();
         }
         // we're at the very end of this line,
         // trim trailing whitespace
-        t = t.trim_end().to_owned();
-        tokens.push(Text(t));
+        t = t.trim_end_matches(WHITESPACE).to_owned();
+        if !t.is_empty() {
+            tokens.push(Text(t));
+        }
     }
     tokens
 }
 
+// Returns how many of the following lines belong to an image or link that starts
+// on the first line, because its destination or title is wrapped, e.g.
+//
+// ![a long description](
+//   https://example.com/image.png "and its title")
+//
+// Every line ending within the parentheses is preceded or followed by
+// whitespace, so at most three lines follow, and none of them can be blank.
+pub fn wrapped_lines(lines: &[&str], options: &ParseOptions) -> usize {
+    let line = lines[0];
+    if !line.contains("](") {
+        return 0;
+    }
+    let mut joined = line.to_owned();
+    for next in lines[1..].iter().take(3) {
+        if is_blank(next) {
+            break;
+        }
+        joined.push('\n');
+        joined.push_str(next);
+    }
+
+    let mut i = 0;
+    while i < line.len() {
+        let rest = &joined[i..];
+        if rest.starts_with('[') || rest.starts_with("![") {
+            let wrapped = parse_image(rest).or_else(|| parse_link(rest, options));
+            if let Some((_, len)) = wrapped.filter(|(_, len)| i + len > line.len()) {
+                return joined[..i + len].matches('\n').count();
+            }
+        }
+        i += match parse_span(&line[i..], options) {
+            Some((_, len)) => len,
+            None => line[i..].chars().next().map_or(1, char::len_utf8),
+        };
+    }
+    0
+}
+
 fn parse_escape(text: &str) -> Option<(Span, usize)> {
     let mut chars = text.chars();
     if let Some('\\') = chars.next() {
         return match chars.next() {
             Some(x @ '\\') | Some(x @ '`') | Some(x @ '*') | Some(x @ '_') | Some(x @ '{')
             | Some(x @ '}') | Some(x @ '[') | Some(x @ ']') | Some(x @ '(') | Some(x @ ')')
-            | Some(x @ '#') | Some(x @ '+') | Some(x @ '-') | Some(x @ '.') | Some(x @ '!') => {
-                Some((Literal(x), 2))
-            }
+            | Some(x @ '#') | Some(x @ '+') | Some(x @ '-') | Some(x @ '.') | Some(x @ '!')
+            | Some(x @ '|') | Some(x @ '=') => Some((Literal(x), 2)),
             _ => None,
         };
     }
     None
 }
 
-fn parse_span(text: &str) -> Option<(Span, usize)> {
-    pipe_opt!(
-    text
-    => parse_escape
-    => parse_code
-    => parse_strong
-    => parse_emphasis
-    => parse_break
-    => parse_image
-    => parse_link
-    )
+fn parse_custom_span(text: &str, options: &ParseOptions) -> Option<(Span, usize)> {
+    let hook = options.span_hook.as_ref()?;
+    hook.call(text)
+}
+
+fn parse_span(text: &str, options: &ParseOptions) -> Option<(Span, usize)> {
+    parse_custom_span(text, options)
+        .or_else(|| parse_escape(text))
+        .or_else(|| parse_comment(text))
+        .or_else(|| parse_html_tag(text))
+        .or_else(|| parse_code(text))
+        .or_else(|| parse_emoji(text, options))
+        .or_else(|| parse_strong(text, options))
+        .or_else(|| parse_emphasis(text, options))
+        .or_else(|| parse_highlight(text, options))
+        .or_else(|| parse_strikethrough(text, options))
+        .or_else(|| parse_break(text))
+        .or_else(|| parse_image(text))
+        .or_else(|| parse_footnote_reference(text))
+        .or_else(|| parse_inline_footnote(text, options))
+        .or_else(|| parse_kbd(text, options))
+        .or_else(|| parse_link(text, options))
 }
 
 #[cfg(test)]
 mod test {
     use parser::span::parse_spans;
-    use parser::Span::{Break, Code, Emphasis, Image, Link, Literal, Strong, Text};
+    use parser::Span::{
+        Break, Code, Emphasis, Highlight, Image, Kbd, Link, Literal, Strikethrough, Strong, Text,
+    };
+    use parser::{ParseMode, ParseOptions};
     use std::str;
 
+    #[test]
+    fn intraword_underscores() {
+        assert_eq!(
+            parse_spans("snake_case_name", &ParseOptions::default()),
+            vec![
+                Text("snake".to_owned()),
+                Emphasis(vec![Text("case".to_owned())]),
+                Text("name".to_owned())
+            ]
+        );
+        let strict = ParseOptions::new().mode(ParseMode::Strict).build();
+        assert_eq!(
+            parse_spans("snake_case_name", &strict),
+            vec![Text("snake_case_name".to_owned())]
+        );
+        assert_eq!(
+            parse_spans("an _emphasized_ word", &strict),
+            vec![
+                Text("an ".to_owned()),
+                Emphasis(vec![Text("emphasized".to_owned())]),
+                Text(" word".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn adjacent_emphasis_runs() {
+        let options = ParseOptions::default();
+        assert_eq!(
+            parse_spans("**a** **b**", &options),
+            vec![
+                Strong(vec![Text("a".to_owned())]),
+                Text(" ".to_owned()),
+                Strong(vec![Text("b".to_owned())])
+            ]
+        );
+        assert_eq!(
+            parse_spans("*a* *b*", &options),
+            vec![
+                Emphasis(vec![Text("a".to_owned())]),
+                Text(" ".to_owned()),
+                Emphasis(vec![Text("b".to_owned())])
+            ]
+        );
+        assert_eq!(
+            parse_spans("**a**b**c**", &options),
+            vec![
+                Strong(vec![Text("a".to_owned())]),
+                Text("b".to_owned()),
+                Strong(vec![Text("c".to_owned())])
+            ]
+        );
+    }
+
+    #[test]
+    fn emphasis_next_to_punctuation() {
+        let options = ParseOptions::default();
+        assert_eq!(
+            parse_spans("**(foo)**", &options),
+            vec![Strong(vec![Text("(foo)".to_owned())])]
+        );
+        assert_eq!(
+            parse_spans("(_foo_)", &options),
+            vec![
+                Text("(".to_owned()),
+                Emphasis(vec![Text("foo".to_owned())]),
+                Text(")".to_owned())
+            ]
+        );
+        assert_eq!(
+            parse_spans("\"*quoted*\"", &options),
+            vec![
+                Text("\"".to_owned()),
+                Emphasis(vec![Text("quoted".to_owned())]),
+                Text("\"".to_owned())
+            ]
+        );
+        assert_eq!(
+            parse_spans("*foo*, bar", &options),
+            vec![
+                Emphasis(vec![Text("foo".to_owned())]),
+                Text(", bar".to_owned())
+            ]
+        );
+        assert_eq!(
+            parse_spans("*foo,* bar", &options),
+            vec![
+                Emphasis(vec![Text("foo,".to_owned())]),
+                Text(" bar".to_owned())
+            ]
+        );
+        // a run after a letter and before punctuation isn't left-flanking,
+        // one after punctuation and before a letter isn't right-flanking
+        assert_eq!(
+            parse_spans("a*\"foo\"*", &options),
+            vec![Text("a*\"foo\"*".to_owned())]
+        );
+        assert_eq!(
+            parse_spans("foo_(bar)_", &options),
+            vec![Text("foo_(bar)_".to_owned())]
+        );
+        assert_eq!(
+            parse_spans("**\"a\"**b", &options),
+            vec![Text("**\"a\"**b".to_owned())]
+        );
+    }
+
+    #[test]
+    fn long_emphasis_runs() {
+        let options = ParseOptions::default();
+        let text = |t: &str| Text(t.to_owned());
+        assert_eq!(
+            parse_spans("****foo****", &options),
+            vec![Strong(vec![Strong(vec![text("foo")])])]
+        );
+        assert_eq!(
+            parse_spans("_____foo_____", &options),
+            vec![Emphasis(vec![Strong(vec![Strong(vec![text("foo")])])])]
+        );
+        assert_eq!(
+            parse_spans("******foo******", &options),
+            vec![Strong(vec![Strong(vec![Strong(vec![text("foo")])])])]
+        );
+        assert_eq!(
+            parse_spans("foo******bar*********baz", &options),
+            vec![
+                text("foo"),
+                Strong(vec![Strong(vec![Strong(vec![text("bar")])])]),
+                text("***baz")
+            ]
+        );
+        assert_eq!(
+            parse_spans("****foo*", &options),
+            vec![text("***"), Emphasis(vec![text("foo")])]
+        );
+        assert_eq!(
+            parse_spans("*foo****", &options),
+            vec![Emphasis(vec![text("foo")]), text("***")]
+        );
+        assert_eq!(
+            parse_spans("*****foo**", &options),
+            vec![text("***"), Strong(vec![text("foo")])]
+        );
+        assert_eq!(
+            parse_spans("***foo** bar****", &options),
+            vec![
+                Emphasis(vec![Strong(vec![text("foo")]), text(" bar")]),
+                text("***")
+            ]
+        );
+    }
+
+    #[test]
+    fn interleaved_emphasis_runs() {
+        let options = ParseOptions::default();
+        assert_eq!(
+            parse_spans("*a**b**c*", &options),
+            vec![Emphasis(vec![
+                Text("a".to_owned()),
+                Strong(vec![Text("b".to_owned())]),
+                Text("c".to_owned())
+            ])]
+        );
+        assert_eq!(
+            parse_spans("**foo*bar*baz**", &options),
+            vec![Strong(vec![
+                Text("foo".to_owned()),
+                Emphasis(vec![Text("bar".to_owned())]),
+                Text("baz".to_owned())
+            ])]
+        );
+        assert_eq!(
+            parse_spans("*a *b* c*", &options),
+            vec![Emphasis(vec![
+                Text("a ".to_owned()),
+                Emphasis(vec![Text("b".to_owned())]),
+                Text(" c".to_owned())
+            ])]
+        );
+        assert_eq!(
+            parse_spans("_a __b__ c_", &options),
+            vec![Emphasis(vec![
+                Text("a ".to_owned()),
+                Strong(vec![Text("b".to_owned())]),
+                Text(" c".to_owned())
+            ])]
+        );
+        assert_eq!(
+            parse_spans("*foo**bar*", &options),
+            vec![Emphasis(vec![Text("foo**bar".to_owned())])]
+        );
+        assert_eq!(
+            parse_spans("***a***", &options),
+            vec![Emphasis(vec![Strong(vec![Text("a".to_owned())])])]
+        );
+    }
+
+    #[test]
+    fn expands_emoji_shortcodes() {
+        let options = ParseOptions::new().emoji(true).build();
+        assert_eq!(
+            parse_spans("Ship it :rocket: :unknown:", &options),
+            vec![
+                Text("Ship it ".to_owned()),
+                Text("🚀".to_owned()),
+                Text(" :unknown:".to_owned())
+            ]
+        );
+        assert_eq!(
+            parse_spans("Ship it :rocket:", &ParseOptions::default()),
+            vec![Text("Ship it :rocket:".to_owned())]
+        );
+    }
+
+    #[test]
+    fn finds_kbd() {
+        let options = ParseOptions::new().kbd(true).build();
+        assert_eq!(
+            parse_spans("Press [[Ctrl+C]] to copy", &options),
+            vec![
+                Text("Press ".to_owned()),
+                Kbd("Ctrl+C".to_owned()),
+                Text(" to copy".to_owned())
+            ]
+        );
+        assert_eq!(
+            parse_spans("Press [[Ctrl+C to copy", &options),
+            vec![Text("Press [[Ctrl+C to copy".to_owned())]
+        );
+    }
+
+    #[test]
+    fn links_issue_and_commit_references() {
+        let options = ParseOptions::new()
+            .issue_url("https://example.com/issues/{n}")
+            .commit_url("https://example.com/commit/{sha}")
+            .build();
+        assert_eq!(
+            parse_spans("Fixes #42 in a1b2c3d, not x#43 or #notanumber", &options),
+            vec![
+                Text("Fixes ".to_owned()),
+                Link(
+                    vec![Text("#42".to_owned())],
+                    "https://example.com/issues/42".to_owned(),
+                    None
+                ),
+                Text(" in ".to_owned()),
+                Link(
+                    vec![Text("a1b2c3d".to_owned())],
+                    "https://example.com/commit/a1b2c3d".to_owned(),
+                    None
+                ),
+                Text(", not x#43 or #notanumber".to_owned())
+            ]
+        );
+        assert_eq!(
+            parse_spans("[see #42](https://example.com)", &options),
+            vec![Link(
+                vec![Text("see #42".to_owned())],
+                "https://example.com".to_owned(),
+                None
+            )]
+        );
+        assert_eq!(
+            parse_spans("Fixes #42", &ParseOptions::default()),
+            vec![Text("Fixes #42".to_owned())]
+        );
+    }
+
     #[test]
     fn converts_into_text() {
         assert_eq!(
-            parse_spans("this is a test"),
+            parse_spans("this is a test", &ParseOptions::default()),
             vec![Text("this is a test".to_owned())]
         );
     }
 
     #[test]
     fn finds_escapes() {
-        assert_eq!(parse_spans(r"\*"), vec![Literal('*')]);
+        assert_eq!(
+            parse_spans(r"\*", &ParseOptions::default()),
+            vec![Literal('*')]
+        );
     }
 
     #[test]
     fn finds_breaks() {
         assert_eq!(
-            parse_spans("this is a test  "),
+            parse_spans("this is a test  ", &ParseOptions::default()),
             vec![Text("this is a test".to_owned()), Break]
         );
     }
 
+    #[test]
+    fn finds_highlight() {
+        assert_eq!(
+            parse_spans("a ==b== = c ==d", &ParseOptions::default()),
+            vec![
+                Text("a ".to_owned()),
+                Highlight(vec![Text("b".to_owned())]),
+                Text(" = c ==d".to_owned())
+            ]
+        );
+    }
+
+    #[test]
+    fn handles_trailing_spaces() {
+        let options = ParseOptions::default();
+        assert_eq!(parse_spans("one ", &options), vec![Text("one".to_owned())]);
+        assert_eq!(
+            parse_spans("two  ", &options),
+            vec![Text("two".to_owned()), Break]
+        );
+        assert_eq!(
+            parse_spans("three   ", &options),
+            vec![Text("three".to_owned()), Break]
+        );
+        assert_eq!(
+            parse_spans("`code  ` ", &options),
+            vec![Code("code  ".to_owned())]
+        );
+        assert_eq!(
+            parse_spans("`code  `  ", &options),
+            vec![Code("code  ".to_owned()), Break]
+        );
+    }
+
     #[test]
     fn finds_code() {
         assert_eq!(
-            parse_spans("this `is a` test"),
+            parse_spans("this `is a` test", &ParseOptions::default()),
             vec![
                 Text("this ".to_owned()),
                 Code("is a".to_owned()),
@@ -124,7 +540,7 @@ mod test {
             ]
         );
         assert_eq!(
-            parse_spans("this ``is a`` test"),
+            parse_spans("this ``is a`` test", &ParseOptions::default()),
             vec![
                 Text("this ".to_owned()),
                 Code("is a".to_owned()),
@@ -136,7 +552,7 @@ mod test {
     #[test]
     fn finds_emphasis() {
         assert_eq!(
-            parse_spans("this _is a_ test"),
+            parse_spans("this _is a_ test", &ParseOptions::default()),
             vec![
                 Text("this ".to_owned()),
                 Emphasis(vec![Text("is a".to_owned())]),
@@ -144,7 +560,7 @@ mod test {
             ]
         );
         assert_eq!(
-            parse_spans("this *is a* test"),
+            parse_spans("this *is a* test", &ParseOptions::default()),
             vec![
                 Text("this ".to_owned()),
                 Emphasis(vec![Text("is a".to_owned())]),
@@ -156,7 +572,7 @@ mod test {
     #[test]
     fn finds_strong() {
         assert_eq!(
-            parse_spans("this __is a__ test"),
+            parse_spans("this __is a__ test", &ParseOptions::default()),
             vec![
                 Text("this ".to_owned()),
                 Strong(vec![Text("is a".to_owned())]),
@@ -164,7 +580,7 @@ mod test {
             ]
         );
         assert_eq!(
-            parse_spans("this **is a** test"),
+            parse_spans("this **is a** test", &ParseOptions::default()),
             vec![
                 Text("this ".to_owned()),
                 Strong(vec![Text("is a".to_owned())]),
@@ -176,7 +592,10 @@ mod test {
     #[test]
     fn finds_link() {
         assert_eq!(
-            parse_spans("this is [an example](example.com) test"),
+            parse_spans(
+                "this is [an example](example.com) test",
+                &ParseOptions::default()
+            ),
             vec![
                 Text("this is ".to_owned()),
                 Link(
@@ -192,10 +611,18 @@ mod test {
     #[test]
     fn finds_image() {
         assert_eq!(
-            parse_spans("this is ![an example](example.com) test"),
+            parse_spans(
+                "this is ![an example](example.com) test",
+                &ParseOptions::default()
+            ),
             vec![
                 Text("this is ".to_owned()),
-                Image("an example".to_owned(), "example.com".to_owned(), None),
+                Image(
+                    "an example".to_owned(),
+                    "example.com".to_owned(),
+                    None,
+                    None
+                ),
                 Text(" test".to_owned())
             ]
         );
@@ -204,10 +631,10 @@ mod test {
     #[test]
     fn finds_everything() {
         assert_eq!(
-            parse_spans("some text ![an image](image.com) _emphasis_ __strong__ `teh codez` [a link](example.com)  "),
+            parse_spans("some text ![an image](image.com) _emphasis_ __strong__ `teh codez` [a link](example.com)  ", &ParseOptions::default()),
             vec![
             Text("some text ".to_owned()),
-            Image("an image".to_owned(), "image.com".to_owned(), None),
+            Image("an image".to_owned(), "image.com".to_owned(), None, None),
             Text(" ".to_owned()),
             Emphasis(vec![Text("emphasis".to_owned())]),
             Text(" ".to_owned()),
@@ -224,6 +651,63 @@ mod test {
     #[test]
     fn properly_consumes_multibyte_utf8() {
         let test_phrase = str::from_utf8(b"This shouldn\xE2\x80\x99t panic").unwrap();
-        let _ = parse_spans(&test_phrase);
+        let _ = parse_spans(test_phrase, &ParseOptions::default());
+    }
+
+    #[test]
+    fn uses_span_hook() {
+        let options = ParseOptions::new()
+            .span_hook(|text| {
+                let name: String = text
+                    .strip_prefix('@')?
+                    .chars()
+                    .take_while(|c| c.is_ascii_alphanumeric())
+                    .collect();
+                if name.is_empty() {
+                    return None;
+                }
+                let url = format!("https://example.com/{}", name);
+                Some((
+                    Link(vec![Text(format!("@{}", name))], url, None),
+                    1 + name.len(),
+                ))
+            })
+            .build();
+        assert_eq!(
+            parse_spans("Ask *@ann* or @ bob", &options),
+            vec![
+                Text("Ask ".to_owned()),
+                Emphasis(vec![Link(
+                    vec![Text("@ann".to_owned())],
+                    "https://example.com/ann".to_owned(),
+                    None
+                )]),
+                Text(" or @ bob".to_owned())
+            ]
+        );
+        assert_eq!(
+            parse_spans("Ask @ann", &ParseOptions::default()),
+            vec![Text("Ask @ann".to_owned())]
+        );
+
+        // an empty span would never end
+        let options = ParseOptions::new()
+            .span_hook(|_| Some((Text("x".to_owned()), 0)))
+            .build();
+        assert_eq!(parse_spans("a", &options), vec![Text("a".to_owned())]);
+    }
+
+    #[test]
+    fn tilde_runs_close_runs_of_the_same_length() {
+        let options = ParseOptions::new().single_tilde_strikethrough(true).build();
+        assert_eq!(parse_spans("~~x~", &options), vec![Text("~~x~".to_owned())]);
+        assert_eq!(
+            parse_spans("~x~~ and ~~y", &options),
+            vec![Text("~x~~ and ~~y".to_owned())]
+        );
+        assert_eq!(
+            parse_spans("~~x~ y~~", &options),
+            vec![Strikethrough(vec![Text("x~ y".to_owned())])]
+        );
     }
 }
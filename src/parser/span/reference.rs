@@ -0,0 +1,98 @@
+use alloc::borrow::ToOwned;
+use parser::ParseOptions;
+use parser::Span;
+use parser::Span::{Link, Text};
+use regex::Regex;
+
+// Turns `#42` into a link to the issue and a short or full commit hash like
+// `a1b2c3d` into a link to the commit, using the templates in the options.
+// The caller makes sure the reference doesn't start in the middle of a word.
+pub fn parse_reference(text: &str, options: &ParseOptions) -> Option<(Span, usize)> {
+    lazy_static! {
+        static ref ISSUE: Regex = Regex::new(r"^#(?P<n>[0-9]+)\b").unwrap();
+        static ref COMMIT: Regex = Regex::new(r"^(?P<sha>[0-9a-f]{7,40})\b").unwrap();
+    }
+
+    if let Some(ref template) = options.issue_url {
+        if let Some(caps) = ISSUE.captures(text) {
+            let url = template.replace("{n}", &caps["n"]);
+            return Some((
+                Link(vec![Text(caps[0].to_owned())], url, None),
+                caps[0].len(),
+            ));
+        }
+    }
+
+    if let Some(ref template) = options.commit_url {
+        let caps = COMMIT.captures(text)?;
+        let sha = &caps["sha"];
+        // plain numbers and words like `deadbeef` aren't taken for hashes
+        if !sha.contains(|c: char| c.is_ascii_digit())
+            || !sha.contains(|c: char| c.is_ascii_alphabetic())
+        {
+            return None;
+        }
+        let url = template.replace("{sha}", sha);
+        return Some((Link(vec![Text(sha.to_owned())], url, None), sha.len()));
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_reference;
+    use parser::ParseOptions;
+    use parser::Span::{Link, Text};
+
+    #[test]
+    fn finds_issues() {
+        let options = ParseOptions::new()
+            .issue_url("https://github.com/org/repo/issues/{n}")
+            .build();
+        assert_eq!(
+            parse_reference("#42, see", &options),
+            Some((
+                Link(
+                    vec![Text("#42".to_owned())],
+                    "https://github.com/org/repo/issues/42".to_owned(),
+                    None
+                ),
+                3
+            ))
+        );
+    }
+
+    #[test]
+    fn finds_commits() {
+        let options = ParseOptions::new()
+            .commit_url("https://github.com/org/repo/commit/{sha}")
+            .build();
+        assert_eq!(
+            parse_reference("a1b2c3d fixed it", &options),
+            Some((
+                Link(
+                    vec![Text("a1b2c3d".to_owned())],
+                    "https://github.com/org/repo/commit/a1b2c3d".to_owned(),
+                    None
+                ),
+                7
+            ))
+        );
+    }
+
+    #[test]
+    fn no_false_positives() {
+        let options = ParseOptions::new()
+            .issue_url("https://github.com/org/repo/issues/{n}")
+            .commit_url("https://github.com/org/repo/commit/{sha}")
+            .build();
+        assert_eq!(parse_reference("#notanumber", &options), None);
+        assert_eq!(parse_reference("#42abc", &options), None);
+        assert_eq!(parse_reference("a1b2c3", &options), None);
+        assert_eq!(parse_reference("a1b2c3dx", &options), None);
+        assert_eq!(parse_reference("deadbeef", &options), None);
+        assert_eq!(parse_reference("12345678", &options), None);
+        assert_eq!(parse_reference("#42", &ParseOptions::default()), None);
+        assert_eq!(parse_reference("a1b2c3d", &ParseOptions::default()), None);
+    }
+}
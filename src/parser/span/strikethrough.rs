@@ -0,0 +1,121 @@
+use parser::span::delimiter::flanking;
+use parser::span::parse_spans;
+use parser::ParseOptions;
+use parser::Span;
+use parser::Span::Strikethrough;
+
+// Like on GitHub, the opening and closing runs have to be equally long, so
+// `~~~a~~~` isn't struck through and `~~a~` isn't either. Runs of another
+// length in between are text, e.g. the `~` in `~~a~b~~`. A run of two tildes
+// always takes precedence, single ones only count with
+// `ParseOptions::single_tilde_strikethrough`.
+pub fn parse_strikethrough(text: &str, options: &ParseOptions) -> Option<(Span, usize)> {
+    let run = text.chars().take_while(|c| *c == '~').count();
+    if run != 2 && (run != 1 || !options.single_tilde_strikethrough) {
+        return None;
+    }
+    // the opening run has to be followed by something other than whitespace
+    let content = &text[run..];
+    if content.chars().next().is_none_or(char::is_whitespace) {
+        return None;
+    }
+
+    let mut prev = Some('~');
+    let mut i = 0;
+    while i < content.len() {
+        let rest = &content[i..];
+        let next = rest.chars().next().unwrap();
+        if next == '\\' {
+            // skip escaped characters
+            let escaped = rest.chars().nth(1).map_or(0, char::len_utf8);
+            prev = rest[1..].chars().next();
+            i += 1 + escaped;
+            continue;
+        } else if next == '`' {
+            // code spans bind tighter than strikethrough
+            let ticks = rest.chars().take_while(|c| *c == '`').count();
+            if let Some(end) = rest[ticks..].find(&"`".repeat(ticks)) {
+                i += ticks + end + ticks;
+                prev = Some('`');
+                continue;
+            }
+        } else if next == '~' {
+            let closing = rest.chars().take_while(|c| *c == '~').count();
+            let (_, right_flanking) = flanking(prev, rest[closing..].chars().next());
+            if closing == run && right_flanking {
+                return Some((
+                    Strikethrough(parse_spans(&content[..i], options)),
+                    run + i + run,
+                ));
+            }
+            prev = Some('~');
+            i += closing;
+            continue;
+        }
+        prev = Some(next);
+        i += next.len_utf8();
+    }
+    None
+}
+
+#[cfg(test)]
+mod test {
+    use super::parse_strikethrough;
+    use parser::ParseOptions;
+    use parser::Span::{Code, Emphasis, Strikethrough, Text};
+
+    #[test]
+    fn finds_strikethrough() {
+        assert_eq!(
+            parse_strikethrough("~~gone~~ here", &ParseOptions::default()),
+            Some((Strikethrough(vec![Text("gone".to_owned())]), 8))
+        );
+        assert_eq!(
+            parse_strikethrough("~~*gone*~~", &ParseOptions::default()),
+            Some((
+                Strikethrough(vec![Emphasis(vec![Text("gone".to_owned())])]),
+                10
+            ))
+        );
+        assert_eq!(
+            parse_strikethrough("~~~gone~~~", &ParseOptions::default()),
+            None
+        );
+        assert_eq!(
+            parse_strikethrough("~~gone~~~", &ParseOptions::default()),
+            None
+        );
+        assert_eq!(
+            parse_strikethrough("~~ gone~~", &ParseOptions::default()),
+            None
+        );
+    }
+
+    #[test]
+    fn finds_single_tilde_strikethrough() {
+        assert_eq!(
+            parse_strikethrough("~gone~", &ParseOptions::default()),
+            None
+        );
+
+        let options = ParseOptions::new().single_tilde_strikethrough(true).build();
+        assert_eq!(
+            parse_strikethrough("~gone~ here", &options),
+            Some((Strikethrough(vec![Text("gone".to_owned())]), 6))
+        );
+        assert_eq!(
+            parse_strikethrough("~~gone~~", &options),
+            Some((Strikethrough(vec![Text("gone".to_owned())]), 8))
+        );
+        assert_eq!(parse_strikethrough("~gone~~", &options), None);
+        assert_eq!(parse_strikethrough("~~gone~", &options), None);
+        assert_eq!(
+            parse_strikethrough("~~a~b~~", &options),
+            Some((Strikethrough(vec![Text("a~b".to_owned())]), 7))
+        );
+        assert_eq!(
+            parse_strikethrough("~~`a~~`~~", &options),
+            Some((Strikethrough(vec![Code("a~~".to_owned())]), 9))
+        );
+    }
+}
@@ -0,0 +1,156 @@
+use parser::{self, Block, ParseOptions};
+use regex::Regex;
+use std::io::{self, BufRead, BufReader, Read};
+
+/// Parses Markdown read from `reader`, see `parse_reader_with_options`
+pub fn parse_reader<R: Read>(reader: R) -> io::Result<Vec<Block>> {
+    parse_reader_with_options(reader, &ParseOptions::default())
+}
+
+/// Parses Markdown read from `reader` using the given parsing options
+///
+/// The input is read line by line and parsed in chunks of whole blocks, so
+/// apart from the resulting blocks only the chunk currently being read is
+/// kept in memory. The result is the same as parsing the whole input at once.
+/// Fails if reading fails or the input isn't valid UTF-8.
+pub fn parse_reader_with_options<R: Read>(
+    reader: R,
+    options: &ParseOptions,
+) -> io::Result<Vec<Block>> {
+    let mut reader = BufReader::new(reader);
+    let mut chunker = Chunker::default();
+    let mut blocks = vec![];
+    let mut chunk = String::new();
+    let mut line = String::new();
+    while reader.read_line(&mut line)? > 0 {
+        if chunker.starts_chunk(&line) && !chunk.is_empty() {
+            blocks.extend(parser::parse_with_options(&chunk, options));
+            chunk.clear();
+        }
+        chunk.push_str(&line);
+        line.clear();
+    }
+    blocks.extend(parser::parse_with_options(&chunk, options));
+    Ok(blocks)
+}
+
+// Finds the lines a new chunk can start on without changing how the input is
+// parsed. That is an unindented line of text after a blank line, outside of
+// fenced code, comments, details and divs, which doesn't continue a list or a
+// blockquote. Anything it's unsure about stays in the current chunk.
+#[derive(Default)]
+struct Chunker {
+    fence: Option<String>,
+    comment: bool,
+    details: usize,
+    divs: usize,
+    after_blank: bool,
+}
+
+impl Chunker {
+    fn starts_chunk(&mut self, line: &str) -> bool {
+        lazy_static! {
+            static ref FENCE: Regex =
+                Regex::new(r"^ {0,3}(?P<fence>`{3,}|~{3,})(?P<info>.*)$").unwrap();
+            static ref COMMENT_BEGIN: Regex = Regex::new(r"^ {0,3}<!--").unwrap();
+            static ref DETAILS_BEGIN: Regex = Regex::new(r"^ {0,3}<details>").unwrap();
+            static ref DETAILS_END: Regex = Regex::new(r"^ {0,3}</details>").unwrap();
+            static ref DIV_BEGIN: Regex = Regex::new(r"^ {0,3}:{3,}[ \t]*[^\s:]").unwrap();
+            static ref DIV_END: Regex = Regex::new(r"^ {0,3}:{3,}[ \t]*$").unwrap();
+            static ref TEXT: Regex = Regex::new(r"^[\p{L}\p{N}#]").unwrap();
+            static ref LIST_ITEM: Regex = Regex::new(r"^[0-9A-Za-z]+[.)](?:\s|$)").unwrap();
+        }
+
+        let line = line.trim_end_matches(['\n', '\r']);
+        let starts_chunk = self.after_blank
+            && self.fence.is_none()
+            && !self.comment
+            && self.details == 0
+            && self.divs == 0
+            && TEXT.is_match(line)
+            && !LIST_ITEM.is_match(line);
+
+        if let Some(ref fence) = self.fence {
+            let fence_line = line.trim_end().trim_start_matches(' ');
+            if fence_line.len() >= fence.len() && fence_line.chars().all(|c| fence.starts_with(c)) {
+                self.fence = None;
+            }
+        } else if self.comment {
+            self.comment = !line.contains("-->");
+        } else if let Some(caps) = FENCE.captures(line) {
+            if !caps["fence"].starts_with('`') || !caps["info"].contains('`') {
+                self.fence = Some(caps["fence"].to_owned());
+            }
+        } else if COMMENT_BEGIN.is_match(line) {
+            self.comment = !line[line.find("<!--").unwrap() + 4..].contains("-->");
+        } else if DETAILS_BEGIN.is_match(line) {
+            self.details += 1;
+        } else if DETAILS_END.is_match(line) {
+            self.details = self.details.saturating_sub(1);
+        } else if DIV_BEGIN.is_match(line) {
+            self.divs += 1;
+        } else if DIV_END.is_match(line) {
+            self.divs = self.divs.saturating_sub(1);
+        }
+        self.after_blank = line.chars().all(|c| c == ' ' || c == '\t');
+
+        starts_chunk
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{parse_reader, parse_reader_with_options, Chunker};
+    use parser::{parse, parse_with_options, ParseOptions};
+    use std::io::Cursor;
+
+    const DOCUMENT: &str = "# Title\n\nSome *text*\nwrapped.\n\n\
+                            - a list\n\n- that is loose\n\n\
+                            1. ordered\n\n2. items\n\n\
+                            > a quote\n\n> continued\n\n\
+                            ```rust\nfn main() {}\n\nText in code\n```\n\n\
+                            <!-- a\n\nComment -->\n\n\
+                            <details>\n<summary>More</summary>\n\nHidden\n</details>\n\n\
+                            ::: note\n\nIn a div\n\n:::\n\n\
+                            [link]: http://example.com\n\n\
+                            A [link] and a footnote[^1].\n\n\
+                            [^1]: The note\n\n    continued\n\n\
+                            Setext\n------\n";
+
+    #[test]
+    fn parses_from_reader() {
+        assert_eq!(
+            parse_reader(Cursor::new(DOCUMENT)).unwrap(),
+            parse(DOCUMENT)
+        );
+        assert_eq!(
+            parse_reader(Cursor::new("Windows\r\n\r\nlines\r\n")).unwrap(),
+            parse("Windows\r\n\r\nlines\r\n")
+        );
+        assert_eq!(parse_reader(Cursor::new("")).unwrap(), vec![]);
+
+        let options = ParseOptions::new().lead_paragraphs(true).build();
+        assert_eq!(
+            parse_reader_with_options(Cursor::new("^^^ Lead\n\nText"), &options).unwrap(),
+            parse_with_options("^^^ Lead\n\nText", &options)
+        );
+    }
+
+    #[test]
+    fn fails_on_invalid_utf8() {
+        assert!(parse_reader(Cursor::new(b"a\xff")).is_err());
+    }
+
+    #[test]
+    fn chunks_at_block_boundaries() {
+        let mut chunker = Chunker::default();
+        let starts: Vec<&str> = DOCUMENT
+            .lines()
+            .filter(|line| chunker.starts_chunk(line))
+            .collect();
+        assert_eq!(
+            starts,
+            vec!["Some *text*", "A [link] and a footnote[^1].", "Setext"]
+        );
+    }
+}
@@ -0,0 +1,204 @@
+use alloc::borrow::ToOwned;
+use alloc::string::String;
+use parser::{Block, ListItem, Span};
+
+/// Renders blocks as an indented tree with one node per line
+///
+/// Unlike the derived `Debug` output, the tree is meant to be compared in
+/// snapshot tests, where a change in the parse only touches the lines of the
+/// nodes that changed. Children are indented by two spaces, strings are
+/// quoted and escaped like with `Debug`, e.g.
+///
+/// ```text
+/// Header 1
+///   Text "Title"
+/// UnorderedList Dash
+///   Simple
+///     Emphasis
+///       Text "item"
+/// ```
+pub fn debug_tree(blocks: &[Block]) -> String {
+    let mut out = String::new();
+    write_blocks(&mut out, blocks, 0);
+    out
+}
+
+fn write_line(out: &mut String, depth: usize, line: &str) {
+    for _ in 0..depth {
+        out.push_str("  ");
+    }
+    out.push_str(line);
+    out.push('\n');
+}
+
+fn write_blocks(out: &mut String, blocks: &[Block], depth: usize) {
+    for block in blocks {
+        write_block(out, block, depth);
+    }
+}
+
+fn write_block(out: &mut String, block: &Block, depth: usize) {
+    match *block {
+        Block::Header(ref content, level) => {
+            write_line(out, depth, &format!("Header {}", level));
+            write_spans(out, content, depth + 1);
+        }
+        Block::Paragraph(ref content) => {
+            write_line(out, depth, "Paragraph");
+            write_spans(out, content, depth + 1);
+        }
+        Block::LeadParagraph(ref content) => {
+            write_line(out, depth, "LeadParagraph");
+            write_spans(out, content, depth + 1);
+        }
+        Block::Blockquote(ref content) => {
+            write_line(out, depth, "Blockquote");
+            write_blocks(out, content, depth + 1);
+        }
+        Block::CodeBlock(ref info, ref code) => {
+            write_line(out, depth, &format!("CodeBlock {:?} {:?}", info, code));
+        }
+        Block::Comment(ref text) => write_line(out, depth, &format!("Comment {:?}", text)),
+        Block::Details(ref summary, ref content) => {
+            write_line(out, depth, "Details");
+            write_line(out, depth + 1, "Summary");
+            write_spans(out, summary, depth + 2);
+            write_blocks(out, content, depth + 1);
+        }
+        Block::Div(ref classes, ref content) => {
+            write_line(out, depth, &format!("Div {:?}", classes));
+            write_blocks(out, content, depth + 1);
+        }
+        Block::FootnoteDefinition(ref id, ref content) => {
+            write_line(out, depth, &format!("FootnoteDefinition {:?}", id));
+            write_blocks(out, content, depth + 1);
+        }
+        Block::LinkReference(ref id, ref url, ref title) => write_line(
+            out,
+            depth,
+            &format!("LinkReference {:?} {:?} {:?}", id, url, title),
+        ),
+        Block::OrderedList(ref items, ref list_type, _) => {
+            write_line(out, depth, &format!("OrderedList {:?}", list_type));
+            write_items(out, items, depth + 1);
+        }
+        Block::UnorderedList(ref items, list_type) => {
+            write_line(out, depth, &format!("UnorderedList {:?}", list_type));
+            write_items(out, items, depth + 1);
+        }
+        Block::Raw(ref html) => write_line(out, depth, &format!("Raw {:?}", html)),
+        Block::Hr => write_line(out, depth, "Hr"),
+        Block::LineBlock(ref lines) => {
+            write_line(out, depth, "LineBlock");
+            for line in lines {
+                write_line(out, depth + 1, "Line");
+                write_spans(out, line, depth + 2);
+            }
+        }
+        Block::Table(ref alignments, ref header, ref rows) => {
+            write_line(out, depth, &format!("Table {:?}", alignments));
+            for row in Some(header).into_iter().chain(rows) {
+                write_line(out, depth + 1, "Row");
+                for cell in row {
+                    write_line(out, depth + 2, "Cell");
+                    write_spans(out, cell, depth + 3);
+                }
+            }
+        }
+    }
+}
+
+fn write_items(out: &mut String, items: &[ListItem], depth: usize) {
+    for item in items {
+        match *item {
+            ListItem::Simple(ref content) => {
+                write_line(out, depth, "Simple");
+                write_spans(out, content, depth + 1);
+            }
+            ListItem::Paragraph(ref content) => {
+                write_line(out, depth, "Paragraph");
+                write_blocks(out, content, depth + 1);
+            }
+            ListItem::Task(state, ref content) => {
+                write_line(out, depth, &format!("Task {:?}", state));
+                write_spans(out, content, depth + 1);
+            }
+        }
+    }
+}
+
+fn write_spans(out: &mut String, spans: &[Span], depth: usize) {
+    for span in spans {
+        let (line, content) = match *span {
+            Span::Break => ("Break".to_owned(), None),
+            Span::Text(ref text) => (format!("Text {:?}", text), None),
+            Span::Code(ref code) => (format!("Code {:?}", code), None),
+            Span::Kbd(ref keys) => (format!("Kbd {:?}", keys), None),
+            Span::Comment(ref text) => (format!("Comment {:?}", text), None),
+            Span::Html(ref tag) => (format!("Html {:?}", tag), None),
+            Span::Literal(c) => (format!("Literal {:?}", c), None),
+            Span::Link(ref content, ref url, ref title) => {
+                (format!("Link {:?} {:?}", url, title), Some(content))
+            }
+            Span::Object(ref content, ref url, ref title, ref size) => (
+                format!("Object {:?} {:?} {:?}", url, title, size),
+                Some(content),
+            ),
+            Span::RefLink(ref content, ref reference, ref raw) => {
+                (format!("RefLink {:?} {:?}", reference, raw), Some(content))
+            }
+            Span::Image(ref alt, ref url, ref title, ref size) => (
+                format!("Image {:?} {:?} {:?} {:?}", alt, url, title, size),
+                None,
+            ),
+            Span::FootnoteReference(ref id) => (format!("FootnoteReference {:?}", id), None),
+            Span::InlineFootnote(ref content) => ("InlineFootnote".to_owned(), Some(content)),
+            Span::Emphasis(ref content) => ("Emphasis".to_owned(), Some(content)),
+            Span::Strong(ref content) => ("Strong".to_owned(), Some(content)),
+            Span::Highlight(ref content) => ("Highlight".to_owned(), Some(content)),
+            Span::Strikethrough(ref content) => ("Strikethrough".to_owned(), Some(content)),
+        };
+        write_line(out, depth, &line);
+        if let Some(content) = content {
+            write_spans(out, content, depth + 1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::debug_tree;
+    use parser::parse;
+
+    #[test]
+    fn renders_nested_document() {
+        let blocks = parse(
+            "# A *title*\n\n> - [link](url \"t\") and `code`\n> - [x] done\n\n| a |\n|---|\n| b |",
+        );
+        assert_eq!(
+            debug_tree(&blocks),
+            concat!(
+                "Header 1\n",
+                "  Text \"A \"\n",
+                "  Emphasis\n",
+                "    Text \"title\"\n",
+                "Blockquote\n",
+                "  UnorderedList Dash\n",
+                "    Simple\n",
+                "      Link \"url\" Some(\"t\")\n",
+                "        Text \"link\"\n",
+                "      Text \" and \"\n",
+                "      Code \"code\"\n",
+                "    Task Checked\n",
+                "      Text \"done\"\n",
+                "Table [None]\n",
+                "  Row\n",
+                "    Cell\n",
+                "      Text \"a\"\n",
+                "  Row\n",
+                "    Cell\n",
+                "      Text \"b\"\n",
+            )
+        );
+    }
+}
@@ -0,0 +1,161 @@
+use alloc::vec::Vec;
+use core::ops::Range;
+use core::slice;
+
+use extract::{walk_blocks, walk_spans};
+use parser::{self, Block, Feature, ListItem, ParseOptions, Span};
+
+/// A disallowed construct found by `validate`
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Violation {
+    /// The kind of construct
+    pub feature: Feature,
+    /// The byte range of the top-level block the construct appears in
+    ///
+    /// Use `utf16_range` to get the range in UTF-16 code units editors use.
+    pub range: Range<usize>,
+}
+
+/// Finds every construct of the `disallowed` kinds in a Markdown string
+///
+/// The string is parsed with the given options, which decide what is
+/// recognized as e.g. a table or a footnote in the first place.
+/// The violations are returned in document order, one for each occurrence.
+/// Since constructs nested in other blocks can't be traced back to their
+/// exact position, every violation points at the top-level block (a paragraph,
+/// a whole list, ...) it appears in.
+pub fn validate(md: &str, options: &ParseOptions, disallowed: &[Feature]) -> Vec<Violation> {
+    let mut violations = vec![];
+    if disallowed.is_empty() {
+        return violations;
+    }
+
+    for (block, range) in parse_with_ranges(md, options) {
+        let mut report = |feature: Option<Feature>| match feature {
+            Some(feature) if disallowed.contains(&feature) => violations.push(Violation {
+                feature,
+                range: range.clone(),
+            }),
+            _ => {}
+        };
+        let blocks = slice::from_ref(&block);
+        walk_blocks(blocks, &mut |block| {
+            report(block_feature(block));
+            if let Block::UnorderedList(ref items, _) | Block::OrderedList(ref items, _, _) = *block
+            {
+                for item in items {
+                    if let ListItem::Task(..) = *item {
+                        report(Some(Feature::TaskLists));
+                    }
+                }
+            }
+        });
+        walk_spans(blocks, &mut |span| report(span_feature(span)));
+    }
+    violations
+}
+
+// parses the top-level blocks along with the byte range each of them spans
+pub fn parse_with_ranges(md: &str, options: &ParseOptions) -> Vec<(Block, Range<usize>)> {
+    let lines: Vec<&str> = md.lines().collect();
+    let offset = |line: &str| line.as_ptr() as usize - md.as_ptr() as usize;
+    parser::parse_with_lines(md, options)
+        .into_iter()
+        .map(|(block, range)| {
+            let start = offset(lines[range.start]);
+            let last = lines[range.end - 1];
+            (block, start..offset(last) + last.len())
+        })
+        .collect()
+}
+
+fn block_feature(block: &Block) -> Option<Feature> {
+    Some(match *block {
+        Block::Header(..) => Feature::Headers,
+        Block::Blockquote(_) => Feature::Blockquotes,
+        Block::CodeBlock(..) => Feature::CodeBlocks,
+        Block::Comment(_) | Block::Raw(_) => Feature::Html,
+        Block::Details(..) => Feature::Details,
+        Block::Div(..) => Feature::Divs,
+        Block::FootnoteDefinition(..) => Feature::Footnotes,
+        Block::LinkReference(..) => Feature::Links,
+        Block::OrderedList(..) | Block::UnorderedList(..) => Feature::Lists,
+        Block::Hr => Feature::HorizontalRules,
+        Block::LineBlock(_) => Feature::LineBlocks,
+        Block::Table(..) => Feature::Tables,
+        Block::Paragraph(_) | Block::LeadParagraph(_) => return None,
+    })
+}
+
+fn span_feature(span: &Span) -> Option<Feature> {
+    Some(match *span {
+        Span::Code(_) => Feature::InlineCode,
+        Span::Comment(_) | Span::Html(_) => Feature::Html,
+        Span::Link(..) | Span::RefLink(..) | Span::Object(..) => Feature::Links,
+        Span::Image(..) => Feature::Images,
+        Span::FootnoteReference(_) | Span::InlineFootnote(_) => Feature::Footnotes,
+        Span::Emphasis(_) | Span::Strong(_) => Feature::Emphasis,
+        Span::Highlight(_) => Feature::Highlights,
+        Span::Kbd(_) => Feature::Kbd,
+        Span::Strikethrough(_) => Feature::Strikethrough,
+        Span::Break | Span::Text(_) | Span::Literal(_) => return None,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::{validate, Violation};
+    use parser::{Feature, ParseOptions};
+
+    #[test]
+    fn flags_disallowed_images() {
+        let md = "A comment.\n\nLook at ![this](cat.png) and\n![that](dog.png)!\n";
+        assert_eq!(
+            validate(md, &ParseOptions::default(), &[Feature::Images]),
+            vec![
+                Violation {
+                    feature: Feature::Images,
+                    range: 12..58
+                },
+                Violation {
+                    feature: Feature::Images,
+                    range: 12..58
+                }
+            ]
+        );
+        assert_eq!(
+            &md[12..58],
+            "Look at ![this](cat.png) and\n![that](dog.png)!"
+        );
+    }
+
+    #[test]
+    fn flags_nested_constructs() {
+        let md = "# Title\n\n> - [link](/url)\n> - *text*\n";
+        assert_eq!(
+            validate(
+                md,
+                &ParseOptions::default(),
+                &[Feature::Links, Feature::Blockquotes]
+            ),
+            vec![
+                Violation {
+                    feature: Feature::Blockquotes,
+                    range: 9..36
+                },
+                Violation {
+                    feature: Feature::Links,
+                    range: 9..36
+                }
+            ]
+        );
+    }
+
+    #[test]
+    fn allows_everything_by_default() {
+        assert_eq!(
+            validate("![a](b.png) <b>c</b>", &ParseOptions::default(), &[]),
+            vec![]
+        );
+    }
+}
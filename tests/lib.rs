@@ -1,3 +1,6 @@
+// the fixtures are read with `file_to_html`, which needs file IO
+#![cfg(feature = "std")]
+
 extern crate difference;
 extern crate markdown;
 